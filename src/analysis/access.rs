@@ -0,0 +1,543 @@
+//! Access control of classes and members.
+
+use std::collections::HashMap;
+
+use crate::{
+    jvm::{
+        class,
+        class_loader::ClassPath,
+        field, method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Class, Method, Module,
+    },
+    macros::see_jvm_spec,
+};
+
+use super::{modules::ObservableModule, ResolutionContext, ResolutionError, UnknownClass};
+
+/// A class or a member accessed by a class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Member<'a> {
+    /// A class or an interface.
+    Class(&'a ClassRef),
+    /// A field, optionally accessed through a receiver of the given class.
+    Field {
+        /// The reference to the field.
+        field: &'a FieldRef,
+        /// The class of the receiver, if the field is accessed on an object.
+        receiver: Option<&'a ClassRef>,
+    },
+    /// A method, optionally invoked on a receiver of the given class.
+    Method {
+        /// The reference to the method.
+        method: &'a MethodRef,
+        /// The class of the receiver, if the method is invoked on an object.
+        receiver: Option<&'a ClassRef>,
+    },
+}
+
+impl<'a> Member<'a> {
+    /// Sets the class of the receiver through which a field or a method is accessed.
+    /// This has no effect on a class.
+    #[must_use]
+    pub fn with_receiver(self, receiver: &'a ClassRef) -> Self {
+        match self {
+            Self::Class(_) => self,
+            Self::Field { field, .. } => Self::Field {
+                field,
+                receiver: Some(receiver),
+            },
+            Self::Method { method, .. } => Self::Method {
+                method,
+                receiver: Some(receiver),
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a ClassRef> for Member<'a> {
+    fn from(class_ref: &'a ClassRef) -> Self {
+        Self::Class(class_ref)
+    }
+}
+
+impl<'a> From<&'a FieldRef> for Member<'a> {
+    fn from(field: &'a FieldRef) -> Self {
+        Self::Field {
+            field,
+            receiver: None,
+        }
+    }
+}
+
+impl<'a> From<&'a MethodRef> for Member<'a> {
+    fn from(method: &'a MethodRef) -> Self {
+        Self::Method {
+            method,
+            receiver: None,
+        }
+    }
+}
+
+/// The access flags of a field or a method.
+#[derive(Debug, Clone, Copy)]
+enum Access {
+    Public,
+    Protected,
+    Package,
+    Private,
+}
+
+impl ResolutionContext {
+    /// Checks whether `accessor` can access a class, a field or a method.
+    /// - A class is accessible if it is public and exported to the module of `accessor`, or it
+    ///   is in the same package as `accessor`.
+    /// - A member is accessible if the class it is referenced through is accessible, and
+    ///   the member is public, protected and declared in a superclass of `accessor`, in the same
+    ///   package as `accessor`, or private and declared in a nestmate of `accessor`.
+    /// - A protected instance member declared in another package is only accessible through a
+    ///   receiver of `accessor` or its subclasses, unless it is a constructor.
+    ///
+    /// The modules are taken from the `module-info` classes of the class path entries and the
+    /// ones added with [`ResolutionContext::add_modules`]; the classes in other packages are in
+    /// the unnamed module, which exports all its packages.
+    /// Whether the module of `accessor` reads the module of the accessed class is not checked.
+    #[doc = see_jvm_spec!(5, 4, 4)]
+    ///
+    /// # Errors
+    /// - [`ResolutionError::UnknownClass`] if a class needed to check the access is missing.
+    /// - The errors of resolving the referenced field or method (see
+    ///   [`ResolutionContext::resolve_field`] and [`ResolutionContext::resolve_method`]).
+    pub fn is_accessible<'m>(
+        &self,
+        accessor: &ClassRef,
+        member: impl Into<Member<'m>>,
+    ) -> Result<bool, ResolutionError> {
+        let (owner, declaring_class, access, is_static, is_constructor, receiver) = match member
+            .into()
+        {
+            Member::Class(class_ref) => return Ok(self.is_class_accessible(accessor, class_ref)?),
+            Member::Field { field, receiver } => {
+                let resolved = self.resolve_field(field)?;
+                let flags = resolved.access_flags;
+                let access = if flags.contains(field::AccessFlags::PUBLIC) {
+                    Access::Public
+                } else if flags.contains(field::AccessFlags::PROTECTED) {
+                    Access::Protected
+                } else if flags.contains(field::AccessFlags::PRIVATE) {
+                    Access::Private
+                } else {
+                    Access::Package
+                };
+                let is_static = flags.contains(field::AccessFlags::STATIC);
+                (
+                    &field.owner,
+                    &resolved.owner,
+                    access,
+                    is_static,
+                    false,
+                    receiver,
+                )
+            }
+            Member::Method { method, receiver } => {
+                let resolved = self.resolve_any_method(method)?;
+                let flags = resolved.access_flags;
+                let access = if flags.contains(method::AccessFlags::PUBLIC) {
+                    Access::Public
+                } else if flags.contains(method::AccessFlags::PROTECTED) {
+                    Access::Protected
+                } else if flags.contains(method::AccessFlags::PRIVATE) {
+                    Access::Private
+                } else {
+                    Access::Package
+                };
+                let is_static = flags.contains(method::AccessFlags::STATIC);
+                let is_constructor = resolved.is_constructor();
+                (
+                    &method.owner,
+                    &resolved.owner,
+                    access,
+                    is_static,
+                    is_constructor,
+                    receiver,
+                )
+            }
+        };
+        if !self.is_class_accessible(accessor, owner)? {
+            return Ok(false);
+        }
+        let same_package = accessor.package() == declaring_class.package();
+        let accessible = match access {
+            Access::Public => true,
+            Access::Package => same_package,
+            Access::Private => self.are_nestmates(accessor, declaring_class)?,
+            Access::Protected if same_package => true,
+            Access::Protected => {
+                let is_inherited = self.is_subclass(accessor, declaring_class)?;
+                let is_valid_receiver = match receiver {
+                    Some(receiver) if !is_static && !is_constructor => {
+                        receiver == accessor || self.is_subclass(receiver, accessor)?
+                    }
+                    _ => true,
+                };
+                is_inherited && is_valid_receiver
+            }
+        };
+        Ok(accessible)
+    }
+
+    /// Checks whether `accessor` can access a class.
+    fn is_class_accessible(
+        &self,
+        accessor: &ClassRef,
+        class_ref: &ClassRef,
+    ) -> Result<bool, UnknownClass> {
+        if accessor == class_ref || accessor.package() == class_ref.package() {
+            return Ok(true);
+        }
+        let class = self
+            .class(class_ref)
+            .ok_or_else(|| UnknownClass(class_ref.clone()))?;
+        if !class.access_flags.contains(class::AccessFlags::PUBLIC) {
+            return Ok(false);
+        }
+        let Some(module) = self.module_of(class_ref) else {
+            return Ok(true);
+        };
+        let accessor_module = self.module_of(accessor);
+        if accessor_module.is_some_and(|it| it.name == module.name) {
+            return Ok(true);
+        }
+        let is_exported = module.exports.iter().any(|export| {
+            export.package.binary_name == class_ref.package()
+                && (export.to.is_empty()
+                    || accessor_module.is_some_and(|accessor_module| {
+                        export.to.iter().any(|it| it.name == accessor_module.name)
+                    }))
+        });
+        Ok(is_exported)
+    }
+
+    /// Checks whether two classes belong to the same nest.
+    #[doc = see_jvm_spec!(5, 4, 4)]
+    fn are_nestmates(&self, lhs: &ClassRef, rhs: &ClassRef) -> Result<bool, UnknownClass> {
+        if lhs == rhs {
+            return Ok(true);
+        }
+        Ok(self.nest_host(lhs)? == self.nest_host(rhs)?)
+    }
+
+    /// Returns the nest host of a class, which is the class itself if the host does not list it
+    /// as a member.
+    fn nest_host<'c>(&'c self, class_ref: &'c ClassRef) -> Result<&'c ClassRef, UnknownClass> {
+        let class = self.known_class(class_ref)?;
+        let Some(host_ref) = &class.nest_host else {
+            return Ok(class_ref);
+        };
+        let host: &Class = self.known_class(host_ref)?;
+        if host.nest_members.contains(class_ref) {
+            Ok(host_ref)
+        } else {
+            Ok(class_ref)
+        }
+    }
+
+    /// Resolves a method reference as an interface method reference if its owner is an
+    /// interface, or as a method reference otherwise.
+    pub(super) fn resolve_any_method(
+        &self,
+        method_ref: &MethodRef,
+    ) -> Result<&Method, ResolutionError> {
+        if self.known_class(&method_ref.owner)?.is_interface() {
+            self.resolve_interface_method(method_ref)
+        } else {
+            self.resolve_method(method_ref)
+        }
+    }
+
+    /// Adds modules to the ones declared by the class path entries, e.g., the modules in a
+    /// run-time image read by `ObservableModule::from_jimage`, which holds more than one module
+    /// in a single class path entry.
+    /// A package already contained in a known module stays in that module.
+    /// Automatic modules are ignored as they export all their packages.
+    pub fn add_modules(&mut self, modules: impl IntoIterator<Item = ObservableModule>) {
+        for module in modules {
+            self.modules.add(module);
+        }
+    }
+
+    /// Finds the explicit module containing a class.
+    fn module_of(&self, class_ref: &ClassRef) -> Option<&Module> {
+        self.modules.module_of(class_ref.package())
+    }
+}
+
+/// The explicit modules containing the packages of the classes in a [`ResolutionContext`].
+#[derive(Debug, Default)]
+pub(super) struct ModuleIndex {
+    modules: Vec<Module>,
+    package_modules: HashMap<String, usize>,
+}
+
+impl ModuleIndex {
+    /// Indexes the modules declared by the `module-info` classes of the class path entries,
+    /// each given together with the packages of the classes in it.
+    /// The packages of a module are those packages, together with the ones listed in its
+    /// `ModulePackages` attribute.
+    pub(super) fn from_modular_entries<'a, P>(
+        entries: impl IntoIterator<Item = &'a (&'a P, Vec<String>)>,
+    ) -> Self
+    where
+        P: ClassPath + 'a,
+    {
+        let mut index = Self::default();
+        for (entry, packages) in entries {
+            let Ok(module_info) = entry.find_class(Module::INFO_CLASS_NAME) else {
+                continue;
+            };
+            if let Some(module) = ObservableModule::explicit(&module_info, packages.iter().cloned())
+            {
+                index.add(module);
+            }
+        }
+        index
+    }
+
+    fn add(&mut self, module: ObservableModule) {
+        let Some(descriptor) = module.descriptor else {
+            return;
+        };
+        let module_idx = self.modules.len();
+        self.modules.push(descriptor);
+        for package in module.packages {
+            self.package_modules.entry(package).or_insert(module_idx);
+        }
+    }
+
+    fn module_of(&self, package: &str) -> Option<&Module> {
+        self.package_modules
+            .get(package)
+            .map(|&idx| &self.modules[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{
+            class_path_with, class_with_supertypes, context_with, field_named, method_ref,
+            module_info, module_info_named, with_methods,
+        },
+        types::field_type::{FieldType, PrimitiveType},
+    };
+
+    use super::*;
+
+    const OBJECT: &str = "java/lang/Object";
+
+    fn context() -> ResolutionContext {
+        let mut base = with_methods(
+            class_with_supertypes("org/mokapot/Base", Some(OBJECT), &[]),
+            &[
+                ("<init>", "()V", method::AccessFlags::PROTECTED),
+                ("m", "()V", method::AccessFlags::PROTECTED),
+                (
+                    "s",
+                    "()V",
+                    method::AccessFlags::PROTECTED | method::AccessFlags::STATIC,
+                ),
+            ],
+        );
+        base.fields = vec![
+            field_named(
+                "org/mokapot/Base",
+                "publicField",
+                field::AccessFlags::PUBLIC,
+            ),
+            field_named(
+                "org/mokapot/Base",
+                "packageField",
+                field::AccessFlags::empty(),
+            ),
+            field_named(
+                "org/mokapot/Base",
+                "privateField",
+                field::AccessFlags::PRIVATE,
+            ),
+        ];
+        let mut outer = class_with_supertypes("org/mokapot/Outer", Some(OBJECT), &[]);
+        outer.nest_members = vec![ClassRef::new("org/mokapot/Outer$Inner")];
+        outer.fields = vec![field_named(
+            "org/mokapot/Outer",
+            "secret",
+            field::AccessFlags::PRIVATE,
+        )];
+        let mut inner = class_with_supertypes("org/mokapot/Outer$Inner", Some(OBJECT), &[]);
+        inner.nest_host = Some(ClassRef::new("org/mokapot/Outer"));
+        let mut impostor = class_with_supertypes("org/mokapot/Impostor", Some(OBJECT), &[]);
+        impostor.nest_host = Some(ClassRef::new("org/mokapot/Outer"));
+        let mut hidden = class_with_supertypes("org/mokapot/Hidden", Some(OBJECT), &[]);
+        hidden.access_flags = class::AccessFlags::SUPER;
+        let mut ctx = context_with([
+            class_with_supertypes(OBJECT, None, &[]),
+            base,
+            class_with_supertypes("org/other/Sub", Some("org/mokapot/Base"), &[]),
+            class_with_supertypes("org/other/SubSub", Some("org/other/Sub"), &[]),
+            class_with_supertypes("org/other/Other", Some(OBJECT), &[]),
+            outer,
+            inner,
+            impostor,
+            hidden,
+            class_with_supertypes("org/lib/api/Api", Some(OBJECT), &[]),
+            class_with_supertypes("org/lib/internal/Impl", Some(OBJECT), &[]),
+            class_with_supertypes("org/friend/Friend", Some(OBJECT), &[]),
+        ]);
+        let lib = module_info(
+            "org.lib",
+            &["org/lib/api", "org/lib/internal"],
+            &[("org/lib/api", &[]), ("org/lib/internal", &["org.friend"])],
+        );
+        let friend = module_info("org.friend", &["org/friend"], &[]);
+        ctx.add_modules(
+            [lib, friend]
+                .iter()
+                .filter_map(|it| ObservableModule::explicit(it, [])),
+        );
+        ctx
+    }
+
+    fn field_ref(owner: &str, name: &str) -> FieldRef {
+        FieldRef {
+            owner: ClassRef::new(owner),
+            name: name.to_owned(),
+            field_type: FieldType::Base(PrimitiveType::Int),
+        }
+    }
+
+    #[test]
+    fn class_access() {
+        let ctx = context();
+        let accessible = |accessor, class| {
+            ctx.is_accessible(&ClassRef::new(accessor), &ClassRef::new(class))
+                .unwrap()
+        };
+        assert!(accessible("org/other/Other", "org/mokapot/Base"));
+        assert!(accessible("org/mokapot/Base", "org/mokapot/Hidden"));
+        assert!(!accessible("org/other/Other", "org/mokapot/Hidden"));
+    }
+
+    #[test]
+    fn module_exports() {
+        let ctx = context();
+        let accessible = |accessor, class| {
+            ctx.is_accessible(&ClassRef::new(accessor), &ClassRef::new(class))
+                .unwrap()
+        };
+        assert!(accessible("org/other/Other", "org/lib/api/Api"));
+        assert!(!accessible("org/other/Other", "org/lib/internal/Impl"));
+        assert!(accessible("org/friend/Friend", "org/lib/internal/Impl"));
+        assert!(accessible("org/lib/api/Api", "org/lib/internal/Impl"));
+    }
+
+    #[test]
+    fn modules_of_class_path_entries() {
+        let modular_jar = |module: &str, package: &str, exports: &[(&str, &[&str])]| {
+            let mut class_path = class_path_with(&[(&format!("{package}/Public"), OBJECT)]);
+            class_path.insert(Module::INFO_CLASS_NAME, module_info_named(module, exports));
+            class_path
+        };
+        let app = [modular_jar("org.app", "org/app", &[])];
+        let lib = [
+            modular_jar("org.a", "org/a", &[("org/a", &[])]),
+            modular_jar("org.b", "org/b", &[("org/b", &["org.app"])]),
+            modular_jar("org.c", "org/c", &[]),
+            class_path_with(&[("org/unnamed/Public", OBJECT)]),
+        ];
+        let ctx = ResolutionContext::new(&app, &lib);
+        let accessible = |accessor, class| {
+            ctx.is_accessible(&ClassRef::new(accessor), &ClassRef::new(class))
+                .unwrap()
+        };
+        assert!(accessible("org/app/Public", "org/a/Public"));
+        assert!(accessible("org/app/Public", "org/b/Public"));
+        assert!(!accessible("org/app/Public", "org/c/Public"));
+        assert!(!accessible("org/a/Public", "org/b/Public"));
+        assert!(!accessible("org/unnamed/Public", "org/c/Public"));
+        assert!(accessible("org/c/Public", "org/unnamed/Public"));
+    }
+
+    #[test]
+    fn field_access() {
+        let ctx = context();
+        let accessible = |accessor, owner, name| {
+            ctx.is_accessible(&ClassRef::new(accessor), &field_ref(owner, name))
+                .unwrap()
+        };
+        assert!(accessible(
+            "org/other/Other",
+            "org/mokapot/Base",
+            "publicField"
+        ));
+        assert!(accessible(
+            "org/other/Other",
+            "org/other/Sub",
+            "publicField"
+        ));
+        assert!(!accessible(
+            "org/other/Other",
+            "org/mokapot/Base",
+            "packageField"
+        ));
+        assert!(accessible(
+            "org/mokapot/Outer",
+            "org/mokapot/Base",
+            "packageField"
+        ));
+        assert!(!accessible(
+            "org/mokapot/Outer",
+            "org/mokapot/Base",
+            "privateField"
+        ));
+        assert!(accessible(
+            "org/mokapot/Base",
+            "org/mokapot/Base",
+            "privateField"
+        ));
+    }
+
+    #[test]
+    fn nestmate_access() {
+        let ctx = context();
+        let secret = field_ref("org/mokapot/Outer", "secret");
+        let accessible = |accessor| {
+            ctx.is_accessible(&ClassRef::new(accessor), &secret)
+                .unwrap()
+        };
+        assert!(accessible("org/mokapot/Outer$Inner"));
+        // The nest host does not list the class as a member.
+        assert!(!accessible("org/mokapot/Impostor"));
+    }
+
+    #[test]
+    fn protected_access() {
+        let ctx = context();
+        let sub = ClassRef::new("org/other/Sub");
+        let m = method_ref("org/mokapot/Base", "m", "()V");
+        let accessible =
+            |accessor: &ClassRef, member: Member<'_>| ctx.is_accessible(accessor, member).unwrap();
+        assert!(accessible(&sub, Member::from(&m)));
+        assert!(!accessible(
+            &ClassRef::new("org/other/Other"),
+            Member::from(&m)
+        ));
+        let sub_sub = ClassRef::new("org/other/SubSub");
+        assert!(accessible(&sub, Member::from(&m).with_receiver(&sub_sub)));
+        let base = ClassRef::new("org/mokapot/Base");
+        assert!(!accessible(&sub, Member::from(&m).with_receiver(&base)));
+        // Static methods and constructors are not checked against the receiver.
+        let s = method_ref("org/mokapot/Base", "s", "()V");
+        assert!(accessible(&sub, Member::from(&s).with_receiver(&base)));
+        let init = method_ref("org/mokapot/Base", "<init>", "()V");
+        assert!(accessible(&sub, Member::from(&init).with_receiver(&base)));
+    }
+}
@@ -0,0 +1,343 @@
+//! Detection of duplicate classes and split packages across a class path.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::jvm::{
+    class_loader::{ClassPath, Error},
+    references::{ClassRef, FieldRef, MethodRef},
+    Class, Module,
+};
+
+use super::ClassRefs;
+
+/// The conflicts found in a class path.
+#[derive(Debug, Default)]
+pub struct ClassPathConflicts {
+    /// The classes defined by more than one class path entry, sorted by binary name.
+    pub duplicates: Vec<DuplicateClass>,
+    /// The packages containing classes from more than one class path entry, sorted by name.
+    /// Such packages are not allowed in the Java Platform Module System.
+    pub split_packages: Vec<SplitPackage>,
+}
+
+/// A class defined by more than one class path entry.
+#[derive(Debug)]
+pub struct DuplicateClass {
+    /// The class.
+    pub class_ref: ClassRef,
+    /// The index of the class path entry that wins under [`ClassLoader::load_class`](crate::jvm::ClassLoader::load_class),
+    /// i.e., the first entry defining the class.
+    pub winner: usize,
+    /// The copies shadowed by the winning one, in class path order.
+    pub shadowed: Vec<ShadowedCopy>,
+}
+
+/// A copy of a class shadowed by another copy earlier in the class path.
+#[derive(Debug)]
+pub struct ShadowedCopy {
+    /// The index of the class path entry containing the copy.
+    pub entry: usize,
+    /// How the copy compares to the winning copy.
+    pub comparison: Comparison,
+}
+
+/// The result of comparing a shadowed copy of a class to the winning copy.
+#[derive(Debug)]
+pub enum Comparison {
+    /// The class files are byte-identical.
+    Identical,
+    /// The class files differ in bytes (e.g., due to a different constant pool layout or
+    /// debug information), but the classes are structurally the same.
+    /// This is also reported when the bytes of either copy are not available
+    /// (see [`ClassPath::find_resource`]).
+    Equivalent,
+    /// The classes are structurally different.
+    Different(Vec<Difference>),
+    /// Either copy could not be loaded.
+    Unloadable(Error),
+}
+
+/// A structural difference between two copies of a class.
+/// Members are reported from the perspective of the shadowed copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// The class file versions differ.
+    Version,
+    /// The access flags of the classes differ.
+    AccessFlags,
+    /// The superclasses differ.
+    SuperClass,
+    /// The implemented interfaces differ.
+    Interfaces,
+    /// A field is declared only in the shadowed copy.
+    ExtraField(FieldRef),
+    /// A field is declared only in the winning copy.
+    MissingField(FieldRef),
+    /// A field is declared in both copies but with different access flags or constant values.
+    ChangedField(FieldRef),
+    /// A method is declared only in the shadowed copy.
+    ExtraMethod(MethodRef),
+    /// A method is declared only in the winning copy.
+    MissingMethod(MethodRef),
+    /// A method is declared in both copies but with different access flags, exceptions, or
+    /// instructions.
+    ChangedMethod(MethodRef),
+}
+
+/// A package containing classes from more than one class path entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPackage {
+    /// The binary name of the package (e.g., `org/mokapot`).
+    pub package: String,
+    /// The indices of the class path entries containing classes in the package.
+    pub entries: BTreeSet<usize>,
+}
+
+impl ClassPathConflicts {
+    /// Detects the duplicate classes and split packages in a class path.
+    /// The entries are indexed by their positions in `class_path`.
+    /// The `module-info` classes of modular entries are not considered.
+    #[must_use]
+    pub fn detect<P>(class_path: &[P]) -> Self
+    where
+        P: ClassPath + ClassRefs,
+    {
+        let mut definitions: BTreeMap<ClassRef, Vec<usize>> = BTreeMap::new();
+        let mut packages: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+        for (idx, entry) in class_path.iter().enumerate() {
+            let class_refs = entry
+                .class_refs()
+                .into_iter()
+                .filter(|it| it.binary_name != Module::INFO_CLASS_NAME);
+            for class_ref in class_refs {
+                packages
+                    .entry(class_ref.package().to_owned())
+                    .or_default()
+                    .insert(idx);
+                definitions.entry(class_ref).or_default().push(idx);
+            }
+        }
+        let duplicates = definitions
+            .into_iter()
+            .filter(|(_, entries)| entries.len() > 1)
+            .map(|(class_ref, mut entries)| {
+                entries.sort_unstable();
+                let winner = entries[0];
+                let shadowed = entries[1..]
+                    .iter()
+                    .map(|&entry| ShadowedCopy {
+                        entry,
+                        comparison: compare(&class_path[winner], &class_path[entry], &class_ref),
+                    })
+                    .collect();
+                DuplicateClass {
+                    class_ref,
+                    winner,
+                    shadowed,
+                }
+            })
+            .collect();
+        let split_packages = packages
+            .into_iter()
+            .filter(|(_, entries)| entries.len() > 1)
+            .map(|(package, entries)| SplitPackage { package, entries })
+            .collect();
+        Self {
+            duplicates,
+            split_packages,
+        }
+    }
+}
+
+fn compare<P: ClassPath>(winner: &P, shadowed: &P, class_ref: &ClassRef) -> Comparison {
+    let resource_name = format!("{}.class", class_ref.binary_name);
+    if let (Ok(lhs), Ok(rhs)) = (
+        winner.find_resource(&resource_name),
+        shadowed.find_resource(&resource_name),
+    ) {
+        if lhs == rhs {
+            return Comparison::Identical;
+        }
+    }
+    let classes = winner
+        .find_class(&class_ref.binary_name)
+        .and_then(|lhs| Ok((lhs, shadowed.find_class(&class_ref.binary_name)?)));
+    match classes {
+        Ok((lhs, rhs)) => {
+            let differences = structural_differences(&lhs, &rhs);
+            if differences.is_empty() {
+                Comparison::Equivalent
+            } else {
+                Comparison::Different(differences)
+            }
+        }
+        Err(err) => Comparison::Unloadable(err),
+    }
+}
+
+fn structural_differences(winner: &Class, shadowed: &Class) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    if winner.version != shadowed.version {
+        differences.push(Difference::Version);
+    }
+    if winner.access_flags != shadowed.access_flags {
+        differences.push(Difference::AccessFlags);
+    }
+    if winner.super_class != shadowed.super_class {
+        differences.push(Difference::SuperClass);
+    }
+    let interfaces = |class: &Class| class.interfaces.iter().cloned().collect::<BTreeSet<_>>();
+    if interfaces(winner) != interfaces(shadowed) {
+        differences.push(Difference::Interfaces);
+    }
+
+    for field in &shadowed.fields {
+        match winner.get_field(&field.name, &field.field_type) {
+            None => differences.push(Difference::ExtraField(field.as_ref())),
+            Some(it)
+                if it.access_flags != field.access_flags
+                    || it.constant_value.as_ref().map(ToString::to_string)
+                        != field.constant_value.as_ref().map(ToString::to_string) =>
+            {
+                differences.push(Difference::ChangedField(field.as_ref()));
+            }
+            Some(_) => {}
+        }
+    }
+    for field in &winner.fields {
+        if shadowed.get_field(&field.name, &field.field_type).is_none() {
+            differences.push(Difference::MissingField(field.as_ref()));
+        }
+    }
+
+    for method in &shadowed.methods {
+        match winner.get_method(&method.name, &method.descriptor) {
+            None => differences.push(Difference::ExtraMethod(method.as_ref())),
+            Some(it)
+                if it.access_flags != method.access_flags
+                    || it.exceptions != method.exceptions
+                    || !same_instructions(it, method) =>
+            {
+                differences.push(Difference::ChangedMethod(method.as_ref()));
+            }
+            Some(_) => {}
+        }
+    }
+    for method in &winner.methods {
+        if shadowed
+            .get_method(&method.name, &method.descriptor)
+            .is_none()
+        {
+            differences.push(Difference::MissingMethod(method.as_ref()));
+        }
+    }
+    differences
+}
+
+fn same_instructions(lhs: &crate::jvm::Method, rhs: &crate::jvm::Method) -> bool {
+    match (&lhs.body, &rhs.body) {
+        (Some(lhs), Some(rhs)) => lhs.instructions.iter().eq(rhs.instructions.iter()),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{jvm::class_loader::class_paths::InMemoryClassPath, tests::class_path_with};
+
+    use super::*;
+
+    #[test]
+    fn detect_duplicates() {
+        let mut malformed = class_path_with(&[("org/other/Baz", "java/lang/Object")]);
+        malformed.insert("org/mokapot/Foo", vec![0xCA, 0xFE]);
+        let class_paths = [
+            class_path_with(&[("org/mokapot/Foo", "java/lang/Object")]),
+            class_path_with(&[("org/mokapot/Foo", "java/lang/Object")]),
+            class_path_with(&[
+                ("org/mokapot/Foo", "java/lang/Number"),
+                ("org/mokapot/Bar", "java/lang/Object"),
+            ]),
+            malformed,
+        ];
+        let conflicts = ClassPathConflicts::detect(&class_paths);
+        let [duplicate] = conflicts.duplicates.as_slice() else {
+            panic!("Expected exactly one duplicate class");
+        };
+        assert_eq!(duplicate.class_ref, ClassRef::new("org/mokapot/Foo"));
+        assert_eq!(duplicate.winner, 0);
+        let [identical, different, unloadable] = duplicate.shadowed.as_slice() else {
+            panic!("Expected three shadowed copies");
+        };
+        assert_eq!(identical.entry, 1);
+        assert!(matches!(identical.comparison, Comparison::Identical));
+        assert_eq!(different.entry, 2);
+        assert!(matches!(
+            &different.comparison,
+            Comparison::Different(diffs) if diffs == &[Difference::SuperClass]
+        ));
+        assert_eq!(unloadable.entry, 3);
+        assert!(matches!(
+            unloadable.comparison,
+            Comparison::Unloadable(Error::Malformed(_))
+        ));
+    }
+
+    /// A class path that does not provide the bytes of its classes.
+    struct OpaqueClassPath(InMemoryClassPath);
+
+    impl ClassPath for OpaqueClassPath {
+        fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+            self.0.find_class(binary_name)
+        }
+    }
+
+    impl ClassRefs for OpaqueClassPath {
+        fn class_refs(&self) -> std::collections::HashSet<ClassRef> {
+            self.0.class_refs()
+        }
+    }
+
+    #[test]
+    fn equivalent_without_bytes() {
+        let class_paths = [
+            OpaqueClassPath(class_path_with(&[("org/mokapot/Foo", "java/lang/Object")])),
+            OpaqueClassPath(class_path_with(&[("org/mokapot/Foo", "java/lang/Object")])),
+        ];
+        let conflicts = ClassPathConflicts::detect(&class_paths);
+        assert!(matches!(
+            conflicts.duplicates[0].shadowed[0].comparison,
+            Comparison::Equivalent
+        ));
+    }
+
+    #[test]
+    fn module_info_ignored() {
+        let class_paths = [
+            class_path_with(&[("module-info", "java/lang/Object")]),
+            class_path_with(&[("module-info", "java/lang/Object")]),
+        ];
+        let conflicts = ClassPathConflicts::detect(&class_paths);
+        assert!(conflicts.duplicates.is_empty());
+        assert!(conflicts.split_packages.is_empty());
+    }
+
+    #[test]
+    fn detect_split_packages() {
+        let class_paths = [
+            class_path_with(&[("org/mokapot/Foo", "java/lang/Object")]),
+            class_path_with(&[("org/other/Bar", "java/lang/Object")]),
+            class_path_with(&[("org/mokapot/Bar", "java/lang/Object")]),
+        ];
+        let conflicts = ClassPathConflicts::detect(&class_paths);
+        assert!(conflicts.duplicates.is_empty());
+        assert_eq!(
+            conflicts.split_packages,
+            [SplitPackage {
+                package: "org/mokapot".to_owned(),
+                entries: BTreeSet::from([0, 2]),
+            }]
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! Selection of the methods invoked by method invocation instructions.
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{
+    jvm::{
+        class,
+        method::AccessFlags,
+        references::{ClassRef, MethodRef},
+        Method,
+    },
+    macros::see_jvm_spec,
+};
+
+use super::{subtyping::JAVA_LANG_OBJECT, ResolutionContext, ResolutionError, UnknownClass};
+
+impl ResolutionContext {
+    /// Selects the method invoked by `invokevirtual` or `invokeinterface` on a receiver whose
+    /// runtime class is `receiver`, given the resolved method (see
+    /// [`ResolutionContext::resolve_method`] and [`ResolutionContext::resolve_interface_method`]).
+    /// - A private or static resolved method is selected as is.
+    /// - Otherwise, the first method in `receiver` and its superclasses that can override the
+    ///   resolved method is selected.
+    /// - Otherwise, the only non-abstract maximally specific superinterface method of `receiver`
+    ///   is selected, e.g., a default method.
+    #[doc = see_jvm_spec!(5, 4, 6)]
+    ///
+    /// # Errors
+    /// - [`ResolutionError::AbstractMethod`] if the selected method is abstract or no method is
+    ///   selected.
+    /// - [`ResolutionError::ConflictingDefaultMethods`] if more than one non-abstract maximally
+    ///   specific superinterface method is found.
+    /// - [`ResolutionError::UnknownClass`] if a class needed to select the method is missing.
+    pub fn select_method<'c>(
+        &'c self,
+        resolved: &'c Method,
+        receiver: &ClassRef,
+    ) -> Result<&'c Method, ResolutionError> {
+        if resolved
+            .access_flags
+            .intersects(AccessFlags::PRIVATE | AccessFlags::STATIC)
+        {
+            return Ok(resolved);
+        }
+        let mut visited = HashSet::new();
+        let mut current = Some(receiver);
+        while let Some(class_ref) = current {
+            if !visited.insert(class_ref) {
+                break;
+            }
+            let class = self.known_class(class_ref)?;
+            if let Some(method) = class
+                .get_method(&resolved.name, &resolved.descriptor)
+                .filter(|it| !it.access_flags.contains(AccessFlags::STATIC))
+            {
+                if self.can_override(method, resolved)? {
+                    return concrete(method, receiver);
+                }
+            }
+            current = class.super_class.as_ref();
+        }
+        let method_ref = resolved.as_ref();
+        self.select_default_method(receiver, &method_ref)
+    }
+
+    /// Selects the method invoked by `invokespecial` in `current_class` with the symbolic
+    /// reference `method_ref`.
+    /// If `method_ref` refers to a method of a superclass of `current_class` other than a
+    /// constructor, the lookup starts from the direct superclass of `current_class` as in a
+    /// `super.m()` call; otherwise, it starts from the owner of `method_ref`.
+    #[doc = see_jvm_spec!(6, 5)]
+    ///
+    /// # Errors
+    /// - The errors of [`ResolutionContext::resolve_method`] or
+    ///   [`ResolutionContext::resolve_interface_method`].
+    /// - [`ResolutionError::AbstractMethod`] if the selected method is abstract or no method is
+    ///   selected.
+    /// - [`ResolutionError::ConflictingDefaultMethods`] if more than one non-abstract maximally
+    ///   specific superinterface method is found.
+    pub fn select_special_method(
+        &self,
+        method_ref: &MethodRef,
+        current_class: &ClassRef,
+    ) -> Result<&Method, ResolutionError> {
+        let owner = self.known_class(&method_ref.owner)?;
+        let resolved = if owner.is_interface() {
+            self.resolve_interface_method(method_ref)?
+        } else {
+            self.resolve_method(method_ref)?
+        };
+        let current = self.known_class(current_class)?;
+        let start = match &current.super_class {
+            Some(super_class)
+                if !owner.is_interface()
+                    && !method_ref.is_constructor()
+                    && current.access_flags.contains(class::AccessFlags::SUPER)
+                    && self.is_subclass(current_class, &method_ref.owner)? =>
+            {
+                super_class.clone()
+            }
+            _ => method_ref.owner.clone(),
+        };
+        let mut visited = HashSet::new();
+        let mut current = Some(start.clone());
+        while let Some(class_ref) = current {
+            if !visited.insert(class_ref.clone()) {
+                break;
+            }
+            let class = self.known_class(&class_ref)?;
+            if let Some(method) = class
+                .get_method(&resolved.name, &resolved.descriptor)
+                .filter(|it| !it.access_flags.contains(AccessFlags::STATIC))
+            {
+                return concrete(method, &start);
+            }
+            current = if class.is_interface() {
+                None
+            } else {
+                class.super_class.clone()
+            };
+        }
+        if self.known_class(&start)?.is_interface() {
+            let object = self.known_class(&ClassRef::new(JAVA_LANG_OBJECT))?;
+            if let Some(method) = object
+                .get_method(&resolved.name, &resolved.descriptor)
+                .filter(|it| {
+                    it.access_flags.contains(AccessFlags::PUBLIC)
+                        && !it.access_flags.contains(AccessFlags::STATIC)
+                })
+            {
+                return concrete(method, &start);
+            }
+        }
+        self.select_default_method(&start, &resolved.as_ref())
+    }
+
+    /// Enumerates the methods possibly invoked by `invokevirtual` or `invokeinterface` with the
+    /// symbolic reference `method_ref` on a receiver whose static type is
+    /// `receiver_upper_bound`.
+    /// The methods are selected for `receiver_upper_bound` and all its subclasses, or all the
+    /// classes implementing it if it is an interface, that are neither abstract nor interfaces.
+    /// The receivers for which no method can be selected are skipped, since the invocation
+    /// fails on them.
+    /// The methods are sorted by their owners.
+    ///
+    /// # Errors
+    /// - The errors of [`ResolutionContext::resolve_method`] or
+    ///   [`ResolutionContext::resolve_interface_method`].
+    /// - [`ResolutionError::UnknownClass`] if a class needed to select a method is missing.
+    pub fn dispatch_targets(
+        &self,
+        method_ref: &MethodRef,
+        receiver_upper_bound: &ClassRef,
+    ) -> Result<Vec<&Method>, ResolutionError> {
+        let resolved = self.resolve_any_method(method_ref)?;
+        if resolved
+            .access_flags
+            .intersects(AccessFlags::PRIVATE | AccessFlags::STATIC)
+        {
+            return Ok(vec![resolved]);
+        }
+        let mut receivers = BTreeSet::from([receiver_upper_bound.clone()]);
+        receivers.extend(self.class_hierarchy.subclasses(receiver_upper_bound));
+        for implementor in self
+            .interface_implementations
+            .implementors(receiver_upper_bound)
+        {
+            receivers.extend(self.class_hierarchy.subclasses(&implementor));
+            receivers.insert(implementor);
+        }
+        let mut targets = Vec::new();
+        let mut seen = HashSet::new();
+        for receiver in receivers {
+            let Some(class) = self.class(&receiver) else {
+                continue;
+            };
+            if class
+                .access_flags
+                .intersects(class::AccessFlags::INTERFACE | class::AccessFlags::ABSTRACT)
+            {
+                continue;
+            }
+            match self.select_method(resolved, &receiver) {
+                Ok(method) => {
+                    if seen.insert(method.as_ref()) {
+                        targets.push(method);
+                    }
+                }
+                Err(
+                    ResolutionError::AbstractMethod(_)
+                    | ResolutionError::ConflictingDefaultMethods(_),
+                ) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        targets.sort_by(|lhs, rhs| lhs.owner.cmp(&rhs.owner));
+        Ok(targets)
+    }
+
+    /// Checks whether `overriding` can override `overridden`, where the class declaring
+    /// `overriding` is a subclass of the one declaring `overridden` or the same class.
+    #[doc = see_jvm_spec!(5, 4, 5)]
+    pub(super) fn can_override(
+        &self,
+        overriding: &Method,
+        overridden: &Method,
+    ) -> Result<bool, UnknownClass> {
+        if overriding.name != overridden.name
+            || overriding.descriptor != overridden.descriptor
+            || overriding.access_flags.contains(AccessFlags::PRIVATE)
+            || overridden.access_flags.contains(AccessFlags::PRIVATE)
+        {
+            return Ok(false);
+        }
+        if overridden
+            .access_flags
+            .intersects(AccessFlags::PUBLIC | AccessFlags::PROTECTED)
+            || overriding.owner.package() == overridden.owner.package()
+        {
+            return Ok(true);
+        }
+        // A package-private method can be overridden transitively through an intermediate
+        // method in the package that overrides it.
+        let mut visited = HashSet::new();
+        let mut current = self.known_class(&overriding.owner)?.super_class.as_ref();
+        while let Some(class_ref) = current {
+            if *class_ref == overridden.owner || !visited.insert(class_ref) {
+                break;
+            }
+            let class = self.known_class(class_ref)?;
+            if let Some(intermediate) = class.get_method(&overriding.name, &overriding.descriptor) {
+                let overrides_intermediate = intermediate.owner.package()
+                    == overriding.owner.package()
+                    || intermediate
+                        .access_flags
+                        .intersects(AccessFlags::PUBLIC | AccessFlags::PROTECTED);
+                if overrides_intermediate && self.can_override(intermediate, overridden)? {
+                    return Ok(true);
+                }
+            }
+            current = class.super_class.as_ref();
+        }
+        Ok(false)
+    }
+
+    /// Selects the only non-abstract maximally specific superinterface method of a class.
+    fn select_default_method(
+        &self,
+        class_ref: &ClassRef,
+        method_ref: &MethodRef,
+    ) -> Result<&Method, ResolutionError> {
+        let candidates = self.superinterface_methods(class_ref, method_ref)?;
+        let non_abstract: Vec<_> = self
+            .maximally_specific(&candidates)?
+            .into_iter()
+            .filter(|it| !it.access_flags.contains(AccessFlags::ABSTRACT))
+            .collect();
+        match non_abstract.as_slice() {
+            [method] => Ok(method),
+            [] => Err(ResolutionError::AbstractMethod(MethodRef {
+                owner: class_ref.clone(),
+                ..method_ref.clone()
+            })),
+            _ => Err(ResolutionError::ConflictingDefaultMethods(MethodRef {
+                owner: class_ref.clone(),
+                ..method_ref.clone()
+            })),
+        }
+    }
+}
+
+/// Returns the selected method unless it is abstract.
+fn concrete<'m>(method: &'m Method, receiver: &ClassRef) -> Result<&'m Method, ResolutionError> {
+    if method.access_flags.contains(AccessFlags::ABSTRACT) {
+        Err(ResolutionError::AbstractMethod(MethodRef {
+            owner: receiver.clone(),
+            ..method.as_ref()
+        }))
+    } else {
+        Ok(method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{
+        class_with_supertypes, context_with, interface_named, method_ref, with_methods,
+    };
+
+    use super::*;
+
+    const PUBLIC: AccessFlags = AccessFlags::PUBLIC;
+    const ABSTRACT: AccessFlags = AccessFlags::PUBLIC.union(AccessFlags::ABSTRACT);
+
+    fn context() -> ResolutionContext {
+        let mut base = with_methods(
+            class_with_supertypes(
+                "org/mokapot/Base",
+                Some(JAVA_LANG_OBJECT),
+                &["org/mokapot/Greeter"],
+            ),
+            &[
+                ("greet", "()V", PUBLIC),
+                ("pkg", "()V", AccessFlags::empty()),
+                ("secret", "()V", AccessFlags::PRIVATE),
+            ],
+        );
+        base.access_flags |= class::AccessFlags::ABSTRACT;
+        context_with([
+            with_methods(
+                class_with_supertypes(JAVA_LANG_OBJECT, None, &[]),
+                &[("toString", "()Ljava/lang/String;", PUBLIC)],
+            ),
+            with_methods(
+                interface_named("org/mokapot/Greeter", &[]),
+                &[("greet", "()V", ABSTRACT), ("hello", "()V", PUBLIC)],
+            ),
+            with_methods(
+                interface_named("org/mokapot/LoudGreeter", &["org/mokapot/Greeter"]),
+                &[("greet", "()V", PUBLIC)],
+            ),
+            with_methods(
+                interface_named("org/mokapot/Left", &[]),
+                &[("m", "()V", PUBLIC)],
+            ),
+            with_methods(
+                interface_named("org/mokapot/Right", &[]),
+                &[("m", "()V", PUBLIC)],
+            ),
+            base,
+            with_methods(
+                class_with_supertypes("org/mokapot/A", Some("org/mokapot/Base"), &[]),
+                &[("greet", "()V", PUBLIC)],
+            ),
+            class_with_supertypes(
+                "org/mokapot/B",
+                Some("org/mokapot/Base"),
+                &["org/mokapot/LoudGreeter"],
+            ),
+            with_methods(
+                class_with_supertypes("org/other/C", Some("org/mokapot/Base"), &[]),
+                &[("pkg", "()V", AccessFlags::empty())],
+            ),
+            class_with_supertypes(
+                "org/mokapot/Both",
+                Some(JAVA_LANG_OBJECT),
+                &["org/mokapot/Left", "org/mokapot/Right"],
+            ),
+        ])
+    }
+
+    fn owner(method: Result<&Method, ResolutionError>) -> Result<&str, ResolutionError> {
+        method.map(|it| it.owner.binary_name.as_str())
+    }
+
+    #[test]
+    fn select_virtual_methods() {
+        let ctx = context();
+        let greet = ctx
+            .resolve_method(&method_ref("org/mokapot/Base", "greet", "()V"))
+            .unwrap();
+        let select = |receiver| owner(ctx.select_method(greet, &ClassRef::new(receiver)));
+        assert_eq!(select("org/mokapot/A"), Ok("org/mokapot/A"));
+        // A method in a superclass takes precedence over default methods.
+        assert_eq!(select("org/mokapot/B"), Ok("org/mokapot/Base"));
+
+        let pkg = ctx
+            .resolve_method(&method_ref("org/mokapot/Base", "pkg", "()V"))
+            .unwrap();
+        let selected = ctx.select_method(pkg, &ClassRef::new("org/other/C"));
+        assert_eq!(owner(selected), Ok("org/mokapot/Base"));
+
+        let secret = ctx
+            .resolve_method(&method_ref("org/mokapot/Base", "secret", "()V"))
+            .unwrap();
+        let selected = ctx.select_method(secret, &ClassRef::new("org/mokapot/A"));
+        assert_eq!(owner(selected), Ok("org/mokapot/Base"));
+    }
+
+    #[test]
+    fn select_default_methods() {
+        let ctx = context();
+        let hello = ctx
+            .resolve_interface_method(&method_ref("org/mokapot/Greeter", "hello", "()V"))
+            .unwrap();
+        let selected = ctx.select_method(hello, &ClassRef::new("org/mokapot/A"));
+        assert_eq!(owner(selected), Ok("org/mokapot/Greeter"));
+
+        let m = ctx
+            .resolve_interface_method(&method_ref("org/mokapot/Left", "m", "()V"))
+            .unwrap();
+        assert_eq!(
+            ctx.select_method(m, &ClassRef::new("org/mokapot/Both"))
+                .unwrap_err(),
+            ResolutionError::ConflictingDefaultMethods(method_ref("org/mokapot/Both", "m", "()V"))
+        );
+    }
+
+    #[test]
+    fn select_special_methods() {
+        let ctx = context();
+        let select = |owner_name, name, current| {
+            owner(ctx.select_special_method(
+                &method_ref(owner_name, name, "()V"),
+                &ClassRef::new(current),
+            ))
+        };
+        // `super.greet()` in `A`.
+        assert_eq!(
+            select("org/mokapot/Base", "greet", "org/mokapot/A"),
+            Ok("org/mokapot/Base")
+        );
+        // `super.hello()` in `A` selects the default method inherited by `Base`.
+        assert_eq!(
+            select("org/mokapot/Base", "hello", "org/mokapot/A"),
+            Ok("org/mokapot/Greeter")
+        );
+        // `LoudGreeter.super.greet()` in `B`.
+        assert_eq!(
+            select("org/mokapot/LoudGreeter", "greet", "org/mokapot/B"),
+            Ok("org/mokapot/LoudGreeter")
+        );
+        // The lookup starts from the owner if it is not a superclass of the current class.
+        assert_eq!(
+            select("org/mokapot/A", "greet", "org/mokapot/A"),
+            Ok("org/mokapot/A")
+        );
+    }
+
+    #[test]
+    fn dispatch_targets() {
+        let ctx = context();
+        let owners = |method_ref, receiver| {
+            ctx.dispatch_targets(&method_ref, &ClassRef::new(receiver))
+                .unwrap()
+                .into_iter()
+                .map(|it| it.owner.binary_name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            owners(
+                method_ref("org/mokapot/Greeter", "greet", "()V"),
+                "org/mokapot/Greeter"
+            ),
+            ["org/mokapot/A", "org/mokapot/Base"]
+        );
+        assert_eq!(
+            owners(
+                method_ref("org/mokapot/Base", "greet", "()V"),
+                "org/mokapot/A"
+            ),
+            ["org/mokapot/A"]
+        );
+        assert_eq!(
+            owners(
+                method_ref("org/mokapot/Base", "secret", "()V"),
+                "org/mokapot/Base"
+            ),
+            ["org/mokapot/Base"]
+        );
+        // `Both` is skipped because no method can be selected for it.
+        assert!(owners(
+            method_ref("org/mokapot/Left", "m", "()V"),
+            "org/mokapot/Left"
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,1017 @@
+//! A persistent index of class paths.
+//!
+//! Scanning a class path requires parsing every class in it, which dominates the start-up time
+//! of an analysis over a large set of dependencies.
+//! A [`ClassPathIndex`] stores the summaries of the classes in each class path entry together
+//! with a [`Fingerprint`] of the backing file or directory, so that only the entries changed
+//! since the last run need to be scanned again.
+//! The class hierarchies of the unchanged entries are then built from the index without
+//! parsing them, through [`ClassPathIndex::hierarchies`] or
+//! [`LazyResolutionContext::add_summaries`], and [`ClassPathIndex::indexed`] lists their
+//! classes without reading the entries.
+//!
+//! [`LazyResolutionContext::add_summaries`]: super::LazyResolutionContext::add_summaries
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    hash::Hasher,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    ir::{ClassHierarchy, InterfaceImplHierarchy},
+    jvm::{
+        class,
+        class_loader::{self, class_paths::ClassPathEntry, ClassPath},
+        field, method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Annotation, Class, Field, Method,
+    },
+    types::{field_type::FieldType, method_descriptor::MethodDescriptor},
+};
+
+use super::{load_classes, ClassRefs, LoadFailure};
+
+const HEADER: &str = "mokapot-class-path-index";
+const FORMAT_VERSION: u32 = 2;
+
+/// A summary of the declaration of a class, without the method bodies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSummary {
+    /// The class.
+    pub class_ref: ClassRef,
+    /// The access flags of the class.
+    pub access_flags: class::AccessFlags,
+    /// The superclass of the class.
+    pub super_class: Option<ClassRef>,
+    /// The interfaces directly implemented by the class.
+    pub interfaces: Vec<ClassRef>,
+    /// The types of the annotations on the class, both visible and invisible at runtime.
+    pub annotations: Vec<FieldType>,
+    /// The fields declared in the class.
+    pub fields: Vec<FieldSummary>,
+    /// The methods declared in the class.
+    pub methods: Vec<MethodSummary>,
+}
+
+/// A summary of the declaration of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSummary {
+    /// The access flags of the field.
+    pub access_flags: field::AccessFlags,
+    /// The name of the field.
+    pub name: String,
+    /// The type of the field.
+    pub field_type: FieldType,
+    /// The types of the annotations on the field, both visible and invisible at runtime.
+    pub annotations: Vec<FieldType>,
+}
+
+/// A summary of the declaration of a method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSummary {
+    /// The access flags of the method.
+    pub access_flags: method::AccessFlags,
+    /// The name of the method.
+    pub name: String,
+    /// The descriptor of the method.
+    pub descriptor: MethodDescriptor,
+    /// The types of the annotations on the method, both visible and invisible at runtime.
+    pub annotations: Vec<FieldType>,
+}
+
+fn annotation_types(visible: &[Annotation], invisible: &[Annotation]) -> Vec<FieldType> {
+    visible
+        .iter()
+        .chain(invisible)
+        .map(|it| it.annotation_type.clone())
+        .collect()
+}
+
+impl From<&Class> for ClassSummary {
+    fn from(class: &Class) -> Self {
+        Self {
+            class_ref: class.as_ref(),
+            access_flags: class.access_flags,
+            super_class: class.super_class.clone(),
+            interfaces: class.interfaces.clone(),
+            annotations: annotation_types(
+                &class.runtime_visible_annotations,
+                &class.runtime_invisible_annotations,
+            ),
+            fields: class.fields.iter().map(Into::into).collect(),
+            methods: class.methods.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&Field> for FieldSummary {
+    fn from(field: &Field) -> Self {
+        Self {
+            access_flags: field.access_flags,
+            name: field.name.clone(),
+            field_type: field.field_type.clone(),
+            annotations: annotation_types(
+                &field.runtime_visible_annotations,
+                &field.runtime_invisible_annotations,
+            ),
+        }
+    }
+}
+
+impl From<&Method> for MethodSummary {
+    fn from(method: &Method) -> Self {
+        Self {
+            access_flags: method.access_flags,
+            name: method.name.clone(),
+            descriptor: method.descriptor.clone(),
+            annotations: annotation_types(
+                &method.runtime_visible_annotations,
+                &method.runtime_invisible_annotations,
+            ),
+        }
+    }
+}
+
+impl ClassSummary {
+    /// Returns the references to the fields declared in the class.
+    pub fn field_refs(&self) -> impl Iterator<Item = FieldRef> + '_ {
+        self.fields.iter().map(|it| FieldRef {
+            owner: self.class_ref.clone(),
+            name: it.name.clone(),
+            field_type: it.field_type.clone(),
+        })
+    }
+
+    /// Returns the references to the methods declared in the class.
+    pub fn method_refs(&self) -> impl Iterator<Item = MethodRef> + '_ {
+        self.methods.iter().map(|it| MethodRef {
+            owner: self.class_ref.clone(),
+            name: it.name.clone(),
+            descriptor: it.descriptor.clone(),
+        })
+    }
+}
+
+/// How to decide whether a class path entry has changed since it was indexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FingerprintKind {
+    /// Compares the sizes and the modification times of the files.
+    /// This is cheap, but is fooled by tools that preserve modification times.
+    #[default]
+    Metadata,
+    /// Compares the contents of the files, which requires reading them entirely.
+    Content,
+}
+
+/// A fingerprint of the file or directory backing a class path entry.
+/// For a directory, the fingerprint covers all the files in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fingerprint {
+    /// A hash of the relative paths, sizes and modification times of the files.
+    Metadata(u64),
+    /// A hash of the relative paths and contents of the files.
+    Content(u64),
+}
+
+impl Fingerprint {
+    /// Computes the fingerprint of a file or directory.
+    ///
+    /// # Errors
+    /// Returns an error if the file or directory cannot be read.
+    pub fn of(path: &Path, kind: FingerprintKind) -> io::Result<Self> {
+        let mut files = Vec::new();
+        for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                files.push(entry);
+            }
+        }
+        let mut hasher = Fnv1a::default();
+        for file in files {
+            let relative_path = file.path().strip_prefix(path).unwrap_or(file.path());
+            // The lengths keep the boundaries between the paths and the contents apart.
+            let relative_path = relative_path.as_os_str().as_encoded_bytes();
+            hasher.write_u64(relative_path.len() as u64);
+            hasher.write(relative_path);
+            match kind {
+                FingerprintKind::Metadata => {
+                    let metadata = file.metadata()?;
+                    let modified = metadata
+                        .modified()
+                        .ok()
+                        .and_then(|it| it.duration_since(UNIX_EPOCH).ok())
+                        .unwrap_or_default();
+                    hasher.write_u64(metadata.len());
+                    hasher.write_u128(modified.as_nanos());
+                }
+                FingerprintKind::Content => {
+                    let file = File::open(file.path())?;
+                    hasher.write_u64(file.metadata()?.len());
+                    let mut reader = BufReader::new(file);
+                    loop {
+                        let buf = reader.fill_buf()?;
+                        if buf.is_empty() {
+                            break;
+                        }
+                        hasher.write(buf);
+                        let len = buf.len();
+                        reader.consume(len);
+                    }
+                }
+            }
+        }
+        Ok(match kind {
+            FingerprintKind::Metadata => Self::Metadata(hasher.finish()),
+            FingerprintKind::Content => Self::Content(hasher.finish()),
+        })
+    }
+
+    /// Returns the kind of the fingerprint.
+    #[must_use]
+    pub const fn kind(&self) -> FingerprintKind {
+        match self {
+            Self::Metadata(_) => FingerprintKind::Metadata,
+            Self::Content(_) => FingerprintKind::Content,
+        }
+    }
+}
+
+/// The 64-bit FNV-1a hash, which is stable across platforms and Rust versions.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// The classes in a class path entry, as recorded in a [`ClassPathIndex`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedEntry {
+    /// The fingerprint of the entry when it was scanned.
+    pub fingerprint: Fingerprint,
+    /// The summaries of the classes in the entry.
+    pub classes: BTreeMap<ClassRef, ClassSummary>,
+}
+
+/// An index of class path entries keyed by their paths, which can be saved to and loaded from
+/// disk.
+///
+/// # Examples
+/// ```no_run
+/// use mokapot::{
+///     analysis::index::{ClassPathIndex, FingerprintKind},
+///     jvm::class_loader::class_paths::ClassPathEntry,
+/// };
+///
+/// let mut index = ClassPathIndex::load("classpath.idx").unwrap_or_default();
+/// let class_path = ClassPathEntry::parse_spec("lib/*");
+/// let report = index.update(&class_path, FingerprintKind::Metadata);
+/// println!("Rescanned {} entries", report.rescanned.len());
+/// index.save("classpath.idx").unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassPathIndex {
+    entries: BTreeMap<PathBuf, IndexedEntry>,
+}
+
+/// A class path entry whose classes are listed by a [`ClassPathIndex`], so that neither listing
+/// the classes nor looking up a class missing from the entry needs to read the entry.
+/// Entries not in the index are read as usual.
+///
+/// See [`ClassPathIndex::indexed`].
+#[derive(Debug, Clone, Copy)]
+pub struct IndexedClassPath<'a> {
+    entry: &'a ClassPathEntry,
+    indexed: Option<&'a IndexedEntry>,
+}
+
+impl ClassPath for IndexedClassPath<'_> {
+    fn find_class(&self, binary_name: &str) -> Result<Class, class_loader::Error> {
+        if self
+            .indexed
+            .is_some_and(|it| !it.classes.contains_key(&ClassRef::new(binary_name)))
+        {
+            return Err(class_loader::Error::NotFound);
+        }
+        self.entry.find_class(binary_name)
+    }
+
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, class_loader::Error> {
+        self.entry.find_resource(name)
+    }
+
+    fn list_resources(&self, directory: &str) -> Result<Vec<String>, class_loader::Error> {
+        self.entry.list_resources(directory)
+    }
+}
+
+impl ClassRefs for IndexedClassPath<'_> {
+    fn class_refs(&self) -> HashSet<ClassRef> {
+        self.indexed.map_or_else(
+            || self.entry.class_refs(),
+            |it| it.classes.keys().cloned().collect(),
+        )
+    }
+}
+
+/// The outcome of [`ClassPathIndex::update`].
+#[derive(Debug, Default)]
+pub struct UpdateReport {
+    /// The entries whose fingerprints are unchanged, so that their summaries are reused.
+    pub reused: Vec<PathBuf>,
+    /// The entries that are new or changed, so that they are scanned again.
+    pub rescanned: Vec<PathBuf>,
+    /// The classes that cannot be loaded while scanning. They are left out of the index.
+    pub load_failures: Vec<LoadFailure>,
+    /// The entries whose fingerprints cannot be computed. They are left out of the index.
+    pub fingerprint_failures: Vec<(PathBuf, io::Error)>,
+}
+
+/// An error that occurs while reading a [`ClassPathIndex`].
+#[derive(Debug, thiserror::Error)]
+pub enum IndexError {
+    /// The index cannot be read.
+    #[error("IO error: {0}")]
+    IO(#[from] io::Error),
+    /// The index is written by an incompatible version of this crate.
+    #[error("Unsupported index format: {0}")]
+    UnsupportedFormat(String),
+    /// The index is corrupted.
+    #[error("Malformed index at line {0}")]
+    Malformed(usize),
+}
+
+impl ClassPathIndex {
+    /// Creates an empty index.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the indexed entry at the given path.
+    #[must_use]
+    pub fn entry(&self, path: &Path) -> Option<&IndexedEntry> {
+        self.entries.get(path)
+    }
+
+    /// Returns the paths of all the indexed entries.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.entries.keys().map(PathBuf::as_path)
+    }
+
+    /// Removes an entry from the index.
+    pub fn remove(&mut self, path: &Path) -> Option<IndexedEntry> {
+        self.entries.remove(path)
+    }
+
+    /// Brings the index up to date with a class path.
+    /// Entries whose fingerprints match the recorded ones are reused, while the others are scanned
+    /// by loading their classes in parallel.
+    /// An entry recorded with a different [`FingerprintKind`] is scanned again.
+    ///
+    /// Entries not backed by files (e.g., [`ClassPathEntry::InMemory`]) are ignored, and the
+    /// entries in the index but not in `class_path` are kept.
+    pub fn update(&mut self, class_path: &[ClassPathEntry], kind: FingerprintKind) -> UpdateReport {
+        let mut report = UpdateReport::default();
+        for entry in class_path {
+            let Some(path) = entry.path() else {
+                continue;
+            };
+            let fingerprint = match Fingerprint::of(path, kind) {
+                Ok(it) => it,
+                Err(err) => {
+                    report.fingerprint_failures.push((path.to_owned(), err));
+                    continue;
+                }
+            };
+            if self
+                .entries
+                .get(path)
+                .is_some_and(|it| it.fingerprint == fingerprint)
+            {
+                report.reused.push(path.to_owned());
+                continue;
+            }
+            let (classes, failures) = load_classes(std::slice::from_ref(entry));
+            let classes = classes
+                .iter()
+                .map(|(class_ref, class)| (class_ref.clone(), ClassSummary::from(class)))
+                .collect();
+            report.load_failures.extend(failures);
+            report.rescanned.push(path.to_owned());
+            self.entries.insert(
+                path.to_owned(),
+                IndexedEntry {
+                    fingerprint,
+                    classes,
+                },
+            );
+        }
+        report
+    }
+
+    /// Wraps the entries of a class path so that their classes are listed from the index when
+    /// building a [`ResolutionContext`](super::ResolutionContext) or a
+    /// [`ClassLoader`](crate::jvm::ClassLoader) on them.
+    /// Only listing the classes is skipped: every class is still parsed when it is loaded, so a
+    /// [`ResolutionContext`](super::ResolutionContext) still parses all the classes in the
+    /// entries. Use [`ClassPathIndex::hierarchies`] or
+    /// [`LazyResolutionContext::add_summaries`](super::LazyResolutionContext::add_summaries) to
+    /// start without parsing the unchanged entries.
+    /// The entries are not checked for changes, so the index should be brought up to date with
+    /// [`ClassPathIndex::update`] beforehand.
+    #[must_use]
+    pub fn indexed<'a>(&'a self, class_path: &'a [ClassPathEntry]) -> Vec<IndexedClassPath<'a>> {
+        class_path
+            .iter()
+            .map(|entry| IndexedClassPath {
+                entry,
+                indexed: entry.path().and_then(|it| self.entries.get(it)),
+            })
+            .collect()
+    }
+
+    /// Returns the classes in an indexed entry, or `None` if the entry is not indexed.
+    #[must_use]
+    pub fn class_refs(&self, path: &Path) -> Option<HashSet<ClassRef>> {
+        self.entries
+            .get(path)
+            .map(|it| it.classes.keys().cloned().collect())
+    }
+
+    /// Returns the summaries of the classes visible through a class path, where a class in an
+    /// earlier entry shadows those with the same name in the later ones.
+    /// Paths that are not indexed are skipped.
+    #[must_use]
+    pub fn summaries<P: AsRef<Path>>(&self, class_path: &[P]) -> HashMap<ClassRef, &ClassSummary> {
+        let mut summaries = HashMap::new();
+        for entry in class_path
+            .iter()
+            .filter_map(|it| self.entries.get(it.as_ref()))
+        {
+            for (class_ref, summary) in &entry.classes {
+                summaries.entry(class_ref.clone()).or_insert(summary);
+            }
+        }
+        summaries
+    }
+
+    /// Builds the class hierarchy and the interface implementations of the classes visible through
+    /// a class path without loading any class.
+    /// See [`ClassPathIndex::summaries`] for how the entries are searched.
+    #[must_use]
+    pub fn hierarchies<P: AsRef<Path>>(
+        &self,
+        class_path: &[P],
+    ) -> (ClassHierarchy, InterfaceImplHierarchy) {
+        let mut class_hierarchy = ClassHierarchy::from_classes([]);
+        let mut interface_implementations = InterfaceImplHierarchy::from_classes([]);
+        for (class_ref, summary) in self.summaries(class_path) {
+            class_hierarchy.insert(class_ref.clone(), summary.super_class.as_ref());
+            interface_implementations.insert(&class_ref, &summary.interfaces);
+        }
+        (class_hierarchy, interface_implementations)
+    }
+
+    /// Loads an index from a file.
+    ///
+    /// # Errors
+    /// See [`IndexError`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, IndexError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Saves the index to a file.
+    /// The index is first written to a sibling file with a `.tmp` suffix, which then replaces
+    /// the file atomically, so that concurrent readers never see a partial index.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        let result = File::create(&temp_path)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                self.write_to(&mut writer)?;
+                writer
+                    .into_inner()
+                    .map_err(io::IntoInnerError::into_error)?
+                    .sync_all()
+            })
+            .and_then(|()| fs::rename(&temp_path, path));
+        if result.is_err() {
+            // The partial index is useless, and failing to remove it does not matter.
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    /// Writes the index in a line-based text format with tab-separated fields, where tabs, line
+    /// breaks and backslashes in the names are escaped.
+    /// Entries whose paths are not valid UTF-8 are not written.
+    ///
+    /// # Errors
+    /// Returns an error if the writer fails.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{HEADER}\t{FORMAT_VERSION}")?;
+        for (path, entry) in &self.entries {
+            let Some(path) = path.to_str() else {
+                continue;
+            };
+            let (kind, hash) = match entry.fingerprint {
+                Fingerprint::Metadata(hash) => ("metadata", hash),
+                Fingerprint::Content(hash) => ("content", hash),
+            };
+            writeln!(writer, "entry\t{kind}\t{hash:016x}\t{}", escape(path))?;
+            for summary in entry.classes.values() {
+                write!(
+                    writer,
+                    "class\t{:04x}\t{}\t{}",
+                    summary.access_flags.bits(),
+                    escape(&summary.class_ref.binary_name),
+                    escape(
+                        summary
+                            .super_class
+                            .as_ref()
+                            .map_or("", |it| it.binary_name.as_str())
+                    ),
+                )?;
+                for interface in &summary.interfaces {
+                    write!(writer, "\t{}", escape(&interface.binary_name))?;
+                }
+                writeln!(writer)?;
+                write_annotations(&mut writer, "annotations", &summary.annotations)?;
+                for field in &summary.fields {
+                    writeln!(
+                        writer,
+                        "field\t{:04x}\t{}\t{}",
+                        field.access_flags.bits(),
+                        escape(&field.name),
+                        escape(&field.field_type.descriptor())
+                    )?;
+                    write_annotations(&mut writer, "field_annotations", &field.annotations)?;
+                }
+                for method in &summary.methods {
+                    writeln!(
+                        writer,
+                        "method\t{:04x}\t{}\t{}",
+                        method.access_flags.bits(),
+                        escape(&method.name),
+                        escape(&method.descriptor.descriptor())
+                    )?;
+                    write_annotations(&mut writer, "method_annotations", &method.annotations)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads an index written by [`ClassPathIndex::write_to`].
+    ///
+    /// # Errors
+    /// See [`IndexError`].
+    #[allow(clippy::too_many_lines)]
+    pub fn read_from(reader: impl BufRead) -> Result<Self, IndexError> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        if header != format!("{HEADER}\t{FORMAT_VERSION}") {
+            return Err(IndexError::UnsupportedFormat(header));
+        }
+        let mut index = Self::new();
+        let mut current_entry: Option<&mut IndexedEntry> = None;
+        let mut current_class: Option<ClassSummary> = None;
+        for (idx, line) in lines.enumerate() {
+            let line = line?;
+            // The header is the first line, and line numbers start from 1.
+            let line_no = idx + 2;
+            let malformed = || IndexError::Malformed(line_no);
+            let mut fields = line
+                .split('\t')
+                .map(unescape)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(malformed)?
+                .into_iter();
+            let tag = fields.next().ok_or_else(malformed)?;
+            match tag.as_str() {
+                "entry" => {
+                    let kind = fields.next().ok_or_else(malformed)?;
+                    let hash = fields
+                        .next()
+                        .and_then(|it| u64::from_str_radix(&it, 16).ok())
+                        .ok_or_else(malformed)?;
+                    let fingerprint = match kind.as_str() {
+                        "metadata" => Fingerprint::Metadata(hash),
+                        "content" => Fingerprint::Content(hash),
+                        _ => return Err(malformed()),
+                    };
+                    let path = fields.next().ok_or_else(malformed)?;
+                    if let (Some(entry), Some(class)) = (current_entry, current_class.take()) {
+                        entry.classes.insert(class.class_ref.clone(), class);
+                    }
+                    current_entry = Some(index.entries.entry(PathBuf::from(path)).or_insert(
+                        IndexedEntry {
+                            fingerprint,
+                            classes: BTreeMap::new(),
+                        },
+                    ));
+                }
+                "class" => {
+                    let entry = current_entry.as_mut().ok_or_else(malformed)?;
+                    if let Some(class) = current_class.take() {
+                        entry.classes.insert(class.class_ref.clone(), class);
+                    }
+                    let access_flags = parse_flags(fields.next()).ok_or_else(malformed)?;
+                    let binary_name = fields
+                        .next()
+                        .filter(|it| !it.is_empty())
+                        .ok_or_else(malformed)?;
+                    let super_class = fields.next().ok_or_else(malformed)?;
+                    current_class = Some(ClassSummary {
+                        class_ref: ClassRef::new(binary_name),
+                        access_flags: class::AccessFlags::from_bits_retain(access_flags),
+                        super_class: (!super_class.is_empty()).then(|| ClassRef::new(super_class)),
+                        interfaces: fields.map(ClassRef::new).collect(),
+                        annotations: Vec::new(),
+                        fields: Vec::new(),
+                        methods: Vec::new(),
+                    });
+                }
+                "annotations" => {
+                    let class = current_class.as_mut().ok_or_else(malformed)?;
+                    class.annotations = parse_annotations(fields).ok_or_else(malformed)?;
+                }
+                "field" => {
+                    let class = current_class.as_mut().ok_or_else(malformed)?;
+                    let access_flags = parse_flags(fields.next()).ok_or_else(malformed)?;
+                    let name = fields.next().ok_or_else(malformed)?;
+                    let field_type = fields
+                        .next()
+                        .and_then(|it| FieldType::from_str(&it).ok())
+                        .ok_or_else(malformed)?;
+                    class.fields.push(FieldSummary {
+                        access_flags: field::AccessFlags::from_bits_retain(access_flags),
+                        name,
+                        field_type,
+                        annotations: Vec::new(),
+                    });
+                }
+                "field_annotations" => {
+                    let field = current_class
+                        .as_mut()
+                        .and_then(|it| it.fields.last_mut())
+                        .ok_or_else(malformed)?;
+                    field.annotations = parse_annotations(fields).ok_or_else(malformed)?;
+                }
+                "method" => {
+                    let class = current_class.as_mut().ok_or_else(malformed)?;
+                    let access_flags = parse_flags(fields.next()).ok_or_else(malformed)?;
+                    let name = fields.next().ok_or_else(malformed)?;
+                    let descriptor = fields
+                        .next()
+                        .and_then(|it| MethodDescriptor::from_str(&it).ok())
+                        .ok_or_else(malformed)?;
+                    class.methods.push(MethodSummary {
+                        access_flags: method::AccessFlags::from_bits_retain(access_flags),
+                        name,
+                        descriptor,
+                        annotations: Vec::new(),
+                    });
+                }
+                "method_annotations" => {
+                    let method = current_class
+                        .as_mut()
+                        .and_then(|it| it.methods.last_mut())
+                        .ok_or_else(malformed)?;
+                    method.annotations = parse_annotations(fields).ok_or_else(malformed)?;
+                }
+                _ => return Err(malformed()),
+            }
+        }
+        if let (Some(entry), Some(class)) = (current_entry, current_class) {
+            entry.classes.insert(class.class_ref.clone(), class);
+        }
+        Ok(index)
+    }
+}
+
+fn write_annotations(
+    writer: &mut impl Write,
+    tag: &str,
+    annotations: &[FieldType],
+) -> io::Result<()> {
+    if annotations.is_empty() {
+        return Ok(());
+    }
+    write!(writer, "{tag}")?;
+    for annotation in annotations {
+        write!(writer, "\t{}", escape(&annotation.descriptor()))?;
+    }
+    writeln!(writer)
+}
+
+fn parse_flags(field: Option<String>) -> Option<u16> {
+    field.and_then(|it| u16::from_str_radix(&it, 16).ok())
+}
+
+fn parse_annotations(fields: impl Iterator<Item = String>) -> Option<Vec<FieldType>> {
+    fields.map(|it| FieldType::from_str(&it).ok()).collect()
+}
+
+/// Escapes the backslashes, tabs and line breaks in a field, which may appear in paths and in
+/// the names in class files.
+fn escape(field: &str) -> Cow<'_, str> {
+    if !field.contains(['\\', '\t', '\n', '\r']) {
+        return Cow::Borrowed(field);
+    }
+    let mut escaped = String::with_capacity(field.len() + 2);
+    for ch in field.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(ch),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Reverses [`escape`], or returns `None` if the field contains an invalid escape sequence.
+fn unescape(field: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::ResolutionContext,
+        jvm::{class_loader::class_paths::DirectoryClassPath, ClassLoader},
+        tests::empty_class_named,
+    };
+
+    use super::*;
+
+    fn write_class(dir: &Path, binary_name: &str, super_class: &str) {
+        let path = dir.join(binary_name).with_extension("class");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, empty_class_named(binary_name, super_class)).unwrap();
+    }
+
+    fn directory(dir: &Path) -> ClassPathEntry {
+        DirectoryClassPath::new(dir).into()
+    }
+
+    #[test]
+    fn reuse_unchanged_entries() {
+        let unchanged = tempfile::tempdir().unwrap();
+        let changed = tempfile::tempdir().unwrap();
+        write_class(unchanged.path(), "org/mokapot/Foo", "java/lang/Object");
+        write_class(changed.path(), "org/mokapot/Bar", "java/lang/Object");
+        let class_path = [directory(unchanged.path()), directory(changed.path())];
+
+        let mut index = ClassPathIndex::new();
+        let report = index.update(&class_path, FingerprintKind::Content);
+        assert_eq!(report.rescanned.len(), 2);
+        assert!(report.reused.is_empty());
+
+        write_class(changed.path(), "org/mokapot/Baz", "org/mokapot/Bar");
+        let report = index.update(&class_path, FingerprintKind::Content);
+        assert_eq!(report.reused, [unchanged.path()]);
+        assert_eq!(report.rescanned, [changed.path()]);
+        assert_eq!(
+            index.class_refs(changed.path()).unwrap(),
+            HashSet::from([
+                ClassRef::new("org/mokapot/Bar"),
+                ClassRef::new("org/mokapot/Baz"),
+            ])
+        );
+    }
+
+    #[test]
+    fn rescan_on_different_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        write_class(dir.path(), "org/mokapot/Foo", "java/lang/Object");
+        let class_path = [directory(dir.path())];
+        let mut index = ClassPathIndex::new();
+        index.update(&class_path, FingerprintKind::Metadata);
+        let report = index.update(&class_path, FingerprintKind::Metadata);
+        assert_eq!(report.reused.len(), 1);
+        let report = index.update(&class_path, FingerprintKind::Content);
+        assert_eq!(report.rescanned.len(), 1);
+    }
+
+    #[test]
+    fn content_fingerprint_boundaries() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        std::fs::write(first.path().join("ab"), "c").unwrap();
+        std::fs::write(second.path().join("a"), "bc").unwrap();
+        assert_ne!(
+            Fingerprint::of(first.path(), FingerprintKind::Content).unwrap(),
+            Fingerprint::of(second.path(), FingerprintKind::Content).unwrap()
+        );
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        write_class(dir.path(), "org/mokapot/Foo", "java/lang/Object");
+        write_class(dir.path(), "org/mokapot/Bar", "org/mokapot/Foo");
+        let class_path = [directory(dir.path())];
+        let mut index = ClassPathIndex::new();
+        index.update(&class_path, FingerprintKind::Metadata);
+
+        let index_file = dir.path().join("index");
+        index.save(&index_file).unwrap();
+        let loaded = ClassPathIndex::load(&index_file).unwrap();
+        assert_eq!(loaded, index);
+        assert!(!dir.path().join("index.tmp").exists());
+
+        let report = {
+            let mut loaded = loaded;
+            loaded.update(&class_path, FingerprintKind::Metadata)
+        };
+        // The index file itself lives in the directory, so the entry is considered changed.
+        assert_eq!(report.rescanned.len(), 1);
+    }
+
+    #[test]
+    fn round_trip_summaries() {
+        let summary = ClassSummary {
+            class_ref: ClassRef::new("org/mokapot/Foo"),
+            access_flags: class::AccessFlags::PUBLIC | class::AccessFlags::FINAL,
+            super_class: None,
+            interfaces: vec![ClassRef::new("java/io/Serializable")],
+            annotations: vec![FieldType::Object(ClassRef::new("java/lang/Deprecated"))],
+            fields: vec![FieldSummary {
+                access_flags: field::AccessFlags::PRIVATE,
+                name: "value".to_owned(),
+                field_type: FieldType::from_str("[I").unwrap(),
+                annotations: Vec::new(),
+            }],
+            methods: vec![MethodSummary {
+                access_flags: method::AccessFlags::PUBLIC,
+                name: "<init>".to_owned(),
+                descriptor: MethodDescriptor::from_str("(Ljava/lang/String;J)V").unwrap(),
+                annotations: vec![FieldType::Object(ClassRef::new("org/mokapot/Ann"))],
+            }],
+        };
+        let index = ClassPathIndex {
+            entries: BTreeMap::from([(
+                PathBuf::from("lib/a b.jar"),
+                IndexedEntry {
+                    fingerprint: Fingerprint::Content(42),
+                    classes: BTreeMap::from([(summary.class_ref.clone(), summary)]),
+                },
+            )]),
+        };
+        let mut buf = Vec::new();
+        index.write_to(&mut buf).unwrap();
+        let loaded = ClassPathIndex::read_from(buf.as_slice()).unwrap();
+        assert_eq!(loaded, index);
+    }
+
+    #[test]
+    fn reject_malformed_index() {
+        assert!(matches!(
+            ClassPathIndex::read_from("not an index\n".as_bytes()),
+            Err(IndexError::UnsupportedFormat(_))
+        ));
+        let input = format!("{HEADER}\t{FORMAT_VERSION}\nclass\t0001\torg/mokapot/Foo\t\n");
+        assert!(matches!(
+            ClassPathIndex::read_from(input.as_bytes()),
+            Err(IndexError::Malformed(2))
+        ));
+    }
+
+    #[test]
+    fn escape_names() {
+        let summary = ClassSummary {
+            class_ref: ClassRef::new("org/mokapot/Tab\tNew\nLine\\"),
+            access_flags: class::AccessFlags::PUBLIC,
+            super_class: Some(ClassRef::new("org/mokapot/Carriage\rReturn")),
+            interfaces: vec![ClassRef::new("org/mokapot/Back\\t")],
+            annotations: Vec::new(),
+            fields: vec![FieldSummary {
+                access_flags: field::AccessFlags::PUBLIC,
+                name: "a\tb".to_owned(),
+                field_type: FieldType::from_str("Lorg/mokapot/A\tB;").unwrap(),
+                annotations: Vec::new(),
+            }],
+            methods: Vec::new(),
+        };
+        let index = ClassPathIndex {
+            entries: BTreeMap::from([(
+                PathBuf::from("lib/a\tb\nc.jar"),
+                IndexedEntry {
+                    fingerprint: Fingerprint::Metadata(7),
+                    classes: BTreeMap::from([(summary.class_ref.clone(), summary)]),
+                },
+            )]),
+        };
+        let mut buf = Vec::new();
+        index.write_to(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf.clone()).unwrap().lines().count(), 4);
+        let loaded = ClassPathIndex::read_from(buf.as_slice()).unwrap();
+        assert_eq!(loaded, index);
+
+        let input = format!("{HEADER}\t{FORMAT_VERSION}\nentry\tcontent\t0\tlib\\x.jar\n");
+        assert!(matches!(
+            ClassPathIndex::read_from(input.as_bytes()),
+            Err(IndexError::Malformed(2))
+        ));
+    }
+
+    #[test]
+    fn list_classes_from_index() {
+        let dir = tempfile::tempdir().unwrap();
+        write_class(dir.path(), "org/mokapot/Foo", "java/lang/Object");
+        let class_path = [directory(dir.path())];
+        let mut index = ClassPathIndex::new();
+        index.update(&class_path, FingerprintKind::Metadata);
+        // Classes added after indexing are not visible until the index is updated.
+        write_class(dir.path(), "org/mokapot/Bar", "org/mokapot/Foo");
+
+        let indexed = index.indexed(&class_path);
+        let context = ResolutionContext::new(&indexed, &[]);
+        assert_eq!(
+            context.application_classes.keys().collect::<Vec<_>>(),
+            [&ClassRef::new("org/mokapot/Foo")]
+        );
+        let loader = ClassLoader::new(indexed);
+        assert!(loader.load_class("org/mokapot/Foo").is_ok());
+        assert!(matches!(
+            loader.load_class("org/mokapot/Bar"),
+            Err(class_loader::Error::NotFound)
+        ));
+
+        index.update(&class_path, FingerprintKind::Metadata);
+        let context = ResolutionContext::try_new(
+            &index.indexed(&class_path),
+            &[],
+            crate::analysis::ErrorPolicy::Abort,
+        )
+        .unwrap();
+        assert_eq!(context.application_classes.len(), 2);
+    }
+
+    #[test]
+    fn warm_start_hierarchies() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        write_class(first.path(), "org/mokapot/Foo", "java/lang/Object");
+        write_class(second.path(), "org/mokapot/Foo", "java/lang/Number");
+        write_class(second.path(), "org/mokapot/Bar", "org/mokapot/Foo");
+        let paths = [first.path(), second.path()];
+        let class_path = paths.map(directory);
+        let mut index = ClassPathIndex::new();
+        index.update(&class_path, FingerprintKind::Metadata);
+
+        let (class_hierarchy, _) = index.hierarchies(&paths);
+        assert_eq!(
+            class_hierarchy.super_classes(&ClassRef::new("org/mokapot/Bar")),
+            HashSet::from([
+                ClassRef::new("org/mokapot/Foo"),
+                ClassRef::new("java/lang/Object"),
+            ])
+        );
+    }
+}
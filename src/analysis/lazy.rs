@@ -0,0 +1,384 @@
+//! On-demand class resolution.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::RwLock,
+};
+
+use crate::{
+    ir::{ClassHierarchy, InterfaceImplHierarchy},
+    jvm::{
+        class_loader::{self, CachingClassLoader, ClassPath},
+        references::ClassRef,
+        Class, ClassLoader,
+    },
+};
+
+use super::{
+    index::ClassSummary, load_classes_in_parallel, ClassRefs, ErrorPolicy, InitError, LoadFailure,
+};
+
+/// A context for class resolution that loads library classes only when they are needed.
+///
+/// Unlike [`ResolutionContext`](super::ResolutionContext), which loads every class on the class
+/// path up front, the library classes are loaded and added to the class hierarchies as the
+/// resolution touches them.
+/// The application classes and their library supertypes are still loaded eagerly, so the
+/// queries for subclasses and implementors are complete as long as the subtypes are
+/// application classes.
+#[derive(Debug)]
+pub struct LazyResolutionContext<P> {
+    application_classes: HashMap<ClassRef, Class>,
+    library: CachingClassLoader<P>,
+    hierarchies: RwLock<Hierarchies>,
+    load_failures: Vec<LoadFailure>,
+}
+
+#[derive(Debug)]
+struct Hierarchies {
+    class_hierarchy: ClassHierarchy,
+    interface_implementations: InterfaceImplHierarchy,
+    loaded_library_classes: HashSet<ClassRef>,
+}
+
+impl<P> LazyResolutionContext<P> {
+    /// Creates a new lazy resolution context.
+    /// The application classes are loaded in parallel as in
+    /// [`ResolutionContext::try_new`](super::ResolutionContext::try_new), together with the
+    /// library classes they extend or implement, directly or indirectly.
+    /// Other library classes are not loaded until they are requested.
+    /// The supertypes that cannot be loaded are skipped.
+    ///
+    /// # Errors
+    /// [`InitError::LoadFailures`] if any application class cannot be loaded with
+    /// [`ErrorPolicy::Abort`].
+    pub fn new<A, L>(
+        app_class_path: &[A],
+        lib_class_path: L,
+        error_policy: ErrorPolicy,
+    ) -> Result<Self, InitError>
+    where
+        A: ClassPath + ClassRefs + Sync,
+        L: IntoIterator<Item = P>,
+        P: ClassPath,
+    {
+        let (application_classes, load_failures) = load_classes_in_parallel(app_class_path);
+        if error_policy == ErrorPolicy::Abort && !load_failures.is_empty() {
+            return Err(InitError::LoadFailures(load_failures));
+        }
+        let hierarchies = Hierarchies {
+            class_hierarchy: ClassHierarchy::from_classes(application_classes.values()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(
+                application_classes.values(),
+            ),
+            loaded_library_classes: HashSet::new(),
+        };
+        let context = Self {
+            application_classes,
+            library: CachingClassLoader::from(ClassLoader::new(lib_class_path)),
+            hierarchies: RwLock::new(hierarchies),
+            load_failures,
+        };
+        context.load_library_supertypes();
+        Ok(context)
+    }
+
+    /// Loads the library classes and interfaces that the application classes extend or
+    /// implement, directly or indirectly.
+    fn load_library_supertypes(&self)
+    where
+        P: ClassPath,
+    {
+        let mut visited: HashSet<_> = self.application_classes.keys().cloned().collect();
+        let mut worklist: Vec<_> = self.application_classes.values().collect();
+        while let Some(class) = worklist.pop() {
+            let supertypes = class.super_class.iter().chain(&class.interfaces);
+            for supertype in supertypes {
+                if visited.insert(supertype.clone()) {
+                    if let Ok(super_class) = self.class(supertype) {
+                        worklist.push(super_class);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the application classes.
+    #[must_use]
+    pub fn application_classes(&self) -> &HashMap<ClassRef, Class> {
+        &self.application_classes
+    }
+
+    /// Returns the application classes that cannot be loaded.
+    #[must_use]
+    pub fn load_failures(&self) -> &[LoadFailure] {
+        &self.load_failures
+    }
+
+    /// Checks whether the class is an application class or a library class that has been loaded.
+    ///
+    /// # Panics
+    /// Panics if the lock on the class hierarchies is poisoned.
+    #[must_use]
+    pub fn is_loaded(&self, class_ref: &ClassRef) -> bool {
+        self.application_classes.contains_key(class_ref)
+            || self
+                .hierarchies
+                .read()
+                .expect("The lock should not be poisoned")
+                .loaded_library_classes
+                .contains(class_ref)
+    }
+
+    /// Returns the library classes that have been loaded so far.
+    ///
+    /// # Panics
+    /// Panics if the lock on the class hierarchies is poisoned.
+    #[must_use]
+    pub fn loaded_library_classes(&self) -> HashSet<ClassRef> {
+        self.hierarchies
+            .read()
+            .expect("The lock should not be poisoned")
+            .loaded_library_classes
+            .clone()
+    }
+
+    /// Gets a class, loading it from the library class path if it is not loaded yet.
+    /// Application classes shadow the library classes with the same names.
+    ///
+    /// # Errors
+    /// See [`class_loader::Error`].
+    ///
+    /// # Panics
+    /// Panics if the lock on the class hierarchies is poisoned.
+    pub fn class(&self, class_ref: &ClassRef) -> Result<&Class, class_loader::Error>
+    where
+        P: ClassPath,
+    {
+        if let Some(class) = self.application_classes.get(class_ref) {
+            return Ok(class);
+        }
+        let class = self.library.load_class(class_ref.binary_name.as_str())?;
+        if !self.is_loaded(class_ref) {
+            let mut hierarchies = self
+                .hierarchies
+                .write()
+                .expect("The lock should not be poisoned");
+            if hierarchies.loaded_library_classes.insert(class_ref.clone()) {
+                hierarchies.class_hierarchy.add_class(class);
+                hierarchies.interface_implementations.add_class(class);
+            }
+        }
+        Ok(class)
+    }
+
+    /// Returns the superclasses of a class, from its direct superclass up to the root,
+    /// loading them as needed.
+    ///
+    /// # Errors
+    /// See [`class_loader::Error`] if the class or any of its superclasses cannot be loaded.
+    pub fn super_classes(&self, class_ref: &ClassRef) -> Result<Vec<ClassRef>, class_loader::Error>
+    where
+        P: ClassPath,
+    {
+        let mut super_classes = Vec::new();
+        let mut current = self.class(class_ref)?;
+        while let Some(super_class) = current.super_class.as_ref() {
+            // Guards against malformed class paths with circular inheritance.
+            if super_classes.contains(super_class) || super_class == class_ref {
+                break;
+            }
+            super_classes.push(super_class.clone());
+            current = self.class(super_class)?;
+        }
+        Ok(super_classes)
+    }
+
+    /// Returns all the interfaces implemented by a class, including those inherited from its
+    /// superclasses and superinterfaces, loading them as needed.
+    ///
+    /// # Errors
+    /// See [`class_loader::Error`] if the class or any of its supertypes cannot be loaded.
+    pub fn implemented_interfaces(
+        &self,
+        class_ref: &ClassRef,
+    ) -> Result<HashSet<ClassRef>, class_loader::Error>
+    where
+        P: ClassPath,
+    {
+        let mut interfaces = HashSet::new();
+        let mut worklist = vec![class_ref.clone()];
+        worklist.extend(self.super_classes(class_ref)?);
+        while let Some(current) = worklist.pop() {
+            for interface in &self.class(&current)?.interfaces {
+                if interfaces.insert(interface.clone()) {
+                    worklist.push(interface.clone());
+                }
+            }
+        }
+        Ok(interfaces)
+    }
+
+    /// Adds the classes described by `summaries` (e.g., from a
+    /// [`ClassPathIndex`](super::index::ClassPathIndex)) to the class hierarchies without loading
+    /// them, so that [`LazyResolutionContext::subclasses`] and
+    /// [`LazyResolutionContext::implementors`] also cover the library classes not loaded yet.
+    /// Application classes and the library classes already loaded are not affected.
+    ///
+    /// # Panics
+    /// Panics if the lock on the class hierarchies is poisoned.
+    pub fn add_summaries<'a, I>(&self, summaries: I)
+    where
+        I: IntoIterator<Item = &'a ClassSummary>,
+    {
+        let mut hierarchies = self
+            .hierarchies
+            .write()
+            .expect("The lock should not be poisoned");
+        let Hierarchies {
+            class_hierarchy,
+            interface_implementations,
+            loaded_library_classes,
+        } = &mut *hierarchies;
+        for summary in summaries {
+            let class_ref = &summary.class_ref;
+            if self.application_classes.contains_key(class_ref)
+                || loaded_library_classes.contains(class_ref)
+            {
+                continue;
+            }
+            class_hierarchy.insert(class_ref.clone(), summary.super_class.as_ref());
+            interface_implementations.insert(class_ref, &summary.interfaces);
+        }
+    }
+
+    /// Returns the subclasses of a class among the application classes and the library classes
+    /// loaded so far.
+    ///
+    /// # Panics
+    /// Panics if the lock on the class hierarchies is poisoned.
+    #[must_use]
+    pub fn subclasses(&self, class_ref: &ClassRef) -> HashSet<ClassRef> {
+        self.hierarchies
+            .read()
+            .expect("The lock should not be poisoned")
+            .class_hierarchy
+            .subclasses(class_ref)
+    }
+
+    /// Returns the classes and interfaces that implement or extend an interface, directly or
+    /// indirectly, among the application classes and the library classes loaded so far.
+    ///
+    /// # Panics
+    /// Panics if the lock on the class hierarchies is poisoned.
+    #[must_use]
+    pub fn implementors(&self, interface: &ClassRef) -> HashSet<ClassRef> {
+        let hierarchies = self
+            .hierarchies
+            .read()
+            .expect("The lock should not be poisoned");
+        let mut implementors = hierarchies
+            .interface_implementations
+            .implementors(interface);
+        let subclasses: Vec<_> = implementors
+            .iter()
+            .flat_map(|it| hierarchies.class_hierarchy.subclasses(it))
+            .collect();
+        implementors.extend(subclasses);
+        implementors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        jvm::class_loader::class_paths::InMemoryClassPath,
+        tests::{class_path_with, empty_class_named},
+    };
+
+    use super::*;
+
+    fn context() -> LazyResolutionContext<InMemoryClassPath> {
+        let app = class_path_with(&[
+            ("org/mokapot/App", "org/mokapot/lib/Base"),
+            ("org/mokapot/Other", "java/lang/Object"),
+        ]);
+        let lib = class_path_with(&[
+            ("org/mokapot/lib/Base", "org/mokapot/lib/Root"),
+            ("org/mokapot/lib/Root", "java/lang/Object"),
+            ("org/mokapot/lib/Unused", "java/lang/Object"),
+            ("java/lang/Object", "java/lang/Object"),
+        ]);
+        LazyResolutionContext::new(&[app], [lib], ErrorPolicy::Abort).unwrap()
+    }
+
+    #[test]
+    fn load_on_demand() {
+        let ctx = context();
+        assert_eq!(ctx.application_classes().len(), 2);
+        let supertypes = HashSet::from([
+            ClassRef::new("org/mokapot/lib/Base"),
+            ClassRef::new("org/mokapot/lib/Root"),
+            ClassRef::new("java/lang/Object"),
+        ]);
+        assert_eq!(ctx.loaded_library_classes(), supertypes);
+        let unused = ClassRef::new("org/mokapot/lib/Unused");
+        assert!(!ctx.is_loaded(&unused));
+        ctx.class(&unused).unwrap();
+        assert!(ctx.is_loaded(&unused));
+        assert_eq!(ctx.loaded_library_classes().len(), supertypes.len() + 1);
+        assert!(matches!(
+            ctx.class(&ClassRef::new("org/mokapot/lib/Missing")),
+            Err(class_loader::Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn super_classes_on_demand() {
+        let ctx = context();
+        let super_classes = ctx
+            .super_classes(&ClassRef::new("org/mokapot/App"))
+            .unwrap();
+        assert_eq!(
+            super_classes,
+            [
+                ClassRef::new("org/mokapot/lib/Base"),
+                ClassRef::new("org/mokapot/lib/Root"),
+                ClassRef::new("java/lang/Object"),
+            ]
+        );
+        assert!(!ctx.is_loaded(&ClassRef::new("org/mokapot/lib/Unused")));
+    }
+
+    #[test]
+    fn warm_start_from_summaries() {
+        let ctx = context();
+        let lib = [("org/mokapot/lib/Unused", "org/mokapot/lib/Root")];
+        let summaries: Vec<_> = lib
+            .iter()
+            .map(|(name, super_class)| {
+                let class = Class::from_reader(empty_class_named(name, super_class).as_slice());
+                ClassSummary::from(&class.unwrap())
+            })
+            .collect();
+        ctx.add_summaries(&summaries);
+        let unused = ClassRef::new("org/mokapot/lib/Unused");
+        assert!(ctx
+            .subclasses(&ClassRef::new("org/mokapot/lib/Root"))
+            .contains(&unused));
+        assert!(!ctx.is_loaded(&unused));
+    }
+
+    #[test]
+    fn subclasses_include_application_classes() {
+        let ctx = context();
+        let root = ClassRef::new("org/mokapot/lib/Root");
+        assert_eq!(
+            ctx.subclasses(&root),
+            HashSet::from([
+                ClassRef::new("org/mokapot/lib/Base"),
+                ClassRef::new("org/mokapot/App"),
+            ])
+        );
+    }
+}
@@ -4,10 +4,34 @@ use std::collections::{HashMap, HashSet};
 
 use crate::{
     ir::{ClassHierarchy, InterfaceImplHierarchy},
-    jvm::{class_loader::ClassPath, references::ClassRef, Class},
+    jvm::{
+        class_loader::{self, ClassPath},
+        references::ClassRef,
+        Class, Module,
+    },
 };
 
+mod access;
+pub mod conflicts;
+mod dispatch;
 pub mod fixed_point;
+pub mod index;
+mod lazy;
+pub mod modules;
+pub mod overrides;
+mod resolution;
+mod sealed;
+pub mod services;
+mod subtyping;
+mod unresolved;
+
+pub use access::Member;
+pub use lazy::LazyResolutionContext;
+pub use resolution::ResolutionError;
+pub use sealed::{SealedIssue, SealedSubtypes};
+pub(crate) use subtyping::JAVA_LANG_OBJECT;
+pub use subtyping::{Conversions, UnknownClass};
+pub use unresolved::{FieldAccess, Invocation, Reference, ReferenceLocation, UnresolvedReference};
 
 /// A context for class resolution during analysis.
 #[derive(Debug)]
@@ -20,6 +44,8 @@ pub struct ResolutionContext {
     pub class_hierarchy: ClassHierarchy,
     /// The interface implementations.
     pub interface_implementations: InterfaceImplHierarchy,
+    load_failures: Vec<LoadFailure>,
+    modules: access::ModuleIndex,
 }
 
 /// A trait that can provide an exhaustive list of [`ClassRef`].
@@ -29,15 +55,84 @@ pub trait ClassRefs {
 }
 
 impl ResolutionContext {
-    /// Create a new resolution context.
+    /// Create a new resolution context, loading the classes sequentially.
+    ///
+    /// When a class is listed by more than one class path entry, the first one wins as in
+    /// [`ClassLoader::load_class`](crate::jvm::ClassLoader::load_class), and application classes
+    /// shadow the library classes with the same names in the class hierarchies.
+    ///
+    /// # Panics
+    /// Panics if any class listed by the class paths cannot be loaded.
+    /// Use [`ResolutionContext::try_new`] to handle such errors.
     #[must_use]
     pub fn new<P>(app_class_path: &[P], lib_class_path: &[P]) -> Self
     where
         P: ClassPath + ClassRefs,
     {
-        let application_classes = load_classes(app_class_path);
-        let library_classes = load_classes(lib_class_path);
-        let all_classes = application_classes.values().chain(library_classes.values());
+        let load = |listed: &ListedClasses<'_, P>| {
+            let (classes, failures) = load_listed_classes(&listed.classes);
+            if let Some(failure) = failures.into_iter().next() {
+                panic!("Class ref yielded by the class path must be found: {failure}");
+            }
+            classes
+        };
+        let app = listed_classes(app_class_path);
+        let lib = listed_classes(lib_class_path);
+        Self {
+            modules: access::ModuleIndex::from_modular_entries(
+                app.modular_entries.iter().chain(&lib.modular_entries),
+            ),
+            ..Self::from_classes(load(&app), load(&lib))
+        }
+    }
+
+    /// Create a new resolution context, loading the classes in parallel.
+    ///
+    /// The classes shadow each other in the same way as in [`ResolutionContext::new`].
+    /// The classes that cannot be loaded are handled according to `error_policy`.
+    /// With [`ErrorPolicy::Skip`], they are available from [`ResolutionContext::load_failures`].
+    ///
+    /// # Errors
+    /// [`InitError::LoadFailures`] if any class cannot be loaded with [`ErrorPolicy::Abort`].
+    pub fn try_new<P>(
+        app_class_path: &[P],
+        lib_class_path: &[P],
+        error_policy: ErrorPolicy,
+    ) -> Result<Self, InitError>
+    where
+        P: ClassPath + ClassRefs + Sync,
+    {
+        let app = listed_classes(app_class_path);
+        let lib = listed_classes(lib_class_path);
+        let (application_classes, mut load_failures) =
+            load_listed_classes_in_parallel(&app.classes);
+        let (library_classes, lib_failures) = load_listed_classes_in_parallel(&lib.classes);
+        load_failures.extend(lib_failures);
+        if error_policy == ErrorPolicy::Abort && !load_failures.is_empty() {
+            return Err(InitError::LoadFailures(load_failures));
+        }
+        Ok(Self {
+            load_failures,
+            modules: access::ModuleIndex::from_modular_entries(
+                app.modular_entries.iter().chain(&lib.modular_entries),
+            ),
+            ..Self::from_classes(application_classes, library_classes)
+        })
+    }
+
+    /// Creates a resolution context from the loaded classes.
+    /// Application classes shadow the library classes with the same names in the class
+    /// hierarchies.
+    pub(crate) fn from_classes(
+        application_classes: HashMap<ClassRef, Class>,
+        library_classes: HashMap<ClassRef, Class>,
+    ) -> Self {
+        let all_classes = application_classes.values().chain(
+            library_classes
+                .iter()
+                .filter(|(class_ref, _)| !application_classes.contains_key(class_ref))
+                .map(|(_, class)| class),
+        );
         let class_hierarchy = ClassHierarchy::from_classes(all_classes.clone());
         let interface_implementations = InterfaceImplHierarchy::from_classes(all_classes);
         Self {
@@ -45,28 +140,254 @@ impl ResolutionContext {
             library_classes,
             class_hierarchy,
             interface_implementations,
+            load_failures: Vec::new(),
+            modules: access::ModuleIndex::default(),
         }
     }
+
+    /// Returns the classes that are listed by the class paths but cannot be loaded, which is
+    /// empty unless the context is created with [`ErrorPolicy::Skip`].
+    #[must_use]
+    pub fn load_failures(&self) -> &[LoadFailure] {
+        &self.load_failures
+    }
+}
+
+/// How to handle the classes that cannot be loaded while creating a [`ResolutionContext`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Fails the creation if any class cannot be loaded.
+    #[default]
+    Abort,
+    /// Skips the classes that cannot be loaded.
+    Skip,
+}
+
+/// A class that cannot be loaded.
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to load {class_ref}: {error}")]
+pub struct LoadFailure {
+    /// The class listed by the class path.
+    pub class_ref: ClassRef,
+    /// The error occurred while loading the class.
+    #[source]
+    pub error: class_loader::Error,
 }
 
 /// An error that occurs during initialization of a [`ResolutionContext`].
-#[derive(Debug, derive_more::Display)]
-pub enum InitError {}
+#[derive(Debug, thiserror::Error)]
+pub enum InitError {
+    /// Some classes cannot be loaded.
+    #[error("Failed to load {} classes", .0.len())]
+    LoadFailures(Vec<LoadFailure>),
+}
+
+/// The classes listed by a class path.
+struct ListedClasses<'a, P> {
+    /// The classes together with the entries defining them.
+    /// When a class is listed by more than one entry, only the first one is kept.
+    classes: Vec<(&'a P, ClassRef)>,
+    /// The entries listing a `module-info` class together with the packages of their classes.
+    modular_entries: Vec<(&'a P, Vec<String>)>,
+}
+
+/// Lists the classes in a class path, enumerating each entry once.
+fn listed_classes<P: ClassRefs>(class_path: &[P]) -> ListedClasses<'_, P> {
+    let mut seen = HashSet::new();
+    let mut listed = ListedClasses {
+        classes: Vec::new(),
+        modular_entries: Vec::new(),
+    };
+    for cp in class_path {
+        let mut class_refs: Vec<_> = cp.class_refs().into_iter().collect();
+        class_refs.sort_unstable();
+        if class_refs
+            .iter()
+            .any(|it| it.binary_name == Module::INFO_CLASS_NAME)
+        {
+            let packages = class_refs
+                .iter()
+                .filter(|it| it.binary_name != Module::INFO_CLASS_NAME)
+                .map(|it| it.package().to_owned())
+                .collect();
+            listed.modular_entries.push((cp, packages));
+        }
+        listed.classes.extend(
+            class_refs
+                .into_iter()
+                .filter(|it| seen.insert(it.clone()))
+                .map(|it| (cp, it)),
+        );
+    }
+    listed
+}
+
+fn load_listed_class<P: ClassPath>(
+    class_path: &P,
+    class_ref: &ClassRef,
+) -> Result<Class, LoadFailure> {
+    class_path
+        .find_class(&class_ref.binary_name)
+        .map_err(|error| LoadFailure {
+            class_ref: class_ref.clone(),
+            error,
+        })
+}
+
+fn partition_loaded(
+    results: impl IntoIterator<Item = Result<Class, LoadFailure>>,
+) -> (HashMap<ClassRef, Class>, Vec<LoadFailure>) {
+    let mut classes = HashMap::new();
+    let mut failures = Vec::new();
+    for result in results {
+        match result {
+            Ok(class) => {
+                classes.insert(class.as_ref(), class);
+            }
+            Err(failure) => failures.push(failure),
+        }
+    }
+    (classes, failures)
+}
 
-fn load_classes<P>(class_path: &[P]) -> HashMap<ClassRef, Class>
+/// Loads the classes listed by the class path one by one.
+fn load_classes<P>(class_path: &[P]) -> (HashMap<ClassRef, Class>, Vec<LoadFailure>)
 where
     P: ClassPath + ClassRefs,
 {
-    class_path
-        .iter()
-        .flat_map(|cp| {
-            cp.class_refs()
-                .into_iter()
-                .map(|cr| {
-                    cp.find_class(&cr.binary_name)
-                        .expect("Class ref yielded by the class path must be found.")
+    load_listed_classes(&listed_classes(class_path).classes)
+}
+
+fn load_listed_classes<P: ClassPath>(
+    listed: &[(&P, ClassRef)],
+) -> (HashMap<ClassRef, Class>, Vec<LoadFailure>) {
+    partition_loaded(
+        listed
+            .iter()
+            .map(|(cp, class_ref)| load_listed_class(*cp, class_ref)),
+    )
+}
+
+/// Loads the classes listed by the class path in parallel.
+fn load_classes_in_parallel<P>(class_path: &[P]) -> (HashMap<ClassRef, Class>, Vec<LoadFailure>)
+where
+    P: ClassPath + ClassRefs + Sync,
+{
+    load_listed_classes_in_parallel(&listed_classes(class_path).classes)
+}
+
+fn load_listed_classes_in_parallel<P: ClassPath + Sync>(
+    work_items: &[(&P, ClassRef)],
+) -> (HashMap<ClassRef, Class>, Vec<LoadFailure>) {
+    let workers = std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+    let chunk_size = work_items.len().div_ceil(workers).max(1);
+    let results: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = work_items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(|| {
+                    chunk
+                        .iter()
+                        .map(|(cp, class_ref)| load_listed_class(*cp, class_ref))
+                        .collect::<Vec<_>>()
                 })
-                .map(|it| (it.as_ref(), it))
-        })
-        .collect()
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|it| it.join().expect("Loading classes should not panic"))
+            .collect()
+    });
+    partition_loaded(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{jvm::class_loader::class_paths::InMemoryClassPath, tests::empty_class_named};
+
+    use super::*;
+
+    fn class_paths() -> [InMemoryClassPath; 2] {
+        let app = [
+            (
+                "org/mokapot/App".to_owned(),
+                empty_class_named("org/mokapot/App", "org/mokapot/Lib"),
+            ),
+            ("org/mokapot/Broken".to_owned(), vec![0xCA, 0xFE]),
+        ];
+        let lib = [
+            (
+                "org/mokapot/Lib".to_owned(),
+                empty_class_named("org/mokapot/Lib", "java/lang/Object"),
+            ),
+            (
+                "org/mokapot/App".to_owned(),
+                empty_class_named("org/mokapot/App", "java/lang/Object"),
+            ),
+        ];
+        [app.into_iter().collect(), lib.into_iter().collect()]
+    }
+
+    #[test]
+    fn abort_on_load_failure() {
+        let [app, lib] = class_paths();
+        let result = ResolutionContext::try_new(&[app], &[lib], ErrorPolicy::Abort);
+        let Err(InitError::LoadFailures(failures)) = result else {
+            panic!("Expected load failures");
+        };
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].class_ref, ClassRef::new("org/mokapot/Broken"));
+        assert!(matches!(
+            failures[0].error,
+            class_loader::Error::Malformed(_)
+        ));
+    }
+
+    #[test]
+    fn skip_load_failure() {
+        let [app, lib] = class_paths();
+        let ctx = ResolutionContext::try_new(&[app], &[lib], ErrorPolicy::Skip).unwrap();
+        assert_eq!(ctx.load_failures().len(), 1);
+        assert_eq!(ctx.application_classes.len(), 1);
+        assert_eq!(ctx.library_classes.len(), 2);
+        assert!(ctx
+            .class_hierarchy
+            .super_classes(&ClassRef::new("org/mokapot/App"))
+            .contains(&ClassRef::new("org/mokapot/Lib")));
+    }
+
+    #[test]
+    fn first_class_path_entry_wins() {
+        let [app, lib] = class_paths();
+        let ctx = ResolutionContext::try_new(&[], &[app, lib], ErrorPolicy::Skip).unwrap();
+        let app_class = &ctx.library_classes[&ClassRef::new("org/mokapot/App")];
+        assert_eq!(
+            app_class.super_class,
+            Some(ClassRef::new("org/mokapot/Lib"))
+        );
+    }
+
+    #[test]
+    fn new_shadows_like_try_new() {
+        let [_, lib] = class_paths();
+        let app: InMemoryClassPath = [(
+            "org/mokapot/App".to_owned(),
+            empty_class_named("org/mokapot/App", "org/mokapot/Lib"),
+        )]
+        .into_iter()
+        .collect();
+        let ctx = ResolutionContext::new(std::slice::from_ref(&app), &[lib, app.clone()]);
+        let app_ref = ClassRef::new("org/mokapot/App");
+        // The first library class path entry wins among the library classes.
+        assert_eq!(
+            ctx.library_classes[&app_ref].super_class,
+            Some(ClassRef::new("java/lang/Object"))
+        );
+        // The application class shadows the library class in the class hierarchy.
+        assert!(ctx
+            .class_hierarchy
+            .super_classes(&app_ref)
+            .contains(&ClassRef::new("org/mokapot/Lib")));
+        assert!(ctx.load_failures().is_empty());
+    }
 }
@@ -0,0 +1,854 @@
+//! Resolution of the module graph of the Java Platform Module System.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::jvm::{
+    class_loader::{self, manifest::Manifest, manifest::MANIFEST_PATH, ClassPath},
+    module::{Flags, RequireFlags},
+    Class, Module,
+};
+
+#[cfg(feature = "jimage")]
+use crate::jvm::class_loader::class_paths::JrtImageClassPath;
+
+use super::ClassRefs;
+
+const AUTOMATIC_MODULE_NAME: &str = "Automatic-Module-Name";
+
+/// The keywords and literals that cannot be used as a component of a module name.
+const RESERVED_WORDS: [&str; 54] = [
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "try",
+    "void",
+    "volatile",
+    "while",
+    "true",
+    "false",
+    "null",
+    "_",
+];
+
+/// The kind of a module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleKind {
+    /// A module declared by a `module-info` class.
+    Explicit,
+    /// A JAR on the module path without a `module-info` class.
+    Automatic,
+}
+
+/// A module found on the module path, before resolution.
+#[derive(Debug, Clone)]
+pub struct ObservableModule {
+    /// The name of the module.
+    pub name: String,
+    /// The kind of the module.
+    pub kind: ModuleKind,
+    /// The binary names of the packages in the module (e.g., `org/mokapot`).
+    pub packages: BTreeSet<String>,
+    /// The module declaration of an explicit module.
+    pub descriptor: Option<Module>,
+}
+
+impl ObservableModule {
+    /// Creates an explicit module from a `module-info` class and the packages containing the
+    /// classes of the module.
+    /// The packages listed in the `ModulePackages` attribute are also included.
+    /// Returns `None` if the class does not declare a module.
+    #[must_use]
+    pub fn explicit(
+        module_info: &Class,
+        packages: impl IntoIterator<Item = String>,
+    ) -> Option<Self> {
+        let descriptor = module_info.module.clone()?;
+        let mut packages: BTreeSet<_> = packages.into_iter().collect();
+        packages.extend(
+            module_info
+                .module_packages
+                .iter()
+                .map(|it| it.binary_name.clone()),
+        );
+        Some(Self {
+            name: descriptor.name.clone(),
+            kind: ModuleKind::Explicit,
+            packages,
+            descriptor: Some(descriptor),
+        })
+    }
+
+    /// Creates an automatic module.
+    #[must_use]
+    pub fn automatic(name: impl Into<String>, packages: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            name: name.into(),
+            kind: ModuleKind::Automatic,
+            packages: packages.into_iter().collect(),
+            descriptor: None,
+        }
+    }
+
+    /// Creates a module from a module path entry.
+    /// The entry is an explicit module if it contains a `module-info` class, or an automatic
+    /// module otherwise.
+    /// The name of an automatic module is taken from the `Automatic-Module-Name` attribute in
+    /// its manifest, or derived from `file_name` (see [`automatic_module_name`]).
+    ///
+    /// # Errors
+    /// - [`ModuleError::UnnamedAutomaticModule`] if the name of an automatic module cannot be
+    ///   determined.
+    /// - [`ModuleError::Load`] if the `module-info` class or the manifest cannot be read.
+    pub fn from_class_path<P>(entry: &P, file_name: Option<&str>) -> Result<Self, ModuleError>
+    where
+        P: ClassPath + ClassRefs,
+    {
+        let packages = entry
+            .class_refs()
+            .into_iter()
+            .filter(|it| it.binary_name != Module::INFO_CLASS_NAME)
+            .map(|it| it.package().to_owned())
+            .filter(|it| !it.is_empty());
+        match entry.find_class(Module::INFO_CLASS_NAME) {
+            Ok(module_info) => {
+                return Self::explicit(&module_info, packages).ok_or(ModuleError::Load(
+                    class_loader::Error::Other("module-info does not declare a module".into()),
+                ));
+            }
+            Err(class_loader::Error::NotFound) => {}
+            Err(err) => return Err(ModuleError::Load(err)),
+        }
+        let manifest = match entry.find_resource(MANIFEST_PATH) {
+            Ok(bytes) => Some(Manifest::parse(&String::from_utf8_lossy(&bytes))),
+            Err(class_loader::Error::NotFound) => None,
+            Err(err) => return Err(ModuleError::Load(err)),
+        };
+        let name = manifest
+            .as_ref()
+            .and_then(|it| it.main_attribute(AUTOMATIC_MODULE_NAME))
+            .map(|it| it.trim().to_owned())
+            .or_else(|| file_name.and_then(automatic_module_name))
+            .ok_or_else(|| ModuleError::UnnamedAutomaticModule(file_name.map(str::to_owned)))?;
+        Ok(Self::automatic(name, packages))
+    }
+
+    /// Creates the modules in a run-time image (i.e., `lib/modules`).
+    ///
+    /// # Errors
+    /// [`ModuleError::Load`] if a `module-info` class cannot be read.
+    #[cfg(feature = "jimage")]
+    pub fn from_jimage(image: &JrtImageClassPath) -> Result<Vec<Self>, ModuleError> {
+        image
+            .modules()
+            .into_iter()
+            .map(|module| {
+                let module_info = image.find_module_info(module)?;
+                let packages = image.packages_of(module).into_iter().map(str::to_owned);
+                Self::explicit(&module_info, packages).ok_or(ModuleError::Load(
+                    class_loader::Error::Other("module-info does not declare a module".into()),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Derives the name of an automatic module from the name of its JAR file in the way of
+/// `java.lang.module.ModuleFinder::of`.
+/// The `.jar` extension and the version (starting from a hyphen followed by a digit) are
+/// removed, and the characters other than `[A-Za-z0-9]` are replaced with dots.
+/// For example, `foo-bar-1.2.3.jar` is named `foo.bar`.
+///
+/// Returns `None` if the derived name is not a valid module name, e.g., when a component
+/// starts with a digit or is a Java keyword.
+#[must_use]
+pub fn automatic_module_name(file_name: &str) -> Option<String> {
+    let file_name = file_name.rsplit(['/', '\\']).next().unwrap_or(file_name);
+    let mut name = file_name.strip_suffix(".jar").unwrap_or(file_name);
+    let bytes = name.as_bytes();
+    let version_start = (0..bytes.len()).find(|&idx| {
+        if bytes[idx] != b'-' {
+            return false;
+        }
+        let digits = bytes[idx + 1..]
+            .iter()
+            .take_while(|it| it.is_ascii_digit())
+            .count();
+        digits > 0 && matches!(bytes.get(idx + 1 + digits), None | Some(b'.'))
+    });
+    if let Some(version_start) = version_start {
+        name = &name[..version_start];
+    }
+    let replaced: String = name
+        .chars()
+        .map(|it| if it.is_ascii_alphanumeric() { it } else { '.' })
+        .collect();
+    let components: Vec<_> = replaced.split('.').filter(|it| !it.is_empty()).collect();
+    let is_valid = !components.is_empty()
+        && components.iter().all(|it| {
+            !it.starts_with(|c: char| c.is_ascii_digit()) && !RESERVED_WORDS.contains(it)
+        });
+    is_valid.then(|| components.join("."))
+}
+
+/// A module in a resolved [`ModuleGraph`].
+#[derive(Debug, Clone)]
+pub struct ResolvedModule {
+    /// The name of the module.
+    pub name: String,
+    /// The kind of the module.
+    pub kind: ModuleKind,
+    /// The binary names of the packages in the module.
+    pub packages: BTreeSet<String>,
+    /// The module declaration of an explicit module.
+    pub descriptor: Option<Module>,
+    /// The names of the modules read by the module, including itself.
+    /// The unnamed module is not included.
+    pub reads: BTreeSet<String>,
+}
+
+/// The resolved modules and the readability relation between them.
+///
+/// The module graph answers whether the code in a module can access the public types in a
+/// package of another module, which requires the former to read the latter and the latter to
+/// export the package to the former.
+/// The classes on the class path are in the unnamed module, which reads all modules and
+/// exports all its packages; the unnamed module is denoted by `None` in the queries.
+#[derive(Debug, Clone)]
+pub struct ModuleGraph {
+    modules: BTreeMap<String, ResolvedModule>,
+    package_modules: HashMap<String, String>,
+}
+
+/// An error that occurs while resolving a module graph.
+#[derive(Debug, thiserror::Error)]
+pub enum ModuleError {
+    /// A module required by another module, or a root module, is not observable.
+    #[error("Module {required} required by {module} is not found")]
+    MissingModule {
+        /// The name of the requiring module, which is empty for a root module.
+        module: String,
+        /// The name of the required module.
+        required: String,
+    },
+    /// A package is contained in more than one resolved module.
+    #[error("Package {package} is in both {first} and {second}")]
+    SplitPackage {
+        /// The binary name of the package.
+        package: String,
+        /// The name of a module containing the package.
+        first: String,
+        /// The name of another module containing the package.
+        second: String,
+    },
+    /// The `requires` directives of the resolved modules form a cycle.
+    #[error("Cycle detected: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+    /// The name of an automatic module cannot be determined.
+    #[error("Cannot derive the name of the automatic module {0:?}")]
+    UnnamedAutomaticModule(Option<String>),
+    /// A module cannot be read from the module path.
+    #[error("Failed to read module: {0}")]
+    Load(#[from] class_loader::Error),
+}
+
+impl ModuleGraph {
+    /// Resolves the modules required by the root modules, directly or indirectly, among the
+    /// observable modules.
+    /// When more than one observable module has the same name, the first one is used as on a
+    /// module path.
+    /// - `requires static` does not cause the required module to be resolved, but the
+    ///   requiring module reads it if it is resolved.
+    /// - A missing mandated dependence (i.e., `java.base` when the run-time image is not
+    ///   given) is ignored.
+    /// - If any automatic module is resolved, all the observable automatic modules are
+    ///   resolved, and every module reading an automatic module reads all of them.
+    ///
+    /// # Errors
+    /// - [`ModuleError::MissingModule`] if a root module or a required module is missing.
+    /// - [`ModuleError::Cycle`] if the `requires` directives form a cycle.
+    /// - [`ModuleError::SplitPackage`] if a package is in more than one resolved module.
+    pub fn resolve<'a>(
+        observable: impl IntoIterator<Item = ObservableModule>,
+        roots: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, ModuleError> {
+        let mut candidates: BTreeMap<String, ObservableModule> = BTreeMap::new();
+        for module in observable {
+            candidates.entry(module.name.clone()).or_insert(module);
+        }
+        let resolved = Self::resolve_names(&candidates, roots)?;
+        detect_cycle(&candidates, &resolved)?;
+
+        let automatic: BTreeSet<_> = resolved
+            .iter()
+            .filter(|it| candidates[*it].kind == ModuleKind::Automatic)
+            .cloned()
+            .collect();
+        let mut modules = BTreeMap::new();
+        for name in &resolved {
+            let module = &candidates[name];
+            let mut reads = BTreeSet::from([name.clone()]);
+            match &module.descriptor {
+                None => reads.extend(resolved.iter().cloned()),
+                Some(descriptor) => {
+                    for require in &descriptor.requires {
+                        let required = &require.module.name;
+                        if !resolved.contains(required) {
+                            continue;
+                        }
+                        reads.insert(required.clone());
+                        reads.extend(transitive_reads(&candidates, &resolved, required));
+                    }
+                }
+            }
+            if reads.iter().any(|it| automatic.contains(it)) {
+                reads.extend(automatic.iter().cloned());
+            }
+            modules.insert(
+                name.clone(),
+                ResolvedModule {
+                    name: name.clone(),
+                    kind: module.kind,
+                    packages: module.packages.clone(),
+                    descriptor: module.descriptor.clone(),
+                    reads,
+                },
+            );
+        }
+
+        let mut package_modules: HashMap<String, String> = HashMap::new();
+        for module in modules.values() {
+            for package in &module.packages {
+                if let Some(first) = package_modules.insert(package.clone(), module.name.clone()) {
+                    return Err(ModuleError::SplitPackage {
+                        package: package.clone(),
+                        first,
+                        second: module.name.clone(),
+                    });
+                }
+            }
+        }
+        Ok(Self {
+            modules,
+            package_modules,
+        })
+    }
+
+    /// Resolves all the observable modules.
+    ///
+    /// # Errors
+    /// See [`ModuleGraph::resolve`].
+    pub fn resolve_all(
+        observable: impl IntoIterator<Item = ObservableModule>,
+    ) -> Result<Self, ModuleError> {
+        let observable: Vec<_> = observable.into_iter().collect();
+        let names: Vec<_> = observable.iter().map(|it| it.name.clone()).collect();
+        Self::resolve(observable, names.iter().map(String::as_str))
+    }
+
+    /// Finds the names of the modules to resolve.
+    fn resolve_names<'a>(
+        candidates: &BTreeMap<String, ObservableModule>,
+        roots: impl IntoIterator<Item = &'a str>,
+    ) -> Result<BTreeSet<String>, ModuleError> {
+        let mut resolved = BTreeSet::new();
+        let mut queue: Vec<(String, String)> = roots
+            .into_iter()
+            .map(|it| (String::new(), it.to_owned()))
+            .collect();
+        let mut has_automatic = false;
+        while let Some((requiring, name)) = queue.pop() {
+            if resolved.contains(&name) {
+                continue;
+            }
+            let module = candidates
+                .get(&name)
+                .ok_or_else(|| ModuleError::MissingModule {
+                    module: requiring,
+                    required: name.clone(),
+                })?;
+            if module.kind == ModuleKind::Automatic && !has_automatic {
+                has_automatic = true;
+                queue.extend(
+                    candidates
+                        .values()
+                        .filter(|it| it.kind == ModuleKind::Automatic)
+                        .map(|it| (name.clone(), it.name.clone())),
+                );
+            }
+            for require in module.descriptor.iter().flat_map(|it| &it.requires) {
+                let is_missing = !candidates.contains_key(&require.module.name);
+                if require.flags.contains(RequireFlags::STATIC_PHASE)
+                    || (is_missing && require.flags.contains(RequireFlags::MANDATED))
+                {
+                    continue;
+                }
+                queue.push((name.clone(), require.module.name.clone()));
+            }
+            resolved.insert(name);
+        }
+        Ok(resolved)
+    }
+
+    /// Gets a resolved module by its name.
+    #[must_use]
+    pub fn module(&self, name: &str) -> Option<&ResolvedModule> {
+        self.modules.get(name)
+    }
+
+    /// Returns the resolved modules sorted by their names.
+    pub fn modules(&self) -> impl Iterator<Item = &ResolvedModule> {
+        self.modules.values()
+    }
+
+    /// Finds the module containing a package, or `None` if the package is in the unnamed
+    /// module.
+    #[must_use]
+    pub fn module_of_package(&self, package: &str) -> Option<&ResolvedModule> {
+        self.package_modules
+            .get(package)
+            .and_then(|it| self.modules.get(it))
+    }
+
+    /// Checks whether module `from` reads module `to`.
+    /// Every module reads itself, and the unnamed module reads all modules, while named modules
+    /// other than automatic modules do not read the unnamed module.
+    #[must_use]
+    pub fn reads(&self, from: Option<&str>, to: Option<&str>) -> bool {
+        match (from, to) {
+            (None, _) => true,
+            (Some(from), None) => self
+                .modules
+                .get(from)
+                .is_some_and(|it| it.kind == ModuleKind::Automatic),
+            (Some(from), Some(to)) => self
+                .modules
+                .get(from)
+                .is_some_and(|it| it.reads.contains(to)),
+        }
+    }
+
+    /// Checks whether `module` exports `package` to module `to`, either unqualified or
+    /// qualified to `to`.
+    /// Automatic modules and the unnamed module export all their packages.
+    #[must_use]
+    pub fn is_exported(&self, module: Option<&str>, package: &str, to: Option<&str>) -> bool {
+        self.is_visible(module, package, to, |descriptor| {
+            descriptor
+                .exports
+                .iter()
+                .filter(|it| it.package.binary_name == package)
+                .map(|it| it.to.iter().map(|to| to.name.as_str()).collect())
+                .collect()
+        })
+    }
+
+    /// Checks whether `module` opens `package` to module `to` for deep reflection, either by
+    /// being an open module or by an unqualified or a qualified `opens` directive.
+    /// Automatic modules and the unnamed module open all their packages.
+    #[must_use]
+    pub fn is_opened(&self, module: Option<&str>, package: &str, to: Option<&str>) -> bool {
+        self.is_visible(module, package, to, |descriptor| {
+            if descriptor.flags.contains(Flags::OPEN) {
+                return vec![Vec::new()];
+            }
+            descriptor
+                .opens
+                .iter()
+                .filter(|it| it.package.binary_name == package)
+                .map(|it| it.to.iter().map(|to| to.name.as_str()).collect())
+                .collect()
+        })
+    }
+
+    /// Checks whether the code in module `from` can access the public types in `package` of
+    /// module `to`, i.e., `from` reads `to` and `to` exports `package` to `from`.
+    #[must_use]
+    pub fn can_access(&self, from: Option<&str>, to: Option<&str>, package: &str) -> bool {
+        from == to || (self.reads(from, to) && self.is_exported(to, package, from))
+    }
+
+    /// Checks whether the code in module `from` can reflectively access all the members of the
+    /// types in `package` of module `to`, i.e., `from` reads `to` and `to` opens `package` to
+    /// `from`.
+    #[must_use]
+    pub fn can_reflect(&self, from: Option<&str>, to: Option<&str>, package: &str) -> bool {
+        from == to || (self.reads(from, to) && self.is_opened(to, package, from))
+    }
+
+    /// Checks whether a package of a module is visible to another module, given a function
+    /// returning the target modules of each matching directive, where an empty list means an
+    /// unqualified directive.
+    fn is_visible<'g>(
+        &'g self,
+        module: Option<&str>,
+        package: &str,
+        to: Option<&str>,
+        directives: impl FnOnce(&'g Module) -> Vec<Vec<&'g str>>,
+    ) -> bool {
+        let Some(module) = module.and_then(|it| self.modules.get(it)) else {
+            return module.is_none();
+        };
+        if !module.packages.contains(package) {
+            return false;
+        }
+        let Some(descriptor) = &module.descriptor else {
+            return true;
+        };
+        if Some(module.name.as_str()) == to {
+            return true;
+        }
+        directives(descriptor)
+            .iter()
+            .any(|targets| targets.is_empty() || to.is_some_and(|to| targets.contains(&to)))
+    }
+}
+
+/// Returns the modules read by a module reading `module` through `requires transitive`.
+fn transitive_reads(
+    candidates: &BTreeMap<String, ObservableModule>,
+    resolved: &BTreeSet<String>,
+    module: &str,
+) -> BTreeSet<String> {
+    let mut reads = BTreeSet::new();
+    let mut stack = vec![module.to_owned()];
+    while let Some(current) = stack.pop() {
+        let directives = candidates
+            .get(&current)
+            .and_then(|it| it.descriptor.as_ref())
+            .into_iter()
+            .flat_map(|it| &it.requires);
+        for require in directives {
+            let required = &require.module.name;
+            if require.flags.contains(RequireFlags::TRANSITIVE)
+                && resolved.contains(required)
+                && reads.insert(required.clone())
+            {
+                stack.push(required.clone());
+            }
+        }
+    }
+    reads
+}
+
+/// Detects a cycle in the `requires` directives of the resolved modules.
+fn detect_cycle(
+    candidates: &BTreeMap<String, ObservableModule>,
+    resolved: &BTreeSet<String>,
+) -> Result<(), ModuleError> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Visiting,
+        Done,
+    }
+    fn visit<'a>(
+        name: &'a str,
+        candidates: &'a BTreeMap<String, ObservableModule>,
+        resolved: &BTreeSet<String>,
+        states: &mut HashMap<&'a str, State>,
+        path: &mut Vec<&'a str>,
+    ) -> Result<(), ModuleError> {
+        match states.get(name) {
+            Some(State::Done) => return Ok(()),
+            Some(State::Visiting) => {
+                let start = path.iter().position(|it| *it == name).unwrap_or(0);
+                let mut cycle: Vec<_> = path[start..].iter().map(|it| (*it).to_owned()).collect();
+                cycle.push(name.to_owned());
+                return Err(ModuleError::Cycle(cycle));
+            }
+            None => {}
+        }
+        states.insert(name, State::Visiting);
+        path.push(name);
+        let requires = candidates[name]
+            .descriptor
+            .iter()
+            .flat_map(|it| &it.requires)
+            .map(|it| it.module.name.as_str())
+            .filter(|it| resolved.contains(*it));
+        for required in requires {
+            visit(required, candidates, resolved, states, path)?;
+        }
+        path.pop();
+        states.insert(name, State::Done);
+        Ok(())
+    }
+
+    let mut states = HashMap::new();
+    for name in resolved {
+        visit(name, candidates, resolved, &mut states, &mut Vec::new())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        jvm::{
+            module::{Open, OpenFlags, Require},
+            references::{ModuleRef, PackageRef},
+        },
+        tests::module_info,
+    };
+
+    use super::*;
+
+    fn explicit(
+        name: &str,
+        packages: &[&str],
+        requires: &[(&str, RequireFlags)],
+    ) -> ObservableModule {
+        let mut class = module_info(name, packages, &[(packages[0], &[])]);
+        let descriptor = class.module.as_mut().unwrap();
+        descriptor.requires = requires
+            .iter()
+            .map(|(module, flags)| Require {
+                module: ModuleRef {
+                    name: (*module).to_owned(),
+                },
+                flags: *flags,
+                version: None,
+            })
+            .collect();
+        ObservableModule::explicit(&class, []).unwrap()
+    }
+
+    fn packages(packages: &[&str]) -> Vec<String> {
+        packages.iter().map(|it| (*it).to_owned()).collect()
+    }
+
+    fn observable() -> Vec<ObservableModule> {
+        let mut lib = explicit(
+            "org.lib",
+            &["org/lib/api", "org/lib/internal"],
+            &[("org.base", RequireFlags::TRANSITIVE)],
+        );
+        let descriptor = lib.descriptor.as_mut().unwrap();
+        descriptor.exports.push(crate::jvm::module::Export {
+            package: PackageRef {
+                binary_name: "org/lib/internal".to_owned(),
+            },
+            flags: crate::jvm::module::ExportFlags::empty(),
+            to: vec![ModuleRef {
+                name: "org.app".to_owned(),
+            }],
+        });
+        descriptor.opens.push(Open {
+            package: PackageRef {
+                binary_name: "org/lib/internal".to_owned(),
+            },
+            flags: OpenFlags::empty(),
+            to: Vec::new(),
+        });
+        vec![
+            explicit("java.base", &["java/lang"], &[]),
+            explicit(
+                "org.base",
+                &["org/base"],
+                &[("java.base", RequireFlags::MANDATED)],
+            ),
+            lib,
+            explicit(
+                "org.app",
+                &["org/app"],
+                &[
+                    ("org.lib", RequireFlags::empty()),
+                    ("org.optional", RequireFlags::STATIC_PHASE),
+                    ("guava", RequireFlags::empty()),
+                ],
+            ),
+            explicit("org.other", &["org/other"], &[]),
+            ObservableModule::automatic("guava", packages(&["com/google/common"])),
+            ObservableModule::automatic("commons.io", packages(&["org/apache/commons/io"])),
+        ]
+    }
+
+    #[test]
+    fn resolve_from_roots() {
+        let graph = ModuleGraph::resolve(observable(), ["org.app"]).unwrap();
+        let names: Vec<_> = graph.modules().map(|it| it.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "commons.io",
+                "guava",
+                "java.base",
+                "org.app",
+                "org.base",
+                "org.lib"
+            ]
+        );
+        assert_eq!(
+            graph
+                .module_of_package("org/lib/api")
+                .map(|it| it.name.as_str()),
+            Some("org.lib")
+        );
+        assert!(graph.module_of_package("org/other").is_none());
+    }
+
+    #[test]
+    fn readability() {
+        let graph = ModuleGraph::resolve(observable(), ["org.app"]).unwrap();
+        let app = Some("org.app");
+        assert!(graph.reads(app, Some("org.lib")));
+        // Through `requires transitive`.
+        assert!(graph.reads(app, Some("org.base")));
+        assert!(!graph.reads(app, Some("java.base")));
+        assert!(graph.reads(Some("org.base"), Some("java.base")));
+        // Reading an automatic module implies reading all of them.
+        assert!(graph.reads(app, Some("commons.io")));
+        assert!(graph.reads(Some("guava"), Some("org.app")));
+        assert!(graph.reads(Some("guava"), None));
+        assert!(!graph.reads(app, None));
+        assert!(graph.reads(None, app));
+    }
+
+    #[test]
+    fn access() {
+        let graph = ModuleGraph::resolve(observable(), ["org.app"]).unwrap();
+        let (app, lib) = (Some("org.app"), Some("org.lib"));
+        assert!(graph.can_access(app, lib, "org/lib/api"));
+        assert!(graph.can_access(app, lib, "org/lib/internal"));
+        assert!(graph.can_access(None, lib, "org/lib/api"));
+        assert!(!graph.can_access(None, lib, "org/lib/internal"));
+        assert!(graph.can_reflect(None, lib, "org/lib/internal"));
+        assert!(!graph.can_reflect(app, lib, "org/lib/api"));
+        assert!(graph.can_access(app, Some("guava"), "com/google/common"));
+        assert!(graph.can_access(Some("guava"), None, "org/other"));
+        assert!(!graph.can_access(Some("org.base"), lib, "org/lib/api"));
+    }
+
+    #[test]
+    fn resolution_errors() {
+        let result = ModuleGraph::resolve(observable(), ["org.missing"]);
+        assert!(matches!(
+            result,
+            Err(ModuleError::MissingModule { required, .. }) if required == "org.missing"
+        ));
+
+        let mut modules = observable();
+        modules.push(explicit("org.split", &["org/lib/api"], &[]));
+        let result = ModuleGraph::resolve_all(modules);
+        assert!(matches!(
+            result,
+            Err(ModuleError::SplitPackage { package, .. }) if package == "org/lib/api"
+        ));
+
+        let modules = [
+            explicit("org.a", &["org/a"], &[("org.b", RequireFlags::empty())]),
+            explicit("org.b", &["org/b"], &[("org.a", RequireFlags::empty())]),
+        ];
+        let Err(ModuleError::Cycle(cycle)) = ModuleGraph::resolve_all(modules) else {
+            panic!("Expected a cycle");
+        };
+        assert_eq!(cycle, ["org.a", "org.b", "org.a"]);
+    }
+
+    #[test]
+    fn automatic_module_names() {
+        assert_eq!(
+            automatic_module_name("foo-bar-1.2.3-SNAPSHOT.jar").as_deref(),
+            Some("foo.bar")
+        );
+        assert_eq!(
+            automatic_module_name("/libs/commons_io-2.jar").as_deref(),
+            Some("commons.io")
+        );
+        assert_eq!(
+            automatic_module_name("jsr305-3.0.2.jar").as_deref(),
+            Some("jsr305")
+        );
+        assert_eq!(automatic_module_name("x-1a.jar"), None);
+        assert_eq!(automatic_module_name("1-2.jar"), None);
+        assert_eq!(
+            automatic_module_name("caf\u{e9}-lib.jar").as_deref(),
+            Some("caf.lib")
+        );
+        assert_eq!(automatic_module_name("foo-native-1.0.jar"), None);
+        assert_eq!(automatic_module_name("new.jar"), None);
+    }
+
+    #[cfg(feature = "jar")]
+    #[test]
+    fn automatic_module_from_jar() {
+        use crate::{
+            jvm::class_loader::class_paths::JarClassPath,
+            tests::{empty_class_named, jar_with_entries},
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let class = empty_class_named("org/mokapot/Test", "java/lang/Object");
+        let named = dir.path().join("named-1.0.jar");
+        let manifest = b"Manifest-Version: 1.0\r\nAutomatic-Module-Name: org.mokapot\r\n\r\n";
+        std::fs::write(
+            &named,
+            jar_with_entries(&[
+                (MANIFEST_PATH, manifest),
+                ("org/mokapot/Test.class", &class),
+            ]),
+        )
+        .unwrap();
+        let module =
+            ObservableModule::from_class_path(&JarClassPath::new(&named), Some("named-1.0.jar"))
+                .unwrap();
+        assert_eq!(module.name, "org.mokapot");
+        assert_eq!(module.kind, ModuleKind::Automatic);
+        assert_eq!(module.packages, BTreeSet::from(["org/mokapot".to_owned()]));
+
+        let unnamed = dir.path().join("mokapot-core-0.17.jar");
+        std::fs::write(
+            &unnamed,
+            jar_with_entries(&[("org/mokapot/Test.class", &class)]),
+        )
+        .unwrap();
+        let module = ObservableModule::from_class_path(
+            &JarClassPath::new(&unnamed),
+            Some("mokapot-core-0.17.jar"),
+        )
+        .unwrap();
+        assert_eq!(module.name, "mokapot.core");
+    }
+}
@@ -0,0 +1,413 @@
+//! The override relation between methods.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    jvm::{code::Instruction, method::AccessFlags, references::MethodRef, Class, Method},
+    macros::see_jvm_spec,
+};
+
+use super::{ResolutionContext, UnknownClass};
+
+/// An index of the methods overridden by each method, and the methods overriding it.
+///
+/// An instance method overrides the methods with the same name and descriptor declared in its
+/// supertypes, following the rules in the JVM specification:
+/// - Private, static and final methods, constructors and class initializers are never
+///   overridden, and private and static methods never override.
+/// - A package-private method is only overridden by methods in the same package, or
+///   transitively through a method in its package that overrides it.
+/// - The default methods and the abstract methods in interfaces are overridden like the other
+///   instance methods.
+///
+/// A bridge method generated by the compiler has the erased descriptor of the methods it
+/// overrides, while the method it delegates to has a more specific one.
+/// Therefore, the delegate is also recorded as overriding the methods overridden by the bridge.
+#[doc = see_jvm_spec!(5, 4, 5)]
+#[derive(Debug, Default)]
+pub struct OverrideGraph {
+    overridden: BTreeMap<MethodRef, BTreeSet<MethodRef>>,
+    overriding: BTreeMap<MethodRef, BTreeSet<MethodRef>>,
+    bridges: BTreeMap<MethodRef, MethodRef>,
+    undetermined: Vec<UndeterminedOverride>,
+}
+
+/// A pair of methods whose override relation cannot be determined because a class is missing.
+/// The pair is left out of the [`OverrideGraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndeterminedOverride {
+    /// The method that may override the other one.
+    pub method: MethodRef,
+    /// The method that may be overridden.
+    pub overridden: MethodRef,
+    /// The class that is needed to determine the relation but is missing.
+    pub missing: UnknownClass,
+}
+
+impl OverrideGraph {
+    /// Builds the override graph of all the classes in a [`ResolutionContext`].
+    /// Methods in missing supertypes are not taken into account, and the pairs of methods whose
+    /// relation depends on a missing class are left out of the graph and reported by
+    /// [`OverrideGraph::undetermined`].
+    #[must_use]
+    pub fn new(context: &ResolutionContext) -> Self {
+        let mut graph = Self::default();
+        let class_refs: BTreeSet<_> = context
+            .application_classes
+            .keys()
+            .chain(context.library_classes.keys())
+            .collect();
+        for class_ref in class_refs {
+            let Some(class) = context.class(class_ref) else {
+                continue;
+            };
+            graph.add_class(context, class);
+        }
+        graph
+    }
+
+    fn add_class(&mut self, context: &ResolutionContext, class: &Class) {
+        let mut supertypes: Vec<_> = context
+            .supertypes(&class.as_ref())
+            .into_iter()
+            .filter(|it| it.binary_name != class.binary_name)
+            .collect();
+        supertypes.sort_unstable();
+        for method in class.methods.iter().filter(|it| can_override(it)) {
+            for supertype in &supertypes {
+                let Some(overridden) = context
+                    .class(supertype)
+                    .and_then(|it| it.get_method(&method.name, &method.descriptor))
+                else {
+                    continue;
+                };
+                if !can_be_overridden(overridden) {
+                    continue;
+                }
+                match context.can_override(method, overridden) {
+                    Ok(true) => self.insert(method.as_ref(), overridden.as_ref()),
+                    Ok(false) => {}
+                    Err(missing) => self.undetermined.push(UndeterminedOverride {
+                        method: method.as_ref(),
+                        overridden: overridden.as_ref(),
+                        missing,
+                    }),
+                }
+            }
+        }
+        let bridges: Vec<_> = class
+            .methods
+            .iter()
+            .filter(|it| it.access_flags.contains(AccessFlags::BRIDGE))
+            .filter_map(|bridge| Some((bridge.as_ref(), bridge_delegate(class, bridge)?.as_ref())))
+            .collect();
+        for (bridge, delegate) in bridges {
+            let overridden = self.overridden.get(&bridge).cloned().unwrap_or_default();
+            for method in overridden {
+                self.insert(delegate.clone(), method);
+            }
+            self.bridges.insert(bridge, delegate);
+        }
+    }
+
+    fn insert(&mut self, method: MethodRef, overridden: MethodRef) {
+        self.overriding
+            .entry(overridden.clone())
+            .or_default()
+            .insert(method.clone());
+        self.overridden
+            .entry(method)
+            .or_default()
+            .insert(overridden);
+    }
+
+    /// Returns the methods overridden by a method, including those in indirect supertypes.
+    pub fn overridden_methods(&self, method: &MethodRef) -> impl Iterator<Item = &MethodRef> {
+        self.overridden.get(method).into_iter().flatten()
+    }
+
+    /// Returns the methods overriding a method, including those in indirect subtypes.
+    pub fn overriding_methods(&self, method: &MethodRef) -> impl Iterator<Item = &MethodRef> {
+        self.overriding.get(method).into_iter().flatten()
+    }
+
+    /// Checks whether a method overrides any method.
+    #[must_use]
+    pub fn overrides_any(&self, method: &MethodRef) -> bool {
+        self.overridden.contains_key(method)
+    }
+
+    /// Checks whether a method is overridden by any method.
+    #[must_use]
+    pub fn is_overridden(&self, method: &MethodRef) -> bool {
+        self.overriding.contains_key(method)
+    }
+
+    /// Returns the pairs of methods whose override relation cannot be determined because a
+    /// class is missing, which are not in the graph.
+    #[must_use]
+    pub fn undetermined(&self) -> &[UndeterminedOverride] {
+        &self.undetermined
+    }
+
+    /// Returns the method a bridge method delegates to.
+    #[must_use]
+    pub fn bridge_delegate(&self, bridge: &MethodRef) -> Option<&MethodRef> {
+        self.bridges.get(bridge)
+    }
+}
+
+impl ResolutionContext {
+    /// Builds the [`OverrideGraph`] of all the classes.
+    #[must_use]
+    pub fn override_graph(&self) -> OverrideGraph {
+        OverrideGraph::new(self)
+    }
+}
+
+/// Checks whether a method can override other methods.
+fn can_override(method: &Method) -> bool {
+    !method
+        .access_flags
+        .intersects(AccessFlags::PRIVATE | AccessFlags::STATIC)
+        && !method.is_constructor()
+        && !method.is_static_initializer_block()
+}
+
+/// Checks whether a method can be overridden by other methods.
+fn can_be_overridden(method: &Method) -> bool {
+    can_override(method) && !method.access_flags.contains(AccessFlags::FINAL)
+}
+
+/// Finds the method in the same class that a bridge method delegates to.
+fn bridge_delegate<'c>(class: &'c Class, bridge: &Method) -> Option<&'c Method> {
+    let body = bridge.body.as_ref()?;
+    body.instructions.iter().find_map(|(_, instruction)| {
+        let (Instruction::InvokeVirtual(method_ref)
+        | Instruction::InvokeSpecial(method_ref)
+        | Instruction::InvokeInterface(method_ref, _)) = instruction
+        else {
+            return None;
+        };
+        let is_delegate = method_ref.name == bridge.name
+            && method_ref.descriptor != bridge.descriptor
+            && method_ref.owner.binary_name == class.binary_name;
+        is_delegate
+            .then(|| class.get_method(&method_ref.name, &method_ref.descriptor))
+            .flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        jvm::{code::MethodBody, references::ClassRef},
+        tests::{class_with_supertypes, context_with, interface_named, method_ref, with_methods},
+    };
+
+    use super::*;
+
+    const PUBLIC: AccessFlags = AccessFlags::PUBLIC;
+
+    fn context() -> ResolutionContext {
+        let mut comparator = with_methods(
+            class_with_supertypes(
+                "org/mokapot/NameComparator",
+                Some("java/lang/Object"),
+                &["java/util/Comparator"],
+            ),
+            &[
+                ("compare", "(Ljava/lang/String;Ljava/lang/String;)I", PUBLIC),
+                (
+                    "compare",
+                    "(Ljava/lang/Object;Ljava/lang/Object;)I",
+                    PUBLIC | AccessFlags::BRIDGE | AccessFlags::SYNTHETIC,
+                ),
+            ],
+        );
+        comparator.methods[1].body = Some(MethodBody {
+            max_stack: 3,
+            max_locals: 3,
+            instructions: [(
+                0.into(),
+                Instruction::InvokeVirtual(method_ref(
+                    "org/mokapot/NameComparator",
+                    "compare",
+                    "(Ljava/lang/String;Ljava/lang/String;)I",
+                )),
+            )]
+            .into(),
+            exception_table: Vec::new(),
+            line_number_table: None,
+            local_variable_table: None,
+            stack_map_table: None,
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+        });
+        context_with([
+            with_methods(
+                class_with_supertypes("java/lang/Object", None, &[]),
+                &[
+                    ("toString", "()Ljava/lang/String;", PUBLIC),
+                    ("<init>", "()V", PUBLIC),
+                    (
+                        "getClass",
+                        "()Ljava/lang/Class;",
+                        PUBLIC | AccessFlags::FINAL,
+                    ),
+                ],
+            ),
+            with_methods(
+                interface_named("java/util/Comparator", &[]),
+                &[(
+                    "compare",
+                    "(Ljava/lang/Object;Ljava/lang/Object;)I",
+                    PUBLIC | AccessFlags::ABSTRACT,
+                )],
+            ),
+            comparator,
+            with_methods(
+                class_with_supertypes("org/mokapot/Base", Some("java/lang/Object"), &[]),
+                &[
+                    ("<init>", "()V", PUBLIC),
+                    ("pkg", "()V", AccessFlags::empty()),
+                    ("helper", "()V", AccessFlags::PRIVATE),
+                    ("create", "()V", PUBLIC | AccessFlags::STATIC),
+                    ("toString", "()Ljava/lang/String;", PUBLIC),
+                ],
+            ),
+            with_methods(
+                class_with_supertypes("org/other/Sub", Some("org/mokapot/Base"), &[]),
+                &[
+                    ("<init>", "()V", PUBLIC),
+                    ("pkg", "()V", AccessFlags::empty()),
+                    ("helper", "()V", AccessFlags::PRIVATE),
+                    ("create", "()V", PUBLIC | AccessFlags::STATIC),
+                    ("toString", "()Ljava/lang/String;", PUBLIC),
+                    ("getClass", "()Ljava/lang/Class;", PUBLIC),
+                ],
+            ),
+            with_methods(
+                interface_named("org/mokapot/Greeter", &[]),
+                &[("greet", "()V", PUBLIC | AccessFlags::ABSTRACT)],
+            ),
+            with_methods(
+                interface_named("org/mokapot/Polite", &["org/mokapot/Greeter"]),
+                &[("greet", "()V", PUBLIC)],
+            ),
+        ])
+    }
+
+    fn refs<'a>(methods: impl Iterator<Item = &'a MethodRef>) -> Vec<String> {
+        methods.map(|it| it.owner.binary_name.clone()).collect()
+    }
+
+    #[test]
+    fn overrides() {
+        let graph = context().override_graph();
+        let to_string = |owner| method_ref(owner, "toString", "()Ljava/lang/String;");
+        assert_eq!(
+            refs(graph.overridden_methods(&to_string("org/other/Sub"))),
+            ["java/lang/Object", "org/mokapot/Base"]
+        );
+        assert_eq!(
+            refs(graph.overriding_methods(&to_string("java/lang/Object"))),
+            ["org/mokapot/Base", "org/other/Sub"]
+        );
+        assert_eq!(
+            refs(graph.overridden_methods(&method_ref("org/mokapot/Polite", "greet", "()V"))),
+            ["org/mokapot/Greeter"]
+        );
+    }
+
+    #[test]
+    fn undetermined_with_missing_class() {
+        let mut ctx = context_with([
+            with_methods(
+                class_with_supertypes("org/mokapot/Base", Some("java/lang/Object"), &[]),
+                &[("pkg", "()V", AccessFlags::empty())],
+            ),
+            class_with_supertypes("org/other/Mid", Some("org/mokapot/Base"), &[]),
+            with_methods(
+                class_with_supertypes("org/other/Leaf", Some("org/other/Mid"), &[]),
+                &[("pkg", "()V", AccessFlags::empty())],
+            ),
+        ]);
+        // The hierarchy still knows `Mid`, but the class itself is missing.
+        let mid = ClassRef::new("org/other/Mid");
+        ctx.application_classes.remove(&mid);
+        ctx.library_classes.remove(&mid);
+
+        let graph = ctx.override_graph();
+        let pkg = |owner| method_ref(owner, "pkg", "()V");
+        assert!(!graph.overrides_any(&pkg("org/other/Leaf")));
+        assert_eq!(
+            graph.undetermined(),
+            [UndeterminedOverride {
+                method: pkg("org/other/Leaf"),
+                overridden: pkg("org/mokapot/Base"),
+                missing: UnknownClass(mid),
+            }]
+        );
+    }
+
+    #[test]
+    fn exclusions() {
+        let graph = context().override_graph();
+        for (name, descriptor) in [
+            ("pkg", "()V"),
+            ("helper", "()V"),
+            ("create", "()V"),
+            ("<init>", "()V"),
+            ("getClass", "()Ljava/lang/Class;"),
+        ] {
+            let method = method_ref("org/other/Sub", name, descriptor);
+            assert!(!graph.overrides_any(&method), "{name}");
+        }
+        assert!(!graph.is_overridden(&method_ref("org/mokapot/Base", "pkg", "()V")));
+    }
+
+    #[test]
+    fn package_private_in_same_package() {
+        let mut ctx = context();
+        let same_package = with_methods(
+            class_with_supertypes("org/mokapot/Sub", Some("org/mokapot/Base"), &[]),
+            &[("pkg", "()V", AccessFlags::empty())],
+        );
+        ctx.class_hierarchy.add_class(&same_package);
+        ctx.library_classes
+            .insert(same_package.as_ref(), same_package);
+        let graph = ctx.override_graph();
+        assert_eq!(
+            refs(graph.overriding_methods(&method_ref("org/mokapot/Base", "pkg", "()V"))),
+            ["org/mokapot/Sub"]
+        );
+    }
+
+    #[test]
+    fn bridge_methods() {
+        let graph = context().override_graph();
+        let erased = method_ref(
+            "org/mokapot/NameComparator",
+            "compare",
+            "(Ljava/lang/Object;Ljava/lang/Object;)I",
+        );
+        let specific = method_ref(
+            "org/mokapot/NameComparator",
+            "compare",
+            "(Ljava/lang/String;Ljava/lang/String;)I",
+        );
+        assert_eq!(graph.bridge_delegate(&erased), Some(&specific));
+        assert_eq!(
+            refs(graph.overridden_methods(&specific)),
+            ["java/util/Comparator"]
+        );
+        let overridden = method_ref(
+            "java/util/Comparator",
+            "compare",
+            "(Ljava/lang/Object;Ljava/lang/Object;)I",
+        );
+        assert_eq!(graph.overriding_methods(&overridden).count(), 2);
+    }
+}
@@ -0,0 +1,461 @@
+//! Resolution of symbolic references to methods and fields.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    jvm::{
+        method::{self, AccessFlags},
+        references::{ClassRef, FieldRef, MethodRef},
+        Class, Field, Method,
+    },
+    macros::see_jvm_spec,
+    types::field_type::FieldType,
+};
+
+use super::{subtyping::JAVA_LANG_OBJECT, ResolutionContext, UnknownClass};
+
+/// An error that occurs when resolving a symbolic reference.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ResolutionError {
+    /// A class needed to resolve the reference is missing.
+    #[error(transparent)]
+    UnknownClass(#[from] UnknownClass),
+    /// The owner of a method reference is an interface, or the owner of an interface method
+    /// reference is a class.
+    /// This is also reported for the owner of a field or a method that is static when it is
+    /// accessed as an instance member, or vice versa.
+    #[error("Incompatible class change: {0}")]
+    IncompatibleClassChange(ClassRef),
+    /// No method matches the reference.
+    #[error("No such method: {0}")]
+    NoSuchMethod(MethodRef),
+    /// No field matches the reference.
+    #[error("No such field: {0}")]
+    NoSuchField(FieldRef),
+    /// The method selected for a receiver is abstract, or no method is selected.
+    #[error("Abstract method: {0}")]
+    AbstractMethod(MethodRef),
+    /// More than one non-abstract maximally specific superinterface method can be selected for
+    /// a receiver.
+    #[error("Conflicting default methods: {0}")]
+    ConflictingDefaultMethods(MethodRef),
+}
+
+impl ResolutionContext {
+    /// Resolves a reference to a method of a class, e.g., the target of an `invokevirtual`,
+    /// `invokespecial` or `invokestatic` instruction.
+    /// The method is looked up in the class and its superclasses, and then in the maximally
+    /// specific superinterface methods.
+    /// A reference to a signature polymorphic method of `java/lang/invoke/MethodHandle` or
+    /// `java/lang/invoke/VarHandle` resolves to the method regardless of its descriptor.
+    #[doc = see_jvm_spec!(5, 4, 3, 3)]
+    ///
+    /// # Errors
+    /// - [`ResolutionError::IncompatibleClassChange`] if the owner is an interface.
+    /// - [`ResolutionError::NoSuchMethod`] if no method matches the reference.
+    /// - [`ResolutionError::UnknownClass`] if a class needed to resolve the reference is missing.
+    pub fn resolve_method(&self, method_ref: &MethodRef) -> Result<&Method, ResolutionError> {
+        let owner = self.known_class(&method_ref.owner)?;
+        if owner.is_interface() {
+            return Err(ResolutionError::IncompatibleClassChange(
+                method_ref.owner.clone(),
+            ));
+        }
+        let mut visited = HashSet::new();
+        let mut current = Some(owner);
+        while let Some(class) = current {
+            if !visited.insert(&class.binary_name) {
+                break;
+            }
+            if let Some(method) = signature_polymorphic_method(class, &method_ref.name)
+                .or_else(|| class.get_method(&method_ref.name, &method_ref.descriptor))
+            {
+                return Ok(method);
+            }
+            current = class
+                .super_class
+                .as_ref()
+                .map(|it| self.known_class(it))
+                .transpose()?;
+        }
+        self.resolve_in_superinterfaces(&method_ref.owner, method_ref)?
+            .ok_or_else(|| ResolutionError::NoSuchMethod(method_ref.clone()))
+    }
+
+    /// Resolves a reference to a method of an interface, e.g., the target of an
+    /// `invokeinterface` instruction.
+    /// The method is looked up in the interface, then in the public instance methods of
+    /// `java/lang/Object`, and then in the maximally specific superinterface methods.
+    #[doc = see_jvm_spec!(5, 4, 3, 4)]
+    ///
+    /// # Errors
+    /// - [`ResolutionError::IncompatibleClassChange`] if the owner is not an interface.
+    /// - [`ResolutionError::NoSuchMethod`] if no method matches the reference.
+    /// - [`ResolutionError::UnknownClass`] if a class needed to resolve the reference is missing.
+    pub fn resolve_interface_method(
+        &self,
+        method_ref: &MethodRef,
+    ) -> Result<&Method, ResolutionError> {
+        let owner = self.known_class(&method_ref.owner)?;
+        if !owner.is_interface() {
+            return Err(ResolutionError::IncompatibleClassChange(
+                method_ref.owner.clone(),
+            ));
+        }
+        if let Some(method) = owner.get_method(&method_ref.name, &method_ref.descriptor) {
+            return Ok(method);
+        }
+        let object = self.known_class(&ClassRef::new(JAVA_LANG_OBJECT))?;
+        if let Some(method) = object
+            .get_method(&method_ref.name, &method_ref.descriptor)
+            .filter(|it| {
+                it.access_flags.contains(AccessFlags::PUBLIC)
+                    && !it.access_flags.contains(AccessFlags::STATIC)
+            })
+        {
+            return Ok(method);
+        }
+        self.resolve_in_superinterfaces(&method_ref.owner, method_ref)?
+            .ok_or_else(|| ResolutionError::NoSuchMethod(method_ref.clone()))
+    }
+
+    /// Resolves a reference to a field.
+    /// The field is looked up in the class, then in its superinterfaces, and then in its
+    /// superclass recursively.
+    #[doc = see_jvm_spec!(5, 4, 3, 2)]
+    ///
+    /// # Errors
+    /// - [`ResolutionError::NoSuchField`] if no field matches the reference.
+    /// - [`ResolutionError::UnknownClass`] if a class needed to resolve the reference is missing.
+    pub fn resolve_field(&self, field_ref: &FieldRef) -> Result<&Field, ResolutionError> {
+        let mut visited = HashSet::new();
+        self.lookup_field(&field_ref.owner, field_ref, &mut visited)?
+            .ok_or_else(|| ResolutionError::NoSuchField(field_ref.clone()))
+    }
+
+    fn lookup_field<'c>(
+        &'c self,
+        class_ref: &ClassRef,
+        field_ref: &FieldRef,
+        visited: &mut HashSet<ClassRef>,
+    ) -> Result<Option<&'c Field>, UnknownClass> {
+        if !visited.insert(class_ref.clone()) {
+            return Ok(None);
+        }
+        let class = self.known_class(class_ref)?;
+        if let Some(field) = class.get_field(&field_ref.name, &field_ref.field_type) {
+            return Ok(Some(field));
+        }
+        for interface in &class.interfaces {
+            if let Some(field) = self.lookup_field(interface, field_ref, visited)? {
+                return Ok(Some(field));
+            }
+        }
+        // The superclass of an interface is always `java/lang/Object`, which is not searched
+        // for the fields of an interface.
+        match &class.super_class {
+            Some(super_class) if !class.is_interface() => {
+                self.lookup_field(super_class, field_ref, visited)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Looks up a method in the superinterfaces of a class or an interface.
+    /// The only non-abstract maximally specific superinterface method is preferred; otherwise,
+    /// the first matching method is chosen in the breadth-first order of the superinterfaces.
+    fn resolve_in_superinterfaces(
+        &self,
+        class_ref: &ClassRef,
+        method_ref: &MethodRef,
+    ) -> Result<Option<&Method>, UnknownClass> {
+        let candidates = self.superinterface_methods(class_ref, method_ref)?;
+        let maximally_specific = self.maximally_specific(&candidates)?;
+        let mut non_abstract = maximally_specific
+            .iter()
+            .filter(|it| !it.access_flags.contains(AccessFlags::ABSTRACT));
+        if let (Some(&method), None) = (non_abstract.next(), non_abstract.next()) {
+            return Ok(Some(method));
+        }
+        Ok(candidates.first().copied())
+    }
+
+    /// Returns the non-private instance methods matching `method_ref` by name and descriptor
+    /// declared in the superinterfaces of a class or an interface, in breadth-first order.
+    pub(super) fn superinterface_methods(
+        &self,
+        class_ref: &ClassRef,
+        method_ref: &MethodRef,
+    ) -> Result<Vec<&Method>, UnknownClass> {
+        Ok(self
+            .superinterfaces(class_ref)?
+            .iter()
+            .filter_map(|interface| {
+                self.class(interface)?
+                    .get_method(&method_ref.name, &method_ref.descriptor)
+            })
+            .filter(|it| {
+                !it.access_flags
+                    .intersects(AccessFlags::PRIVATE | AccessFlags::STATIC)
+            })
+            .collect())
+    }
+
+    /// Keeps the methods whose declaring interfaces have no subinterface declaring another of
+    /// the methods.
+    pub(super) fn maximally_specific<'m>(
+        &self,
+        methods: &[&'m Method],
+    ) -> Result<Vec<&'m Method>, UnknownClass> {
+        let mut maximally_specific = Vec::new();
+        for &method in methods {
+            let mut is_maximal = true;
+            for other in methods {
+                if other.owner != method.owner && self.is_subtype(&other.owner, &method.owner)? {
+                    is_maximal = false;
+                    break;
+                }
+            }
+            if is_maximal {
+                maximally_specific.push(method);
+            }
+        }
+        Ok(maximally_specific)
+    }
+
+    /// Returns the superinterfaces of a class or an interface in breadth-first order, including
+    /// those of its superclasses.
+    fn superinterfaces(&self, class_ref: &ClassRef) -> Result<Vec<ClassRef>, UnknownClass> {
+        let mut superinterfaces = Vec::new();
+        let mut visited = HashSet::from([class_ref.clone()]);
+        let mut queue = VecDeque::from([class_ref.clone()]);
+        while let Some(current) = queue.pop_front() {
+            // `java/lang/Object` has no superinterfaces, so it does not need to be known.
+            if current.binary_name == JAVA_LANG_OBJECT && self.class(&current).is_none() {
+                continue;
+            }
+            let class = self.known_class(&current)?;
+            if current != *class_ref && class.is_interface() {
+                superinterfaces.push(current);
+            }
+            let supertypes = class.super_class.iter().chain(&class.interfaces);
+            for supertype in supertypes {
+                if visited.insert(supertype.clone()) {
+                    queue.push_back(supertype.clone());
+                }
+            }
+        }
+        Ok(superinterfaces)
+    }
+
+    pub(super) fn known_class(&self, class_ref: &ClassRef) -> Result<&Class, UnknownClass> {
+        self.class(class_ref)
+            .ok_or_else(|| UnknownClass(class_ref.clone()))
+    }
+}
+
+/// Gets the only method named `name` in `class` if it is signature polymorphic.
+#[doc = see_jvm_spec!(2, 9, 3)]
+fn signature_polymorphic_method<'c>(class: &'c Class, name: &str) -> Option<&'c Method> {
+    if !matches!(
+        class.binary_name.as_str(),
+        "java/lang/invoke/MethodHandle" | "java/lang/invoke/VarHandle"
+    ) {
+        return None;
+    }
+    let mut methods = class.methods.iter().filter(|it| it.name == name);
+    let method = methods.next()?;
+    let is_signature_polymorphic = methods.next().is_none()
+        && method
+            .access_flags
+            .contains(method::AccessFlags::VARARGS | method::AccessFlags::NATIVE)
+        && method.descriptor.parameters_types
+            == [FieldType::Object(ClassRef::new(JAVA_LANG_OBJECT)).into_array_type()];
+    is_signature_polymorphic.then_some(method)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        jvm::field,
+        tests::{
+            class_with_supertypes, context_with, field_named, interface_named, method_ref,
+            with_methods,
+        },
+    };
+
+    use super::*;
+
+    const STATIC_FIELD: field::AccessFlags =
+        field::AccessFlags::PUBLIC.union(field::AccessFlags::STATIC);
+
+    fn context() -> ResolutionContext {
+        let public = AccessFlags::PUBLIC;
+        let abstract_ = AccessFlags::PUBLIC | AccessFlags::ABSTRACT;
+        context_with([
+            with_methods(
+                class_with_supertypes(JAVA_LANG_OBJECT, None, &[]),
+                &[
+                    ("toString", "()Ljava/lang/String;", public),
+                    ("hashCode", "()I", public),
+                ],
+            ),
+            with_methods(
+                interface_named("org/mokapot/Shape", &[]),
+                &[
+                    ("area", "()D", abstract_),
+                    ("name", "()Ljava/lang/String;", abstract_),
+                ],
+            ),
+            with_methods(
+                interface_named("org/mokapot/Named", &["org/mokapot/Shape"]),
+                &[("name", "()Ljava/lang/String;", public)],
+            ),
+            with_methods(
+                class_with_supertypes(
+                    "org/mokapot/Base",
+                    Some(JAVA_LANG_OBJECT),
+                    &["org/mokapot/Named"],
+                ),
+                &[("area", "()D", abstract_), ("hashCode", "()I", public)],
+            ),
+            class_with_supertypes("org/mokapot/Circle", Some("org/mokapot/Base"), &[]),
+            with_methods(
+                class_with_supertypes("java/lang/invoke/MethodHandle", Some(JAVA_LANG_OBJECT), &[]),
+                &[(
+                    "invokeExact",
+                    "([Ljava/lang/Object;)Ljava/lang/Object;",
+                    AccessFlags::PUBLIC
+                        | AccessFlags::FINAL
+                        | AccessFlags::VARARGS
+                        | AccessFlags::NATIVE,
+                )],
+            ),
+        ])
+    }
+
+    #[test]
+    fn resolve_class_methods() {
+        let ctx = context();
+        let resolved = |owner, name, descriptor| {
+            ctx.resolve_method(&method_ref(owner, name, descriptor))
+                .map(|it| it.owner.binary_name.as_str())
+        };
+        assert_eq!(
+            resolved("org/mokapot/Circle", "area", "()D"),
+            Ok("org/mokapot/Base")
+        );
+        assert_eq!(
+            resolved("org/mokapot/Circle", "hashCode", "()I"),
+            Ok("org/mokapot/Base")
+        );
+        assert_eq!(
+            resolved("org/mokapot/Circle", "toString", "()Ljava/lang/String;"),
+            Ok(JAVA_LANG_OBJECT)
+        );
+        // The default method is the only non-abstract maximally specific one.
+        assert_eq!(
+            resolved("org/mokapot/Circle", "name", "()Ljava/lang/String;"),
+            Ok("org/mokapot/Named")
+        );
+        assert_eq!(
+            resolved("org/mokapot/Circle", "perimeter", "()D"),
+            Err(ResolutionError::NoSuchMethod(method_ref(
+                "org/mokapot/Circle",
+                "perimeter",
+                "()D"
+            )))
+        );
+        assert_eq!(
+            resolved("org/mokapot/Shape", "area", "()D"),
+            Err(ResolutionError::IncompatibleClassChange(ClassRef::new(
+                "org/mokapot/Shape"
+            )))
+        );
+        assert_eq!(
+            resolved("org/mokapot/Square", "area", "()D"),
+            Err(ResolutionError::UnknownClass(UnknownClass(ClassRef::new(
+                "org/mokapot/Square"
+            ))))
+        );
+    }
+
+    #[test]
+    fn resolve_interface_methods() {
+        let ctx = context();
+        let resolved = |owner, name, descriptor| {
+            ctx.resolve_interface_method(&method_ref(owner, name, descriptor))
+                .map(|it| it.owner.binary_name.as_str())
+        };
+        assert_eq!(
+            resolved("org/mokapot/Named", "area", "()D"),
+            Ok("org/mokapot/Shape")
+        );
+        assert_eq!(
+            resolved("org/mokapot/Named", "name", "()Ljava/lang/String;"),
+            Ok("org/mokapot/Named")
+        );
+        assert_eq!(
+            resolved("org/mokapot/Shape", "hashCode", "()I"),
+            Ok(JAVA_LANG_OBJECT)
+        );
+        assert!(matches!(
+            resolved("org/mokapot/Base", "area", "()D"),
+            Err(ResolutionError::IncompatibleClassChange(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_signature_polymorphic_method() {
+        let ctx = context();
+        let method_ref = method_ref(
+            "java/lang/invoke/MethodHandle",
+            "invokeExact",
+            "(Ljava/lang/String;I)V",
+        );
+        let method = ctx.resolve_method(&method_ref).unwrap();
+        assert_eq!(
+            method.descriptor.descriptor(),
+            "([Ljava/lang/Object;)Ljava/lang/Object;"
+        );
+    }
+
+    #[test]
+    fn resolve_fields() {
+        let mut shape = interface_named("org/mokapot/Shape", &[]);
+        shape.fields = vec![field_named("org/mokapot/Shape", "SIDES", STATIC_FIELD)];
+        let mut base = class_with_supertypes("org/mokapot/Base", Some(JAVA_LANG_OBJECT), &[]);
+        base.fields = vec![
+            field_named("org/mokapot/Base", "SIDES", STATIC_FIELD),
+            field_named("org/mokapot/Base", "WIDTH", STATIC_FIELD),
+        ];
+        let ctx = context_with([
+            shape,
+            base,
+            class_with_supertypes(
+                "org/mokapot/Square",
+                Some("org/mokapot/Base"),
+                &["org/mokapot/Shape"],
+            ),
+        ]);
+        let field_ref = |owner: &str, name: &str| FieldRef {
+            owner: ClassRef::new(owner),
+            name: name.to_owned(),
+            field_type: FieldType::Base(crate::types::field_type::PrimitiveType::Int),
+        };
+        // The superinterfaces are searched before the superclass.
+        let resolved = ctx.resolve_field(&field_ref("org/mokapot/Square", "SIDES"));
+        assert_eq!(resolved.unwrap().owner, ClassRef::new("org/mokapot/Shape"));
+        let resolved = ctx.resolve_field(&field_ref("org/mokapot/Base", "SIDES"));
+        assert_eq!(resolved.unwrap().owner, ClassRef::new("org/mokapot/Base"));
+        // The missing superclass of an interface is not needed to search the superclass.
+        let resolved = ctx.resolve_field(&field_ref("org/mokapot/Square", "WIDTH"));
+        assert_eq!(resolved.unwrap().owner, ClassRef::new("org/mokapot/Base"));
+        // `java/lang/Object` is missing in the context.
+        assert_eq!(
+            ctx.resolve_field(&field_ref("org/mokapot/Square", "EDGES"))
+                .unwrap_err(),
+            ResolutionError::UnknownClass(UnknownClass(ClassRef::new(JAVA_LANG_OBJECT)))
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Validation of sealed hierarchies and their exhaustive subtypes.
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{jvm::references::ClassRef, macros::see_jvm_spec};
+
+use super::{ResolutionContext, UnknownClass};
+
+/// A problem found in a sealed hierarchy.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SealedIssue {
+    /// A permitted subclass is missing.
+    #[error("{subclass} permitted by {sealed} is not found")]
+    MissingPermittedSubclass {
+        /// The sealed class or interface.
+        sealed: ClassRef,
+        /// The permitted subclass.
+        subclass: ClassRef,
+    },
+    /// A permitted subclass does not directly extend the sealed class or implement the sealed
+    /// interface.
+    #[error("{subclass} permitted by {sealed} is not its direct subtype")]
+    NotDirectSubtype {
+        /// The sealed class or interface.
+        sealed: ClassRef,
+        /// The permitted subclass.
+        subclass: ClassRef,
+    },
+    /// A direct subtype of a sealed class or interface is not permitted by it, so that loading
+    /// it fails with `IncompatibleClassChangeError`.
+    #[error("{subclass} is not permitted by {sealed}")]
+    UnpermittedSubtype {
+        /// The sealed class or interface.
+        sealed: ClassRef,
+        /// The direct subtype.
+        subclass: ClassRef,
+    },
+}
+
+/// The subtypes of a sealed class or interface that together cover all its instances.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SealedSubtypes {
+    /// The classes that can be instantiated, i.e., the `final` classes and the `sealed` and
+    /// `non-sealed` classes that are not abstract.
+    pub concrete: BTreeSet<ClassRef>,
+    /// The `non-sealed` subtypes, whose subtypes are not restricted.
+    pub non_sealed: BTreeSet<ClassRef>,
+    /// The subtypes of the `non-sealed` subtypes found in the context.
+    /// More of them may exist outside the context.
+    pub known_subtypes: BTreeSet<ClassRef>,
+}
+
+impl SealedSubtypes {
+    /// Checks whether the subtypes are known exhaustively, i.e., there is no `non-sealed`
+    /// subtype.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.non_sealed.is_empty()
+    }
+}
+
+impl ResolutionContext {
+    /// Checks the sealed hierarchies of all the classes for the following problems:
+    /// - A permitted subclass is missing.
+    /// - A permitted subclass is not a direct subtype of the sealed class or interface.
+    /// - A direct subtype of a sealed class or interface is not permitted.
+    ///
+    /// In class files, a direct subtype of a sealed class or interface is `final` if it has the
+    /// `ACC_FINAL` flag, `sealed` if it lists its permitted subclasses, and `non-sealed`
+    /// otherwise, so that it is always one of them.
+    /// The issues are sorted by the sealed classes and then by the subclasses.
+    #[doc = see_jvm_spec!(5, 3, 5)]
+    #[must_use]
+    pub fn validate_sealed_hierarchies(&self) -> Vec<SealedIssue> {
+        let mut issues = Vec::new();
+        let class_refs: BTreeSet<_> = self
+            .application_classes
+            .keys()
+            .chain(self.library_classes.keys())
+            .collect();
+        for class_ref in class_refs {
+            let Some(class) = self.class(class_ref) else {
+                continue;
+            };
+            for subclass in &class.permitted_subclasses {
+                let Some(permitted) = self.class(subclass) else {
+                    issues.push(SealedIssue::MissingPermittedSubclass {
+                        sealed: class_ref.clone(),
+                        subclass: subclass.clone(),
+                    });
+                    continue;
+                };
+                let is_direct_subtype = permitted.super_class.as_ref() == Some(class_ref)
+                    || permitted.interfaces.contains(class_ref);
+                if !is_direct_subtype {
+                    issues.push(SealedIssue::NotDirectSubtype {
+                        sealed: class_ref.clone(),
+                        subclass: subclass.clone(),
+                    });
+                }
+            }
+            for supertype in class.super_class.iter().chain(&class.interfaces) {
+                let is_permitted = self.class(supertype).is_none_or(|it| {
+                    !it.is_sealed() || it.permitted_subclasses.contains(class_ref)
+                });
+                if !is_permitted {
+                    issues.push(SealedIssue::UnpermittedSubtype {
+                        sealed: supertype.clone(),
+                        subclass: class_ref.clone(),
+                    });
+                }
+            }
+        }
+        issues.sort_by(|lhs, rhs| lhs.key().cmp(&rhs.key()));
+        issues
+    }
+
+    /// Finds the subtypes covering all the instances of a sealed class or interface by following
+    /// the permitted subclasses transitively, e.g., to check the exhaustiveness of a pattern
+    /// matching `switch`.
+    /// A sealed class that is not abstract is included as a concrete subtype, and its permitted
+    /// subclasses are followed as well.
+    /// The subtypes of the `non-sealed` subtypes are looked up in the class hierarchy and the
+    /// interface implementations of the context.
+    ///
+    /// Returns `None` if the class is not sealed.
+    ///
+    /// # Errors
+    /// [`UnknownClass`] if the class or any of the permitted subclasses is missing.
+    pub fn sealed_subtypes(
+        &self,
+        class_ref: &ClassRef,
+    ) -> Result<Option<SealedSubtypes>, UnknownClass> {
+        let class = self.known_class(class_ref)?;
+        if !class.is_sealed() {
+            return Ok(None);
+        }
+        let mut subtypes = SealedSubtypes::default();
+        let mut visited = HashSet::from([class_ref]);
+        let mut stack: Vec<_> = class.permitted_subclasses.iter().collect();
+        while let Some(current) = stack.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            let subclass = self.known_class(current)?;
+            if subclass.is_final() {
+                subtypes.concrete.insert(current.clone());
+            } else if subclass.is_sealed() {
+                if !subclass.is_abstract() {
+                    subtypes.concrete.insert(current.clone());
+                }
+                stack.extend(&subclass.permitted_subclasses);
+            } else {
+                if !subclass.is_abstract() {
+                    subtypes.concrete.insert(current.clone());
+                }
+                subtypes.non_sealed.insert(current.clone());
+            }
+        }
+        for non_sealed in &subtypes.non_sealed {
+            let mut stack = vec![non_sealed.clone()];
+            while let Some(current) = stack.pop() {
+                let known_subtypes = self
+                    .class_hierarchy
+                    .subclasses(&current)
+                    .into_iter()
+                    .chain(self.interface_implementations.implementors(&current));
+                for subtype in known_subtypes {
+                    if subtypes.known_subtypes.insert(subtype.clone()) {
+                        stack.push(subtype);
+                    }
+                }
+            }
+        }
+        Ok(Some(subtypes))
+    }
+}
+
+impl SealedIssue {
+    fn key(&self) -> (&ClassRef, &ClassRef) {
+        match self {
+            Self::MissingPermittedSubclass { sealed, subclass }
+            | Self::NotDirectSubtype { sealed, subclass }
+            | Self::UnpermittedSubtype { sealed, subclass } => (sealed, subclass),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        jvm::{class, Class},
+        tests::{class_with_supertypes, context_with, interface_named},
+    };
+
+    fn sealed(mut class: Class, permitted: &[&str]) -> Class {
+        class.permitted_subclasses = permitted.iter().copied().map(ClassRef::new).collect();
+        class
+    }
+
+    fn final_class(binary_name: &str, super_class: &str, interfaces: &[&str]) -> Class {
+        let mut class = class_with_supertypes(binary_name, Some(super_class), interfaces);
+        class.access_flags |= class::AccessFlags::FINAL;
+        class
+    }
+
+    fn shapes() -> Vec<Class> {
+        let mut shape = class_with_supertypes("Shape", Some("java/lang/Object"), &[]);
+        shape.access_flags |= class::AccessFlags::ABSTRACT;
+        vec![
+            class_with_supertypes("java/lang/Object", None, &[]),
+            sealed(shape, &["Circle", "Polygon", "Blob"]),
+            final_class("Circle", "Shape", &[]),
+            sealed(
+                class_with_supertypes("Polygon", Some("Shape"), &[]),
+                &["Square"],
+            ),
+            final_class("Square", "Polygon", &[]),
+            class_with_supertypes("Blob", Some("Shape"), &[]),
+            class_with_supertypes("Droplet", Some("Blob"), &[]),
+            class_with_supertypes("Puddle", Some("Droplet"), &["Liquid"]),
+            sealed(interface_named("Fluid", &[]), &["Liquid"]),
+            interface_named("Liquid", &["Fluid"]),
+        ]
+    }
+
+    #[test]
+    fn consistent_hierarchy() {
+        let context = context_with(shapes());
+        assert!(context.validate_sealed_hierarchies().is_empty());
+    }
+
+    #[test]
+    fn inconsistent_hierarchy() {
+        let mut classes = shapes();
+        classes.retain(|it| it.binary_name != "Blob");
+        classes.push(final_class("Triangle", "Polygon", &[]));
+        classes.push(sealed(interface_named("Marker", &[]), &["Circle"]));
+        let context = context_with(classes);
+
+        assert_eq!(
+            context.validate_sealed_hierarchies(),
+            vec![
+                SealedIssue::NotDirectSubtype {
+                    sealed: ClassRef::new("Marker"),
+                    subclass: ClassRef::new("Circle"),
+                },
+                SealedIssue::UnpermittedSubtype {
+                    sealed: ClassRef::new("Polygon"),
+                    subclass: ClassRef::new("Triangle"),
+                },
+                SealedIssue::MissingPermittedSubclass {
+                    sealed: ClassRef::new("Shape"),
+                    subclass: ClassRef::new("Blob"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn exhaustive_subtypes() {
+        let context = context_with(shapes());
+
+        let subtypes = context
+            .sealed_subtypes(&ClassRef::new("Shape"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            subtypes.concrete,
+            BTreeSet::from(["Blob", "Circle", "Polygon", "Square"].map(ClassRef::new))
+        );
+        assert_eq!(subtypes.non_sealed, BTreeSet::from([ClassRef::new("Blob")]));
+        assert_eq!(
+            subtypes.known_subtypes,
+            BTreeSet::from(["Droplet", "Puddle"].map(ClassRef::new))
+        );
+        assert!(!subtypes.is_closed());
+
+        let polygons = context
+            .sealed_subtypes(&ClassRef::new("Polygon"))
+            .unwrap()
+            .unwrap();
+        assert!(polygons.is_closed());
+        let fluids = context
+            .sealed_subtypes(&ClassRef::new("Fluid"))
+            .unwrap()
+            .unwrap();
+        assert!(fluids.concrete.is_empty());
+        assert_eq!(fluids.non_sealed, BTreeSet::from([ClassRef::new("Liquid")]));
+        assert_eq!(
+            fluids.known_subtypes,
+            BTreeSet::from([ClassRef::new("Puddle")])
+        );
+        assert_eq!(context.sealed_subtypes(&ClassRef::new("Circle")), Ok(None));
+        assert!(context.sealed_subtypes(&ClassRef::new("Unknown")).is_err());
+    }
+}
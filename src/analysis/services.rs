@@ -0,0 +1,417 @@
+//! Discovery of service providers loaded by `java.util.ServiceLoader`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    jvm::{
+        class,
+        class_loader::{self, ClassPath},
+        method,
+        references::ClassRef,
+        Method,
+    },
+    types::{field_type::FieldType, method_descriptor::ReturnType},
+};
+
+use super::{modules::ModuleGraph, ResolutionContext};
+
+const SERVICES_DIR: &str = "META-INF/services/";
+const PROVIDER_METHOD: &str = "provider";
+
+/// The services and their providers found in a class path and a module graph.
+#[derive(Debug, Default)]
+pub struct ServiceCatalog {
+    /// The services keyed by their classes.
+    pub services: BTreeMap<ClassRef, Service>,
+    /// The problems found in the services and the providers.
+    pub issues: Vec<ServiceIssue>,
+}
+
+/// A service, i.e., an interface or a class whose implementations are loaded by
+/// `java.util.ServiceLoader`.
+#[derive(Debug, Default)]
+pub struct Service {
+    /// The providers of the service in discovery order, i.e., the `META-INF/services` files in
+    /// class path order followed by the `provides` directives in module name order.
+    pub providers: Vec<Provider>,
+    /// The names of the modules declaring that they use the service.
+    pub users: BTreeSet<String>,
+}
+
+/// A provider of a service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provider {
+    /// The class of the provider.
+    pub class_ref: ClassRef,
+    /// Where the provider is declared.
+    pub source: ProviderSource,
+}
+
+/// Where a provider is declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderSource {
+    /// A `META-INF/services` file in the class path entry at the given index.
+    ServicesFile(usize),
+    /// A `provides` directive of the module with the given name.
+    Module(String),
+}
+
+/// A problem found in a service or a provider.
+#[derive(Debug, thiserror::Error)]
+pub enum ServiceIssue {
+    /// The class of a provider is not found.
+    #[error("Provider {provider} of {service} is not found")]
+    UnknownProvider {
+        /// The service.
+        service: ClassRef,
+        /// The provider.
+        provider: ClassRef,
+    },
+    /// A provider is neither a subtype of the service nor a module provider with a `provider()`
+    /// method returning a subtype of the service.
+    #[error("Provider {provider} does not implement {service}")]
+    NotImplemented {
+        /// The service.
+        service: ClassRef,
+        /// The provider.
+        provider: ClassRef,
+    },
+    /// A provider without a `provider()` method is not a public concrete class with a public
+    /// constructor taking no arguments, so that it cannot be instantiated.
+    #[error("Provider {provider} of {service} has no public no-arg constructor")]
+    NoPublicConstructor {
+        /// The service.
+        service: ClassRef,
+        /// The provider.
+        provider: ClassRef,
+    },
+    /// A service used by a module has no provider.
+    #[error("Service {service} used by {module} has no provider")]
+    NoProvider {
+        /// The service.
+        service: ClassRef,
+        /// The name of the module using the service.
+        module: String,
+    },
+    /// A `META-INF/services` file cannot be read.
+    #[error("Failed to read the providers of {service} in class path entry {entry}: {error}")]
+    Unreadable {
+        /// The service.
+        service: ClassRef,
+        /// The index of the class path entry.
+        entry: usize,
+        /// The error.
+        #[source]
+        error: class_loader::Error,
+    },
+    /// The `META-INF/services` directory of a class path entry cannot be listed.
+    #[error("Failed to list the services in class path entry {entry}: {error}")]
+    Unlistable {
+        /// The index of the class path entry.
+        entry: usize,
+        /// The error.
+        #[source]
+        error: class_loader::Error,
+    },
+}
+
+impl ServiceCatalog {
+    /// Discovers the services and their providers.
+    /// - The `META-INF/services` files are listed in each class path entry, so that services
+    ///   whose classes are not in `context` are found as well.
+    /// - The `provides` and `uses` directives are taken from the modules in `modules`, if given.
+    ///
+    /// The providers are validated against the classes in `context`.
+    #[must_use]
+    pub fn discover<P>(
+        class_path: &[P],
+        modules: Option<&ModuleGraph>,
+        context: &ResolutionContext,
+    ) -> Self
+    where
+        P: ClassPath,
+    {
+        let mut catalog = Self::default();
+        let descriptors: Vec<_> = modules
+            .into_iter()
+            .flat_map(ModuleGraph::modules)
+            .filter_map(|it| Some((&it.name, it.descriptor.as_ref()?)))
+            .collect();
+        for (entry, entry_class_path) in class_path.iter().enumerate() {
+            let file_names = match entry_class_path.list_resources(SERVICES_DIR) {
+                Ok(file_names) => file_names,
+                Err(error) => {
+                    catalog
+                        .issues
+                        .push(ServiceIssue::Unlistable { entry, error });
+                    continue;
+                }
+            };
+            for file_name in file_names {
+                let Some(service_name) = file_name.strip_prefix(SERVICES_DIR) else {
+                    continue;
+                };
+                let service = ClassRef::new(service_name.replace('.', "/"));
+                let bytes = match entry_class_path.find_resource(&file_name) {
+                    Ok(bytes) => bytes,
+                    Err(error) => {
+                        catalog.issues.push(ServiceIssue::Unreadable {
+                            service,
+                            entry,
+                            error,
+                        });
+                        continue;
+                    }
+                };
+                let providers = parse_services_file(&String::from_utf8_lossy(&bytes));
+                catalog
+                    .service(&service)
+                    .providers
+                    .extend(providers.into_iter().map(|class_ref| Provider {
+                        class_ref,
+                        source: ProviderSource::ServicesFile(entry),
+                    }));
+            }
+        }
+        for (module, descriptor) in &descriptors {
+            for provide in &descriptor.provides {
+                let providers = &mut catalog.service(&provide.service).providers;
+                providers.extend(provide.with.iter().map(|class_ref| Provider {
+                    class_ref: class_ref.clone(),
+                    source: ProviderSource::Module((*module).clone()),
+                }));
+            }
+            for service in &descriptor.uses {
+                catalog.service(service).users.insert((*module).clone());
+            }
+        }
+        catalog.validate(context);
+        catalog
+    }
+
+    fn service(&mut self, service: &ClassRef) -> &mut Service {
+        self.services.entry(service.clone()).or_default()
+    }
+
+    fn validate(&mut self, context: &ResolutionContext) {
+        for (service_ref, service) in &self.services {
+            if service.providers.is_empty() {
+                self.issues
+                    .extend(service.users.iter().map(|module| ServiceIssue::NoProvider {
+                        service: service_ref.clone(),
+                        module: module.clone(),
+                    }));
+            }
+            for provider in &service.providers {
+                if let Some(issue) = validate_provider(context, service_ref, provider) {
+                    self.issues.push(issue);
+                }
+            }
+        }
+    }
+
+    /// Returns the providers of a service.
+    pub fn providers(&self, service: &ClassRef) -> impl Iterator<Item = &Provider> {
+        self.services
+            .get(service)
+            .into_iter()
+            .flat_map(|it| &it.providers)
+    }
+}
+
+/// Parses the provider names in a `META-INF/services` file, which lists one fully qualified
+/// binary name per line, with comments starting with `#`.
+fn parse_services_file(content: &str) -> Vec<ClassRef> {
+    content
+        .lines()
+        .map(|line| line.split_once('#').map_or(line, |(it, _)| it).trim())
+        .filter(|it| !it.is_empty())
+        .map(|it| ClassRef::new(it.replace('.', "/")))
+        .collect()
+}
+
+/// Checks that a provider can be instantiated by `java.util.ServiceLoader` as the service.
+fn validate_provider(
+    context: &ResolutionContext,
+    service: &ClassRef,
+    provider: &Provider,
+) -> Option<ServiceIssue> {
+    let provider_ref = &provider.class_ref;
+    let Some(class) = context.class(provider_ref) else {
+        return Some(ServiceIssue::UnknownProvider {
+            service: service.clone(),
+            provider: provider_ref.clone(),
+        });
+    };
+    let not_implemented = || ServiceIssue::NotImplemented {
+        service: service.clone(),
+        provider: provider_ref.clone(),
+    };
+    // Only the providers declared by modules can have a `provider()` method.
+    let provider_method = matches!(provider.source, ProviderSource::Module(_))
+        .then(|| class.methods.iter().find(|it| is_provider_method(it)))
+        .flatten();
+    if let Some(provider_method) = provider_method {
+        let ReturnType::Some(FieldType::Object(returned)) = &provider_method.descriptor.return_type
+        else {
+            return Some(not_implemented());
+        };
+        // The supertypes of the provider may be missing, in which case it is not reported.
+        return (!context.is_subtype(returned, service).unwrap_or(true)).then(not_implemented);
+    }
+    if !context.is_subtype(provider_ref, service).unwrap_or(true) {
+        return Some(not_implemented());
+    }
+    let is_instantiable = class.access_flags.contains(class::AccessFlags::PUBLIC)
+        && !class
+            .access_flags
+            .intersects(class::AccessFlags::ABSTRACT | class::AccessFlags::INTERFACE)
+        && class.methods.iter().any(|it| {
+            it.is_constructor()
+                && it.descriptor.parameters_types.is_empty()
+                && it.access_flags.contains(method::AccessFlags::PUBLIC)
+        });
+    (!is_instantiable).then(|| ServiceIssue::NoPublicConstructor {
+        service: service.clone(),
+        provider: provider_ref.clone(),
+    })
+}
+
+/// Checks whether a method is a public static `provider()` method.
+fn is_provider_method(method: &Method) -> bool {
+    method.name == PROVIDER_METHOD
+        && method.descriptor.parameters_types.is_empty()
+        && method
+            .access_flags
+            .contains(method::AccessFlags::PUBLIC | method::AccessFlags::STATIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::modules::ObservableModule,
+        jvm::{class_loader::class_paths::DirectoryClassPath, module::Provide},
+        tests::{class_with_supertypes, context_with, interface_named, module_info, with_methods},
+    };
+
+    use super::*;
+
+    const CODEC: &str = "org/mokapot/spi/Codec";
+
+    fn context() -> ResolutionContext {
+        let public = method::AccessFlags::PUBLIC;
+        let codec = |name| class_with_supertypes(name, Some("java/lang/Object"), &[CODEC]);
+        context_with([
+            class_with_supertypes("java/lang/Object", None, &[]),
+            interface_named(CODEC, &[]),
+            interface_named("org/mokapot/spi/Plugin", &[]),
+            with_methods(codec("org/mokapot/impl/Json"), &[("<init>", "()V", public)]),
+            with_methods(
+                codec("org/mokapot/impl/Hidden"),
+                &[("<init>", "()V", method::AccessFlags::PRIVATE)],
+            ),
+            with_methods(
+                class_with_supertypes("org/mokapot/impl/Broken", Some("java/lang/Object"), &[]),
+                &[("<init>", "()V", public)],
+            ),
+            with_methods(
+                class_with_supertypes("org/mokapot/impl/Factory", Some("java/lang/Object"), &[]),
+                &[(
+                    "provider",
+                    "()Lorg/mokapot/impl/Json;",
+                    public | method::AccessFlags::STATIC,
+                )],
+            ),
+        ])
+    }
+
+    fn modules() -> ModuleGraph {
+        let mut module_info =
+            module_info("org.mokapot", &["org/mokapot/spi", "org/mokapot/impl"], &[]);
+        let descriptor = module_info.module.as_mut().unwrap();
+        descriptor.provides = vec![Provide {
+            service: ClassRef::new(CODEC),
+            with: vec![ClassRef::new("org/mokapot/impl/Factory")],
+        }];
+        descriptor.uses = vec![
+            ClassRef::new(CODEC),
+            ClassRef::new("org/mokapot/spi/Plugin"),
+        ];
+        let module = ObservableModule::explicit(&module_info, []).unwrap();
+        ModuleGraph::resolve_all([module]).unwrap()
+    }
+
+    #[test]
+    fn discover_providers() {
+        let dir = tempfile::tempdir().unwrap();
+        let services_dir = dir.path().join(SERVICES_DIR);
+        std::fs::create_dir_all(&services_dir).unwrap();
+        std::fs::write(
+            services_dir.join("org.mokapot.spi.Codec"),
+            "# Codecs\norg.mokapot.impl.Json\norg.mokapot.impl.Broken # Not a codec\n\n\
+             org.mokapot.impl.Hidden\norg.mokapot.impl.Missing\n",
+        )
+        .unwrap();
+        std::fs::write(
+            services_dir.join("org.other.Extension"),
+            "org.mokapot.impl.Json\n",
+        )
+        .unwrap();
+        let class_path = [DirectoryClassPath::new(dir.path())];
+        let modules = modules();
+        let catalog = ServiceCatalog::discover(&class_path, Some(&modules), &context());
+
+        let providers: Vec<_> = catalog
+            .providers(&ClassRef::new(CODEC))
+            .map(|it| (it.class_ref.binary_name.as_str(), it.source.clone()))
+            .collect();
+        assert_eq!(
+            providers,
+            [
+                ("org/mokapot/impl/Json", ProviderSource::ServicesFile(0)),
+                ("org/mokapot/impl/Broken", ProviderSource::ServicesFile(0)),
+                ("org/mokapot/impl/Hidden", ProviderSource::ServicesFile(0)),
+                ("org/mokapot/impl/Missing", ProviderSource::ServicesFile(0)),
+                (
+                    "org/mokapot/impl/Factory",
+                    ProviderSource::Module("org.mokapot".to_owned())
+                ),
+            ]
+        );
+
+        let extensions: Vec<_> = catalog
+            .providers(&ClassRef::new("org/other/Extension"))
+            .map(|it| (it.class_ref.binary_name.as_str(), it.source.clone()))
+            .collect();
+        assert_eq!(
+            extensions,
+            [("org/mokapot/impl/Json", ProviderSource::ServicesFile(0))]
+        );
+
+        let issues: Vec<_> = catalog.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "Provider org/mokapot/impl/Broken does not implement org/mokapot/spi/Codec",
+                "Provider org/mokapot/impl/Hidden of org/mokapot/spi/Codec has no public no-arg \
+             constructor",
+                "Provider org/mokapot/impl/Missing of org/mokapot/spi/Codec is not found",
+                "Service org/mokapot/spi/Plugin used by org.mokapot has no provider",
+                "Provider org/mokapot/impl/Json does not implement org/other/Extension",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_services() {
+        let providers =
+            parse_services_file("  com.example.A  \r\n# com.example.B\ncom.example.C$D#x\n");
+        assert_eq!(
+            providers,
+            [
+                ClassRef::new("com/example/A"),
+                ClassRef::new("com/example/C$D"),
+            ]
+        );
+    }
+}
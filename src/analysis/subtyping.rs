@@ -0,0 +1,630 @@
+//! Subtyping and assignability of types.
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{
+    jvm::{references::ClassRef, Class},
+    macros::see_jvm_spec,
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+use super::ResolutionContext;
+
+pub(crate) const JAVA_LANG_OBJECT: &str = "java/lang/Object";
+pub(super) const JAVA_LANG_CLONEABLE: &str = "java/lang/Cloneable";
+pub(super) const JAVA_IO_SERIALIZABLE: &str = "java/io/Serializable";
+
+/// A class that is needed to answer a query but is in neither the application classes nor the
+/// library classes of a [`ResolutionContext`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Class not found in the resolution context: {0}")]
+pub struct UnknownClass(pub ClassRef);
+
+/// The conversions allowed in addition to the widening reference conversion when checking
+/// assignability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Conversions {
+    /// Allows widening primitive conversions (e.g., `int` to `long`).
+    #[doc = see_jvm_spec!(2, 11, 4)]
+    pub primitive_widening: bool,
+    /// Allows boxing and unboxing conversions (e.g., `int` to `java.lang.Integer`), optionally
+    /// followed by a widening reference or primitive conversion.
+    pub boxing: bool,
+}
+
+impl Conversions {
+    /// No conversion other than the widening reference conversion, as in the JVM verifier.
+    pub const NONE: Self = Self {
+        primitive_widening: false,
+        boxing: false,
+    };
+
+    /// The conversions allowed in assignment contexts of the Java language (JLS §5.2).
+    pub const ASSIGNMENT: Self = Self {
+        primitive_widening: true,
+        boxing: true,
+    };
+}
+
+impl ResolutionContext {
+    /// Gets a class from the application classes, or from the library classes if it is not an
+    /// application class.
+    #[must_use]
+    pub fn class(&self, class_ref: &ClassRef) -> Option<&Class> {
+        self.application_classes
+            .get(class_ref)
+            .or_else(|| self.library_classes.get(class_ref))
+    }
+
+    /// Checks whether `sub` is a proper subclass of `sup`, i.e., `sup` is one of the superclasses
+    /// of `sub`.
+    /// Interfaces are not taken into account; see [`ResolutionContext::is_subtype`] for that.
+    ///
+    /// # Errors
+    /// [`UnknownClass`] if the answer is `false` but a class in the superclass chain of `sub`
+    /// is missing, so that the answer cannot be determined.
+    pub fn is_subclass(&self, sub: &ClassRef, sup: &ClassRef) -> Result<bool, UnknownClass> {
+        if sub == sup {
+            return Ok(false);
+        }
+        let super_classes = self.class_hierarchy.super_classes(sub);
+        if super_classes.contains(sup) {
+            return Ok(true);
+        }
+        if sup.binary_name == JAVA_LANG_OBJECT {
+            // Every class but `java/lang/Object` itself extends `java/lang/Object`.
+            return Ok(true);
+        }
+        // `java/lang/Object` has no superclass, so it cannot change the answer when missing.
+        match std::iter::once(sub)
+            .chain(&super_classes)
+            .find(|it| it.binary_name != JAVA_LANG_OBJECT && self.class(it).is_none())
+        {
+            Some(missing) => Err(UnknownClass(missing.clone())),
+            None => Ok(false),
+        }
+    }
+
+    /// Checks whether the class or interface `sub` is a subtype of `sup`, i.e., `sub` is `sup`,
+    /// or `sup` is a superclass or a (transitive) superinterface of `sub`.
+    #[doc = see_jvm_spec!(4, 10, 1, 2)]
+    ///
+    /// # Errors
+    /// [`UnknownClass`] if the answer is `false` but a supertype of `sub` is missing, so that
+    /// the answer cannot be determined.
+    pub fn is_subtype(&self, sub: &ClassRef, sup: &ClassRef) -> Result<bool, UnknownClass> {
+        if sub == sup || sup.binary_name == JAVA_LANG_OBJECT {
+            return Ok(true);
+        }
+        let supertypes = self.supertypes(sub);
+        if supertypes.contains(sup) {
+            return Ok(true);
+        }
+        // Reports the missing class deterministically. `java/lang/Object` has no supertypes, so
+        // it cannot change the answer when missing.
+        match supertypes
+            .iter()
+            .filter(|it| it.binary_name != JAVA_LANG_OBJECT && self.class(it).is_none())
+            .collect::<BTreeSet<_>>()
+            .first()
+        {
+            Some(missing) => Err(UnknownClass((*missing).clone())),
+            None => Ok(false),
+        }
+    }
+
+    /// Returns `class_ref` together with all its superclasses and superinterfaces, as far as
+    /// they are known.
+    pub(crate) fn supertypes(&self, class_ref: &ClassRef) -> HashSet<ClassRef> {
+        let mut supertypes = self.class_hierarchy.super_classes(class_ref);
+        supertypes.insert(class_ref.clone());
+        let interfaces: Vec<_> = supertypes
+            .iter()
+            .flat_map(|it| self.interface_implementations.implemented_interfaces(it))
+            .collect();
+        supertypes.extend(interfaces);
+        supertypes
+    }
+
+    /// Checks whether a value of type `from` can be assigned to a variable of type `to` without
+    /// conversions other than the widening reference conversion, as in the JVM verifier.
+    /// Arrays are covariant, and are assignable to `java/lang/Object`, `java/lang/Cloneable` and
+    /// `java/io/Serializable`.
+    #[doc = see_jvm_spec!(4, 10, 1, 2)]
+    ///
+    /// # Errors
+    /// [`UnknownClass`] if the answer depends on a class that is missing.
+    pub fn is_assignable(&self, from: &FieldType, to: &FieldType) -> Result<bool, UnknownClass> {
+        self.is_assignable_with(from, to, Conversions::NONE)
+    }
+
+    /// Checks whether a value of type `from` can be assigned to a variable of type `to` with the
+    /// given conversions allowed in addition to the widening reference conversion.
+    ///
+    /// # Errors
+    /// [`UnknownClass`] if the answer depends on a class that is missing.
+    pub fn is_assignable_with(
+        &self,
+        from: &FieldType,
+        to: &FieldType,
+        conversions: Conversions,
+    ) -> Result<bool, UnknownClass> {
+        match (from, to) {
+            (FieldType::Base(from), FieldType::Base(to)) => {
+                Ok(from == to || conversions.primitive_widening && widens_to(*from, *to))
+            }
+            (FieldType::Base(from), FieldType::Object(to)) => {
+                if !conversions.boxing {
+                    return Ok(false);
+                }
+                self.is_subtype(&box_class(*from), to)
+            }
+            (FieldType::Object(from), FieldType::Base(to)) => {
+                if !conversions.boxing {
+                    return Ok(false);
+                }
+                Ok(unbox_class(from).is_some_and(|it| {
+                    it == *to || conversions.primitive_widening && widens_to(it, *to)
+                }))
+            }
+            // No class type is assignable to an array type.
+            (FieldType::Base(_) | FieldType::Object(_), FieldType::Array(_))
+            | (FieldType::Array(_), FieldType::Base(_)) => Ok(false),
+            (FieldType::Object(from), FieldType::Object(to)) => self.is_subtype(from, to),
+            (FieldType::Array(_), FieldType::Object(to)) => Ok(matches!(
+                to.binary_name.as_str(),
+                JAVA_LANG_OBJECT | JAVA_LANG_CLONEABLE | JAVA_IO_SERIALIZABLE
+            )),
+            (FieldType::Array(from), FieldType::Array(to)) => match (from.as_ref(), to.as_ref()) {
+                (FieldType::Base(from), FieldType::Base(to)) => Ok(from == to),
+                (FieldType::Base(_), _) | (_, FieldType::Base(_)) => Ok(false),
+                (from, to) => self.is_assignable(from, to),
+            },
+        }
+    }
+}
+
+impl ResolutionContext {
+    /// Computes the least upper bound of two types in the way of the JVM verifier, which is used
+    /// when merging the types of a local variable or stack slot at a join point.
+    /// - The least upper bound of two classes is their most specific common superclass.
+    /// - Interfaces are treated as `java/lang/Object`.
+    /// - The least upper bound of two arrays of references is the array of the least upper bound
+    ///   of the element types; the other combinations of arrays and classes result in
+    ///   `java/lang/Object`.
+    ///
+    /// Returns `None` if there is no upper bound, i.e., a primitive type is joined with a
+    /// different type.
+    #[doc = see_jvm_spec!(4, 10, 2, 2)]
+    ///
+    /// # Errors
+    /// [`UnknownClass`] if a superclass needed to compute the result is missing.
+    pub fn verifier_lub(
+        &self,
+        lhs: &FieldType,
+        rhs: &FieldType,
+    ) -> Result<Option<FieldType>, UnknownClass> {
+        if lhs == rhs {
+            return Ok(Some(lhs.clone()));
+        }
+        let object = || FieldType::Object(ClassRef::new(JAVA_LANG_OBJECT));
+        match (lhs, rhs) {
+            (FieldType::Base(_), _) | (_, FieldType::Base(_)) => Ok(None),
+            (FieldType::Object(lhs), FieldType::Object(rhs)) => self
+                .common_super_class(lhs, rhs)
+                .map(|it| Some(FieldType::Object(it))),
+            (FieldType::Array(lhs), FieldType::Array(rhs)) => match (lhs.as_ref(), rhs.as_ref()) {
+                (FieldType::Base(_), _) | (_, FieldType::Base(_)) => Ok(Some(object())),
+                (lhs, rhs) => Ok(self.verifier_lub(lhs, rhs)?.map(FieldType::into_array_type)),
+            },
+            (FieldType::Array(_), FieldType::Object(_))
+            | (FieldType::Object(_), FieldType::Array(_)) => Ok(Some(object())),
+        }
+    }
+
+    /// Finds the most specific common superclass of two classes, where interfaces are treated as
+    /// `java/lang/Object`.
+    fn common_super_class(&self, lhs: &ClassRef, rhs: &ClassRef) -> Result<ClassRef, UnknownClass> {
+        let lhs_chain = self.super_class_chain(lhs)?;
+        let rhs_chain = self.super_class_chain(rhs)?;
+        Ok(rhs_chain
+            .iter()
+            .find(|it| lhs_chain.contains(it))
+            .cloned()
+            .unwrap_or_else(|| ClassRef::new(JAVA_LANG_OBJECT)))
+    }
+
+    /// Returns the class followed by its superclasses up to, but excluding, `java/lang/Object`,
+    /// or an empty chain for interfaces.
+    fn super_class_chain(&self, class_ref: &ClassRef) -> Result<Vec<ClassRef>, UnknownClass> {
+        let mut chain = Vec::new();
+        let mut current = class_ref.clone();
+        while current.binary_name != JAVA_LANG_OBJECT && !chain.contains(&current) {
+            let class = self
+                .class(&current)
+                .ok_or_else(|| UnknownClass(current.clone()))?;
+            if class.is_interface() {
+                break;
+            }
+            chain.push(current);
+            match &class.super_class {
+                Some(super_class) => current = super_class.clone(),
+                None => break,
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Computes the minimal erased candidate set of two types, i.e., the most specific types
+    /// among the common supertypes, which is the erasure of their least upper bound in the Java
+    /// language (JLS §4.10.4).
+    /// Arrays are supertypes of `java/lang/Object`, `java/lang/Cloneable` and
+    /// `java/io/Serializable`, and arrays of references are covariant in their element types.
+    ///
+    /// Returns an empty set if there is no common supertype, i.e., a primitive type is joined
+    /// with a different type.
+    ///
+    /// # Errors
+    /// [`UnknownClass`] if a supertype of either type is missing.
+    pub fn minimal_erased_candidates(
+        &self,
+        lhs: &FieldType,
+        rhs: &FieldType,
+    ) -> Result<BTreeSet<FieldType>, UnknownClass> {
+        let lhs_supertypes = self.erased_supertypes(lhs)?;
+        let rhs_supertypes = self.erased_supertypes(rhs)?;
+        let common: Vec<_> = lhs_supertypes.intersection(&rhs_supertypes).collect();
+        let mut candidates = BTreeSet::new();
+        for &candidate in &common {
+            let mut is_minimal = true;
+            for &other in &common {
+                if other != candidate && self.is_assignable(other, candidate)? {
+                    is_minimal = false;
+                    break;
+                }
+            }
+            if is_minimal {
+                candidates.insert(candidate.clone());
+            }
+        }
+        Ok(candidates)
+    }
+
+    /// Returns the erased supertypes of a type including itself (JLS §4.10.4).
+    fn erased_supertypes(&self, ty: &FieldType) -> Result<HashSet<FieldType>, UnknownClass> {
+        let mut supertypes = HashSet::new();
+        match ty {
+            FieldType::Base(_) => {
+                supertypes.insert(ty.clone());
+            }
+            FieldType::Object(class_ref) => {
+                let class_supertypes = self.supertypes(class_ref);
+                // Reports the missing class deterministically.
+                if let Some(missing) = class_supertypes
+                    .iter()
+                    .filter(|it| it.binary_name != JAVA_LANG_OBJECT && self.class(it).is_none())
+                    .min()
+                {
+                    return Err(UnknownClass(missing.clone()));
+                }
+                supertypes.extend(class_supertypes.into_iter().map(FieldType::Object));
+                supertypes.insert(FieldType::Object(ClassRef::new(JAVA_LANG_OBJECT)));
+            }
+            FieldType::Array(element) => {
+                if let FieldType::Base(_) = element.as_ref() {
+                    supertypes.insert(ty.clone());
+                } else {
+                    supertypes.extend(
+                        self.erased_supertypes(element)?
+                            .into_iter()
+                            .map(FieldType::into_array_type),
+                    );
+                }
+                supertypes.extend(
+                    [JAVA_LANG_OBJECT, JAVA_LANG_CLONEABLE, JAVA_IO_SERIALIZABLE]
+                        .map(|it| FieldType::Object(ClassRef::new(it))),
+                );
+            }
+        }
+        Ok(supertypes)
+    }
+}
+
+/// Checks whether `from` can be converted to `to` by a widening primitive conversion.
+#[doc = see_jvm_spec!(2, 11, 4)]
+const fn widens_to(from: PrimitiveType, to: PrimitiveType) -> bool {
+    use PrimitiveType::{Byte, Char, Double, Float, Int, Long, Short};
+    matches!(
+        (from, to),
+        (Byte, Short | Int | Long | Float | Double)
+            | (Short | Char, Int | Long | Float | Double)
+            | (Int, Long | Float | Double)
+            | (Long, Float | Double)
+            | (Float, Double)
+    )
+}
+
+/// Returns the wrapper class of a primitive type.
+fn box_class(primitive: PrimitiveType) -> ClassRef {
+    let binary_name = match primitive {
+        PrimitiveType::Boolean => "java/lang/Boolean",
+        PrimitiveType::Char => "java/lang/Character",
+        PrimitiveType::Float => "java/lang/Float",
+        PrimitiveType::Double => "java/lang/Double",
+        PrimitiveType::Byte => "java/lang/Byte",
+        PrimitiveType::Short => "java/lang/Short",
+        PrimitiveType::Int => "java/lang/Integer",
+        PrimitiveType::Long => "java/lang/Long",
+    };
+    ClassRef::new(binary_name)
+}
+
+/// Returns the primitive type wrapped by a class, if it is a wrapper class.
+fn unbox_class(class_ref: &ClassRef) -> Option<PrimitiveType> {
+    match class_ref.binary_name.as_str() {
+        "java/lang/Boolean" => Some(PrimitiveType::Boolean),
+        "java/lang/Character" => Some(PrimitiveType::Char),
+        "java/lang/Float" => Some(PrimitiveType::Float),
+        "java/lang/Double" => Some(PrimitiveType::Double),
+        "java/lang/Byte" => Some(PrimitiveType::Byte),
+        "java/lang/Short" => Some(PrimitiveType::Short),
+        "java/lang/Integer" => Some(PrimitiveType::Int),
+        "java/lang/Long" => Some(PrimitiveType::Long),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::tests::{class_with_supertypes, context_with, interface_named};
+
+    use super::*;
+
+    fn context() -> ResolutionContext {
+        context_with([
+            class_with_supertypes(JAVA_LANG_OBJECT, None, &[]),
+            interface_named(JAVA_IO_SERIALIZABLE, &[]),
+            interface_named("java/lang/Comparable", &[]),
+            class_with_supertypes(
+                "java/lang/Number",
+                Some(JAVA_LANG_OBJECT),
+                &[JAVA_IO_SERIALIZABLE],
+            ),
+            class_with_supertypes(
+                "java/lang/Integer",
+                Some("java/lang/Number"),
+                &["java/lang/Comparable"],
+            ),
+            interface_named("org/mokapot/Shape", &[]),
+            interface_named("org/mokapot/Polygon", &["org/mokapot/Shape"]),
+            class_with_supertypes(
+                "org/mokapot/Square",
+                Some("org/mokapot/Missing"),
+                &["org/mokapot/Polygon"],
+            ),
+        ])
+    }
+
+    fn ty(descriptor: &str) -> FieldType {
+        FieldType::from_str(descriptor).unwrap()
+    }
+
+    #[test]
+    fn subclasses() {
+        let ctx = context();
+        let integer = ClassRef::new("java/lang/Integer");
+        let number = ClassRef::new("java/lang/Number");
+        assert_eq!(ctx.is_subclass(&integer, &number), Ok(true));
+        assert_eq!(ctx.is_subclass(&integer, &integer), Ok(false));
+        assert_eq!(ctx.is_subclass(&number, &integer), Ok(false));
+        assert_eq!(
+            ctx.is_subclass(&integer, &ClassRef::new("java/lang/Comparable")),
+            Ok(false)
+        );
+        assert_eq!(
+            ctx.is_subclass(&ClassRef::new("org/mokapot/Square"), &number),
+            Err(UnknownClass(ClassRef::new("org/mokapot/Missing")))
+        );
+    }
+
+    #[test]
+    fn missing_object() {
+        let ctx = context_with([
+            class_with_supertypes("org/mokapot/Base", Some(JAVA_LANG_OBJECT), &[]),
+            class_with_supertypes(
+                "org/mokapot/Other",
+                Some(JAVA_LANG_OBJECT),
+                &["org/mokapot/Shape"],
+            ),
+            interface_named("org/mokapot/Shape", &[]),
+        ]);
+        let base = ClassRef::new("org/mokapot/Base");
+        let other = ClassRef::new("org/mokapot/Other");
+        assert_eq!(ctx.is_subclass(&base, &other), Ok(false));
+        assert_eq!(ctx.is_subtype(&base, &other), Ok(false));
+        assert_eq!(
+            ctx.is_subtype(&base, &ClassRef::new("org/mokapot/Shape")),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn subtypes_through_interfaces() {
+        let ctx = context();
+        let square = ClassRef::new("org/mokapot/Square");
+        assert_eq!(
+            ctx.is_subtype(&square, &ClassRef::new("org/mokapot/Shape")),
+            Ok(true)
+        );
+        assert_eq!(
+            ctx.is_subtype(
+                &ClassRef::new("java/lang/Integer"),
+                &ClassRef::new(JAVA_IO_SERIALIZABLE)
+            ),
+            Ok(true)
+        );
+        assert_eq!(
+            ctx.is_subtype(&square, &ClassRef::new("java/lang/Comparable")),
+            Err(UnknownClass(ClassRef::new("org/mokapot/Missing")))
+        );
+    }
+
+    #[test]
+    fn reference_assignability() {
+        let ctx = context();
+        assert_eq!(
+            ctx.is_assignable(&ty("Ljava/lang/Integer;"), &ty("Ljava/lang/Number;")),
+            Ok(true)
+        );
+        assert_eq!(
+            ctx.is_assignable(&ty("[Ljava/lang/Integer;"), &ty("[Ljava/lang/Number;")),
+            Ok(true)
+        );
+        assert_eq!(
+            ctx.is_assignable(&ty("[[I"), &ty("[Ljava/lang/Cloneable;")),
+            Ok(true)
+        );
+        assert_eq!(ctx.is_assignable(&ty("[I"), &ty("[J")), Ok(false));
+        assert_eq!(
+            ctx.is_assignable(&ty("[I"), &ty("Ljava/io/Serializable;")),
+            Ok(true)
+        );
+        assert_eq!(
+            ctx.is_assignable(&ty("[I"), &ty("Ljava/lang/Number;")),
+            Ok(false)
+        );
+        assert_eq!(
+            ctx.is_assignable(&ty("Ljava/lang/Object;"), &ty("[I")),
+            Ok(false)
+        );
+    }
+
+    fn lub_context() -> ResolutionContext {
+        context_with([
+            class_with_supertypes(JAVA_LANG_OBJECT, None, &[]),
+            interface_named(JAVA_IO_SERIALIZABLE, &[]),
+            interface_named(JAVA_LANG_CLONEABLE, &[]),
+            interface_named("java/lang/Comparable", &[]),
+            class_with_supertypes(
+                "java/lang/Number",
+                Some(JAVA_LANG_OBJECT),
+                &[JAVA_IO_SERIALIZABLE],
+            ),
+            class_with_supertypes(
+                "java/lang/Integer",
+                Some("java/lang/Number"),
+                &["java/lang/Comparable"],
+            ),
+            class_with_supertypes(
+                "java/lang/Long",
+                Some("java/lang/Number"),
+                &["java/lang/Comparable"],
+            ),
+            class_with_supertypes(
+                "java/lang/String",
+                Some(JAVA_LANG_OBJECT),
+                &[JAVA_IO_SERIALIZABLE, "java/lang/Comparable"],
+            ),
+        ])
+    }
+
+    #[test]
+    fn verifier_lub() {
+        let ctx = lub_context();
+        let lub = |lhs, rhs| ctx.verifier_lub(&ty(lhs), &ty(rhs)).unwrap();
+        assert_eq!(
+            lub("Ljava/lang/Integer;", "Ljava/lang/Long;"),
+            Some(ty("Ljava/lang/Number;"))
+        );
+        assert_eq!(
+            lub("Ljava/lang/Integer;", "Ljava/lang/String;"),
+            Some(ty("Ljava/lang/Object;"))
+        );
+        assert_eq!(
+            lub("Ljava/lang/Integer;", "Ljava/lang/Comparable;"),
+            Some(ty("Ljava/lang/Object;"))
+        );
+        assert_eq!(
+            lub("[Ljava/lang/Integer;", "[Ljava/lang/Long;"),
+            Some(ty("[Ljava/lang/Number;"))
+        );
+        assert_eq!(lub("[I", "[J"), Some(ty("Ljava/lang/Object;")));
+        assert_eq!(lub("[I", "[I"), Some(ty("[I")));
+        assert_eq!(lub("I", "J"), None);
+        assert_eq!(
+            ctx.verifier_lub(&ty("Ljava/lang/Integer;"), &ty("Lorg/mokapot/Missing;")),
+            Err(UnknownClass(ClassRef::new("org/mokapot/Missing")))
+        );
+    }
+
+    #[test]
+    fn minimal_erased_candidates() {
+        let ctx = lub_context();
+        let mec = |lhs, rhs| {
+            ctx.minimal_erased_candidates(&ty(lhs), &ty(rhs))
+                .unwrap()
+                .into_iter()
+                .map(|it| it.descriptor())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            mec("Ljava/lang/Integer;", "Ljava/lang/Long;"),
+            ["Ljava/lang/Comparable;", "Ljava/lang/Number;"]
+        );
+        assert_eq!(
+            mec("Ljava/lang/Integer;", "Ljava/lang/String;"),
+            ["Ljava/io/Serializable;", "Ljava/lang/Comparable;"]
+        );
+        assert_eq!(
+            mec("[Ljava/lang/Integer;", "[Ljava/lang/String;"),
+            ["[Ljava/io/Serializable;", "[Ljava/lang/Comparable;"]
+        );
+        assert_eq!(mec("[I", "Ljava/lang/Integer;"), ["Ljava/io/Serializable;"]);
+        assert_eq!(
+            mec("[I", "[J"),
+            ["Ljava/io/Serializable;", "Ljava/lang/Cloneable;"]
+        );
+        assert!(mec("I", "Z").is_empty());
+    }
+
+    #[test]
+    fn primitive_conversions() {
+        let ctx = context();
+        assert_eq!(ctx.is_assignable(&ty("I"), &ty("J")), Ok(false));
+        let widening = Conversions {
+            primitive_widening: true,
+            boxing: false,
+        };
+        assert_eq!(
+            ctx.is_assignable_with(&ty("I"), &ty("J"), widening),
+            Ok(true)
+        );
+        assert_eq!(
+            ctx.is_assignable_with(&ty("J"), &ty("I"), widening),
+            Ok(false)
+        );
+        assert_eq!(
+            ctx.is_assignable_with(&ty("C"), &ty("S"), widening),
+            Ok(false)
+        );
+        assert_eq!(
+            ctx.is_assignable_with(&ty("I"), &ty("Ljava/lang/Number;"), widening),
+            Ok(false)
+        );
+        let assignment = Conversions::ASSIGNMENT;
+        assert_eq!(
+            ctx.is_assignable_with(&ty("I"), &ty("Ljava/lang/Number;"), assignment),
+            Ok(true)
+        );
+        assert_eq!(
+            ctx.is_assignable_with(&ty("Ljava/lang/Integer;"), &ty("D"), assignment),
+            Ok(true)
+        );
+        assert_eq!(
+            ctx.is_assignable_with(&ty("J"), &ty("Ljava/lang/Number;"), assignment),
+            Err(UnknownClass(ClassRef::new("java/lang/Long")))
+        );
+    }
+}
@@ -0,0 +1,703 @@
+//! Detection of references that cannot be resolved in a [`ResolutionContext`].
+
+use std::collections::BTreeSet;
+
+use crate::{
+    jvm::{
+        annotation::ElementValue,
+        class::MethodHandle,
+        code::{Instruction, ProgramCounter},
+        field, method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Annotation, Class, ConstantValue, Field, Method, TypeAnnotation,
+    },
+    types::{
+        field_type::FieldType,
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::{subtyping::JAVA_LANG_OBJECT, ResolutionContext, ResolutionError, UnknownClass};
+
+/// The place where a reference is made.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum ReferenceLocation {
+    /// The declaration of a class, e.g., its supertypes, annotations and bootstrap methods.
+    #[display("{_0}")]
+    Class(ClassRef),
+    /// The declaration of a field, i.e., its type and annotations.
+    #[display("{_0}")]
+    Field(FieldRef),
+    /// The declaration of a method, e.g., its descriptor, thrown exceptions and annotations.
+    #[display("{_0}")]
+    Method(MethodRef),
+    /// An instruction or an exception handler in the body of a method.
+    #[display("{method}@{pc}")]
+    Instruction {
+        /// The method containing the instruction.
+        method: MethodRef,
+        /// The program counter of the instruction.
+        pc: ProgramCounter,
+    },
+}
+
+impl ReferenceLocation {
+    /// Returns the class containing the location.
+    #[must_use]
+    pub fn class(&self) -> &ClassRef {
+        match self {
+            Self::Class(class_ref) => class_ref,
+            Self::Field(field_ref) => &field_ref.owner,
+            Self::Method(method) | Self::Instruction { method, .. } => &method.owner,
+        }
+    }
+}
+
+/// A reference to a class or a member.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Reference {
+    /// A reference to a class.
+    Class(ClassRef),
+    /// A reference to a field by an instruction or a method handle accessing it in the given way.
+    #[display("{_0}")]
+    Field(FieldRef, FieldAccess),
+    /// A reference to a method by an instruction or a method handle invoking it in the given way.
+    #[display("{_0}")]
+    Method(MethodRef, Invocation),
+}
+
+/// How a field is accessed, which determines whether it must be static.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FieldAccess {
+    /// `getstatic`, `putstatic`, `REF_getStatic` or `REF_putStatic`.
+    Static,
+    /// `getfield`, `putfield`, `REF_getField` or `REF_putField`.
+    Instance,
+}
+
+/// How a method is invoked, which determines how it is resolved and whether it must be static.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Invocation {
+    /// `invokevirtual` or `REF_invokeVirtual`, which requires a class.
+    Virtual,
+    /// `invokeinterface` or `REF_invokeInterface`, which requires an interface.
+    Interface,
+    /// `invokespecial`, `REF_invokeSpecial` or `REF_newInvokeSpecial`.
+    Special,
+    /// `invokestatic` or `REF_invokeStatic`.
+    Static,
+}
+
+/// A reference that cannot be resolved, which would make the JVM throw a
+/// `NoClassDefFoundError`, a `NoSuchFieldError`, a `NoSuchMethodError` or an
+/// `IncompatibleClassChangeError` when it is resolved.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{location}: cannot resolve {reference}: {error}")]
+pub struct UnresolvedReference {
+    /// Where the reference is made.
+    pub location: ReferenceLocation,
+    /// The reference.
+    pub reference: Reference,
+    /// Why the reference cannot be resolved.
+    pub error: ResolutionError,
+}
+
+impl ResolutionContext {
+    /// Finds the references made by the application classes that cannot be resolved against the
+    /// application and library classes, e.g., because of mismatched versions of dependencies.
+    ///
+    /// The following references are checked:
+    /// - The classes in the supertypes, descriptors, thrown and caught exceptions, annotations
+    ///   and constants.
+    /// - The fields and methods referenced by instructions and method handles, including the
+    ///   bootstrap methods. A member is reported with
+    ///   [`ResolutionError::IncompatibleClassChange`] if it is static but not accessed or
+    ///   invoked as such, or vice versa.
+    ///
+    /// Each reference is reported once per location, and the results are sorted by location.
+    #[must_use]
+    pub fn unresolved_references(&self) -> Vec<UnresolvedReference> {
+        let class_refs: BTreeSet<_> = self.application_classes.keys().collect();
+        let mut references = BTreeSet::new();
+        for class_ref in class_refs {
+            collect_class_references(&self.application_classes[class_ref], &mut references);
+        }
+        references
+            .into_iter()
+            .filter_map(|(location, reference)| {
+                let error = self.resolve_reference(&reference).err()?;
+                Some(UnresolvedReference {
+                    location,
+                    reference,
+                    error,
+                })
+            })
+            .collect()
+    }
+
+    /// Resolves a reference, checking that a referenced member is static exactly when it is
+    /// accessed or invoked as a static one.
+    fn resolve_reference(&self, reference: &Reference) -> Result<(), ResolutionError> {
+        let (owner, is_static, expects_static) = match reference {
+            Reference::Class(class_ref) => {
+                return self
+                    .class(class_ref)
+                    .map(|_| ())
+                    .ok_or_else(|| UnknownClass(class_ref.clone()).into());
+            }
+            Reference::Field(field_ref, access) => {
+                let field = self.resolve_field(field_ref)?;
+                (
+                    &field_ref.owner,
+                    field.access_flags.contains(field::AccessFlags::STATIC),
+                    *access == FieldAccess::Static,
+                )
+            }
+            Reference::Method(method_ref, invocation) => {
+                let method = match invocation {
+                    Invocation::Virtual => self.resolve_method(method_ref)?,
+                    Invocation::Interface => self.resolve_interface_method(method_ref)?,
+                    Invocation::Special | Invocation::Static => {
+                        self.resolve_any_method(method_ref)?
+                    }
+                };
+                (
+                    &method_ref.owner,
+                    method.access_flags.contains(method::AccessFlags::STATIC),
+                    *invocation == Invocation::Static,
+                )
+            }
+        };
+        if is_static == expects_static {
+            Ok(())
+        } else {
+            Err(ResolutionError::IncompatibleClassChange(owner.clone()))
+        }
+    }
+}
+
+type References = BTreeSet<(ReferenceLocation, Reference)>;
+
+fn collect_class_references(class: &Class, references: &mut References) {
+    let location = ReferenceLocation::Class(class.as_ref());
+    let mut add = |reference| {
+        references.insert((location.clone(), reference));
+    };
+    class
+        .super_class
+        .iter()
+        .chain(&class.interfaces)
+        .for_each(|it| add_class(it, &mut add));
+    declaration_annotations(
+        &class.runtime_visible_annotations,
+        &class.runtime_invisible_annotations,
+    )
+    .chain(type_annotations(
+        &class.runtime_visible_type_annotations,
+        &class.runtime_invisible_type_annotations,
+    ))
+    .for_each(|it| add_annotation(it, &mut add));
+    for bootstrap_method in &class.bootstrap_methods {
+        add_method_handle(&bootstrap_method.method, &mut add);
+        bootstrap_method
+            .arguments
+            .iter()
+            .for_each(|it| add_constant(it, &mut add));
+    }
+    for field in &class.fields {
+        collect_field_references(field, references);
+    }
+    for method in &class.methods {
+        collect_method_references(method, references);
+    }
+}
+
+fn collect_field_references(field: &Field, references: &mut References) {
+    let location = ReferenceLocation::Field(field.as_ref());
+    let mut add = |reference| {
+        references.insert((location.clone(), reference));
+    };
+    add_field_type(&field.field_type, &mut add);
+    declaration_annotations(
+        &field.runtime_visible_annotations,
+        &field.runtime_invisible_annotations,
+    )
+    .chain(type_annotations(
+        &field.runtime_visible_type_annotations,
+        &field.runtime_invisible_type_annotations,
+    ))
+    .for_each(|it| add_annotation(it, &mut add));
+}
+
+fn collect_method_references(method: &Method, references: &mut References) {
+    let method_ref = method.as_ref();
+    let location = ReferenceLocation::Method(method_ref.clone());
+    let mut add = |reference| {
+        references.insert((location.clone(), reference));
+    };
+    add_descriptor(&method.descriptor, &mut add);
+    method
+        .exceptions
+        .iter()
+        .for_each(|it| add_class(it, &mut add));
+    declaration_annotations(
+        &method.runtime_visible_annotations,
+        &method.runtime_invisible_annotations,
+    )
+    .chain(
+        method
+            .runtime_visible_parameter_annotations
+            .iter()
+            .chain(&method.runtime_invisible_parameter_annotations)
+            .flatten()
+            .map(|it| (&it.annotation_type, &it.element_value_pairs)),
+    )
+    .chain(type_annotations(
+        &method.runtime_visible_type_annotations,
+        &method.runtime_invisible_type_annotations,
+    ))
+    .for_each(|it| add_annotation(it, &mut add));
+    if let Some(default) = &method.annotation_default {
+        add_element_value(default, &mut add);
+    }
+
+    let Some(body) = &method.body else {
+        return;
+    };
+    for (pc, instruction) in &body.instructions {
+        let location = ReferenceLocation::Instruction {
+            method: method_ref.clone(),
+            pc: *pc,
+        };
+        add_instruction(instruction, &mut |reference| {
+            references.insert((location.clone(), reference));
+        });
+    }
+    for entry in &body.exception_table {
+        if let Some(catch_type) = &entry.catch_type {
+            let location = ReferenceLocation::Instruction {
+                method: method_ref.clone(),
+                pc: entry.handler_pc,
+            };
+            add_class(catch_type, &mut |reference| {
+                references.insert((location.clone(), reference));
+            });
+        }
+    }
+}
+
+fn add_instruction(instruction: &Instruction, add: &mut impl FnMut(Reference)) {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    match instruction {
+        Ldc(constant) | LdcW(constant) | Ldc2W(constant) => add_constant(constant, add),
+        GetStatic(field_ref) | PutStatic(field_ref) => {
+            add_field(field_ref, FieldAccess::Static, add);
+        }
+        GetField(field_ref) | PutField(field_ref) => {
+            add_field(field_ref, FieldAccess::Instance, add);
+        }
+        InvokeVirtual(method_ref) => add_method(method_ref, Invocation::Virtual, add),
+        InvokeSpecial(method_ref) => add_method(method_ref, Invocation::Special, add),
+        InvokeStatic(method_ref) => add_method(method_ref, Invocation::Static, add),
+        InvokeInterface(method_ref, _) => add_method(method_ref, Invocation::Interface, add),
+        InvokeDynamic { descriptor, .. } => add_descriptor(descriptor, add),
+        New(class_ref) | ANewArray(class_ref) => add_class(class_ref, add),
+        CheckCast(field_type) | InstanceOf(field_type) | MultiANewArray(field_type, _) => {
+            add_field_type(field_type, add);
+        }
+        _ => {}
+    }
+}
+
+fn add_constant(constant: &ConstantValue, add: &mut impl FnMut(Reference)) {
+    match constant {
+        ConstantValue::Class(class_ref) => add_class(class_ref, add),
+        ConstantValue::Handle(handle) => add_method_handle(handle, add),
+        ConstantValue::MethodType(descriptor) => add_descriptor(descriptor, add),
+        ConstantValue::Dynamic(_, _, field_type) => add_field_type(field_type, add),
+        _ => {}
+    }
+}
+
+fn add_method_handle(handle: &MethodHandle, add: &mut impl FnMut(Reference)) {
+    match handle {
+        MethodHandle::RefGetField(field_ref) | MethodHandle::RefPutField(field_ref) => {
+            add_field(field_ref, FieldAccess::Instance, add);
+        }
+        MethodHandle::RefGetStatic(field_ref) | MethodHandle::RefPutStatic(field_ref) => {
+            add_field(field_ref, FieldAccess::Static, add);
+        }
+        MethodHandle::RefInvokeVirtual(method_ref) => {
+            add_method(method_ref, Invocation::Virtual, add);
+        }
+        MethodHandle::RefInvokeStatic(method_ref) => {
+            add_method(method_ref, Invocation::Static, add);
+        }
+        MethodHandle::RefInvokeSpecial(method_ref)
+        | MethodHandle::RefNewInvokeSpecial(method_ref) => {
+            add_method(method_ref, Invocation::Special, add);
+        }
+        MethodHandle::RefInvokeInterface(method_ref) => {
+            add_method(method_ref, Invocation::Interface, add);
+        }
+    }
+}
+
+/// Adds a reference to a field, or to the class of its owner if it is an array.
+fn add_field(field_ref: &FieldRef, access: FieldAccess, add: &mut impl FnMut(Reference)) {
+    if is_array(&field_ref.owner) {
+        add_class(&field_ref.owner, add);
+    } else {
+        add(Reference::Field(field_ref.clone(), access));
+    }
+}
+
+/// Adds a reference to a method, where the methods of arrays are those of `java/lang/Object`.
+fn add_method(method_ref: &MethodRef, invocation: Invocation, add: &mut impl FnMut(Reference)) {
+    if is_array(&method_ref.owner) {
+        add_class(&method_ref.owner, add);
+        add(Reference::Method(
+            MethodRef {
+                owner: ClassRef::new(JAVA_LANG_OBJECT),
+                ..method_ref.clone()
+            },
+            invocation,
+        ));
+    } else {
+        add(Reference::Method(method_ref.clone(), invocation));
+    }
+}
+
+/// Adds a reference to a class, or to the element class if it is an array.
+fn add_class(class_ref: &ClassRef, add: &mut impl FnMut(Reference)) {
+    if is_array(class_ref) {
+        if let Ok(field_type) = class_ref.binary_name.parse() {
+            add_field_type(&field_type, add);
+        }
+    } else {
+        add(Reference::Class(class_ref.clone()));
+    }
+}
+
+fn add_field_type(field_type: &FieldType, add: &mut impl FnMut(Reference)) {
+    match field_type {
+        FieldType::Base(_) => {}
+        FieldType::Object(class_ref) => add_class(class_ref, add),
+        FieldType::Array(element) => add_field_type(element, add),
+    }
+}
+
+fn add_descriptor(descriptor: &MethodDescriptor, add: &mut impl FnMut(Reference)) {
+    descriptor
+        .parameters_types
+        .iter()
+        .for_each(|it| add_field_type(it, add));
+    if let ReturnType::Some(return_type) = &descriptor.return_type {
+        add_field_type(return_type, add);
+    }
+}
+
+type AnnotationParts<'a> = (&'a FieldType, &'a Vec<(String, ElementValue)>);
+
+fn declaration_annotations<'a>(
+    visible: &'a [Annotation],
+    invisible: &'a [Annotation],
+) -> impl Iterator<Item = AnnotationParts<'a>> {
+    visible
+        .iter()
+        .chain(invisible)
+        .map(|it| (&it.annotation_type, &it.element_value_pairs))
+}
+
+fn type_annotations<'a>(
+    visible: &'a [TypeAnnotation],
+    invisible: &'a [TypeAnnotation],
+) -> impl Iterator<Item = AnnotationParts<'a>> {
+    visible
+        .iter()
+        .chain(invisible)
+        .map(|it| (&it.annotation_type, &it.element_value_pairs))
+}
+
+fn add_annotation(
+    (annotation_type, element_value_pairs): AnnotationParts<'_>,
+    add: &mut impl FnMut(Reference),
+) {
+    add_field_type(annotation_type, add);
+    for (_, value) in element_value_pairs {
+        add_element_value(value, add);
+    }
+}
+
+fn add_element_value(value: &ElementValue, add: &mut impl FnMut(Reference)) {
+    match value {
+        ElementValue::Primitive(..)
+        | ElementValue::String(_)
+        | ElementValue::Class {
+            return_descriptor: ReturnType::Void,
+        } => {}
+        ElementValue::EnumConstant { enum_type_name, .. } => {
+            if let Ok(enum_type) = enum_type_name.parse() {
+                add_field_type(&enum_type, add);
+            }
+        }
+        ElementValue::Class {
+            return_descriptor: ReturnType::Some(field_type),
+        } => add_field_type(field_type, add),
+        ElementValue::AnnotationInterface(annotation) => add_annotation(
+            (&annotation.annotation_type, &annotation.element_value_pairs),
+            add,
+        ),
+        ElementValue::Array(values) => values.iter().for_each(|it| add_element_value(it, add)),
+    }
+}
+
+fn is_array(class_ref: &ClassRef) -> bool {
+    class_ref.binary_name.starts_with('[')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        jvm::{
+            annotation::ElementValue, class::BootstrapMethod, code::MethodBody,
+            references::MethodRef,
+        },
+        tests::{
+            class_with_supertypes, context_with, field_named, interface_named, method_ref,
+            with_methods,
+        },
+        types::field_type::PrimitiveType,
+    };
+
+    fn body<const N: usize>(instructions: [(ProgramCounter, Instruction); N]) -> MethodBody {
+        MethodBody {
+            max_stack: 2,
+            max_locals: 2,
+            instructions: instructions.into(),
+            exception_table: Vec::new(),
+            line_number_table: None,
+            local_variable_table: None,
+            stack_map_table: None,
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+        }
+    }
+
+    fn helper_field(name: &str) -> FieldRef {
+        FieldRef {
+            owner: ClassRef::new("org/lib/Helper"),
+            name: name.to_owned(),
+            field_type: FieldType::Base(PrimitiveType::Int),
+        }
+    }
+
+    fn context() -> ResolutionContext {
+        let mut app = with_methods(
+            class_with_supertypes("org/mokapot/App", Some("java/lang/Object"), &[]),
+            &[(
+                "run",
+                "(Lorg/mokapot/Missing;)V",
+                method::AccessFlags::PUBLIC,
+            )],
+        );
+        app.methods[0].body = Some(body([
+            (0.into(), Instruction::New(ClassRef::new("org/lib/Helper"))),
+            (
+                3.into(),
+                Instruction::InvokeVirtual(method_ref("org/lib/Helper", "help", "()V")),
+            ),
+            (
+                6.into(),
+                Instruction::InvokeVirtual(method_ref("org/lib/Helper", "gone", "()V")),
+            ),
+            (9.into(), Instruction::GetStatic(helper_field("VALUE"))),
+            (
+                12.into(),
+                Instruction::InvokeVirtual(method_ref(
+                    "[Lorg/lib/Helper;",
+                    "clone",
+                    "()Ljava/lang/Object;",
+                )),
+            ),
+            (15.into(), Instruction::Return),
+        ]));
+        let mut helper = with_methods(
+            class_with_supertypes("org/lib/Helper", Some("java/lang/Object"), &[]),
+            &[("help", "()V", method::AccessFlags::PUBLIC)],
+        );
+        helper.fields.push(field_named(
+            "org/lib/Helper",
+            "VALUE",
+            field::AccessFlags::PUBLIC | field::AccessFlags::STATIC,
+        ));
+        helper.fields.push(field_named(
+            "org/lib/Helper",
+            "count",
+            field::AccessFlags::PUBLIC,
+        ));
+        let api = with_methods(
+            interface_named("org/lib/Api", &[]),
+            &[(
+                "call",
+                "()V",
+                method::AccessFlags::PUBLIC | method::AccessFlags::ABSTRACT,
+            )],
+        );
+        let object = with_methods(
+            class_with_supertypes("java/lang/Object", None, &[]),
+            &[(
+                "clone",
+                "()Ljava/lang/Object;",
+                method::AccessFlags::PROTECTED,
+            )],
+        );
+        let mut context = context_with([object, helper, api]);
+        context.application_classes.insert(app.as_ref(), app);
+        context
+    }
+
+    #[test]
+    fn report_unresolved_references() {
+        let unresolved = context().unresolved_references();
+
+        let run = method_ref("org/mokapot/App", "run", "(Lorg/mokapot/Missing;)V");
+        let gone = method_ref("org/lib/Helper", "gone", "()V");
+        assert_eq!(
+            unresolved,
+            vec![
+                UnresolvedReference {
+                    location: ReferenceLocation::Method(run.clone()),
+                    reference: Reference::Class(ClassRef::new("org/mokapot/Missing")),
+                    error: ResolutionError::UnknownClass(UnknownClass(ClassRef::new(
+                        "org/mokapot/Missing"
+                    ))),
+                },
+                UnresolvedReference {
+                    location: ReferenceLocation::Instruction {
+                        method: run,
+                        pc: 6.into(),
+                    },
+                    reference: Reference::Method(gone.clone(), Invocation::Virtual),
+                    error: ResolutionError::NoSuchMethod(gone),
+                },
+            ]
+        );
+        assert_eq!(
+            unresolved[1].location.class(),
+            &ClassRef::new("org/mokapot/App")
+        );
+    }
+
+    /// Replaces the body of `App::run` and returns the location of the instruction at `pc`.
+    fn run_with<const N: usize>(
+        context: &mut ResolutionContext,
+        instructions: [(ProgramCounter, Instruction); N],
+    ) -> impl Fn(u16) -> ReferenceLocation {
+        let app = context
+            .application_classes
+            .get_mut(&ClassRef::new("org/mokapot/App"))
+            .unwrap();
+        app.methods[0].body = Some(body(instructions));
+        let run = app.methods[0].as_ref();
+        move |pc| ReferenceLocation::Instruction {
+            method: run.clone(),
+            pc: pc.into(),
+        }
+    }
+
+    #[test]
+    fn incompatible_class_changes() {
+        let mut context = context();
+        let call = method_ref("org/lib/Api", "call", "()V");
+        let help = method_ref("org/lib/Helper", "help", "()V");
+        let at = run_with(
+            &mut context,
+            [
+                (0.into(), Instruction::InvokeVirtual(call.clone())),
+                (3.into(), Instruction::InvokeInterface(call.clone(), 1)),
+                (6.into(), Instruction::GetStatic(helper_field("count"))),
+                (9.into(), Instruction::GetField(helper_field("count"))),
+                (12.into(), Instruction::PutField(helper_field("VALUE"))),
+                (15.into(), Instruction::InvokeStatic(help.clone())),
+                (18.into(), Instruction::InvokeInterface(help.clone(), 1)),
+                (21.into(), Instruction::Return),
+            ],
+        );
+        let incompatible =
+            |owner: &str| ResolutionError::IncompatibleClassChange(ClassRef::new(owner));
+        let unresolved: Vec<_> = context
+            .unresolved_references()
+            .into_iter()
+            .filter(|it| matches!(it.location, ReferenceLocation::Instruction { .. }))
+            .map(|it| (it.location, it.error))
+            .collect();
+        assert_eq!(
+            unresolved,
+            [
+                (at(0), incompatible("org/lib/Api")),
+                (at(6), incompatible("org/lib/Helper")),
+                (at(12), incompatible("org/lib/Helper")),
+                (at(15), incompatible("org/lib/Helper")),
+                (at(18), incompatible("org/lib/Helper")),
+            ]
+        );
+    }
+
+    #[test]
+    fn annotation_and_bootstrap_method_references() {
+        let mut context = context();
+        let app = context
+            .application_classes
+            .get_mut(&ClassRef::new("org/mokapot/App"))
+            .unwrap();
+        app.runtime_visible_annotations.push(Annotation {
+            annotation_type: FieldType::Object(ClassRef::new("org/mokapot/Marker")),
+            element_value_pairs: vec![(
+                "value".to_owned(),
+                ElementValue::EnumConstant {
+                    enum_type_name: "Lorg/mokapot/Kind;".to_owned(),
+                    const_name: "A".to_owned(),
+                },
+            )],
+        });
+        let bootstrap: MethodRef = method_ref(
+            "org/lib/Helper",
+            "bootstrap",
+            "()Ljava/lang/invoke/CallSite;",
+        );
+        app.bootstrap_methods.push(BootstrapMethod {
+            method: MethodHandle::RefInvokeStatic(bootstrap.clone()),
+            arguments: vec![ConstantValue::Class(ClassRef::new("org/mokapot/Arg"))],
+        });
+        let location = ReferenceLocation::Class(app.as_ref());
+
+        let unresolved: Vec<_> = context
+            .unresolved_references()
+            .into_iter()
+            .filter(|it| it.location == location)
+            .map(|it| it.reference)
+            .collect();
+        assert_eq!(
+            unresolved,
+            [
+                Reference::Class(ClassRef::new("org/mokapot/Arg")),
+                Reference::Class(ClassRef::new("org/mokapot/Kind")),
+                Reference::Class(ClassRef::new("org/mokapot/Marker")),
+                Reference::Method(bootstrap, Invocation::Static),
+            ]
+        );
+    }
+
+    #[test]
+    fn library_classes_are_not_checked() {
+        let mut context = context();
+        context.application_classes.clear();
+
+        assert!(context.unresolved_references().is_empty());
+    }
+}
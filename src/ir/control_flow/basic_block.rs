@@ -0,0 +1,274 @@
+//! Basic blocks of a control flow graph.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    ir::ControlFlowGraph,
+    jvm::code::{InstructionList, ProgramCounter},
+};
+
+use super::ControlTransfer;
+
+/// A sequence of instructions that is always executed from the first one to the last one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The program counters of the instructions in the block, in the order of execution.
+    /// The first one is the leader of the block.
+    /// They are not necessarily consecutive, e.g., when the block contains a `goto`.
+    pub instructions: Vec<ProgramCounter>,
+}
+
+impl BasicBlock {
+    /// Returns the program counter of the first instruction in the block.
+    ///
+    /// # Panics
+    /// Panics if the block is empty.
+    #[must_use]
+    pub fn leader(&self) -> ProgramCounter {
+        *self
+            .instructions
+            .first()
+            .expect("A basic block is never empty")
+    }
+
+    /// Returns the program counter of the last instruction in the block, where the control
+    /// leaves the block.
+    ///
+    /// # Panics
+    /// Panics if the block is empty.
+    #[must_use]
+    pub fn terminator(&self) -> ProgramCounter {
+        *self
+            .instructions
+            .last()
+            .expect("A basic block is never empty")
+    }
+
+    /// Checks whether the block contains the instruction at the given program counter.
+    #[must_use]
+    pub fn contains(&self, pc: ProgramCounter) -> bool {
+        self.instructions.contains(&pc)
+    }
+
+    /// Returns an iterator over the instructions of the block in an instruction list.
+    pub fn instructions_in<'a, I>(
+        &'a self,
+        instructions: &'a InstructionList<I>,
+    ) -> impl Iterator<Item = (ProgramCounter, &'a I)> + 'a {
+        self.instructions
+            .iter()
+            .filter_map(|pc| instructions.get(pc).map(|insn| (*pc, insn)))
+    }
+}
+
+/// A control flow graph whose nodes are basic blocks identified by their leaders.
+pub type BasicBlockGraph = ControlFlowGraph<BasicBlock, ControlTransfer>;
+
+impl ControlFlowGraph<(), ControlTransfer> {
+    /// Groups the instructions into basic blocks.
+    ///
+    /// An instruction starts a new block if it is the entry point, if it does not have exactly
+    /// one predecessor, or if it is not reached by an unconditional control transfer from an
+    /// instruction with a single successor.
+    /// Therefore, the edges inside the blocks are all [`ControlTransfer::Unconditional`], and
+    /// [`BasicBlockGraph::instruction_graph`] restores the original graph.
+    #[must_use]
+    pub fn basic_blocks(&self) -> BasicBlockGraph {
+        let mut predecessors: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (src, dst, _) in self.edges() {
+            predecessors.entry(dst).or_default().push(src);
+        }
+        let unique_successor = |pc: ProgramCounter| {
+            let mut edges = self.edges_from(pc)?;
+            match (edges.next(), edges.next()) {
+                (Some((_, dst, ControlTransfer::Unconditional)), None) => Some(dst),
+                _ => None,
+            }
+        };
+        let is_leader = |pc: ProgramCounter| {
+            pc == self.entry_point()
+                || !matches!(
+                    predecessors.get(&pc).map(Vec::as_slice),
+                    Some(&[pred]) if unique_successor(pred) == Some(pc)
+                )
+        };
+
+        let mut leaders: BTreeSet<_> = self
+            .nodes()
+            .map(|(pc, ())| pc)
+            .filter(|it| is_leader(*it))
+            .collect();
+        let mut blocks = BTreeMap::new();
+        let mut assigned = BTreeSet::new();
+        loop {
+            for &leader in &leaders {
+                if blocks.contains_key(&leader) {
+                    continue;
+                }
+                let mut instructions = vec![leader];
+                assigned.insert(leader);
+                let mut current = leader;
+                while let Some(next) = unique_successor(current) {
+                    if leaders.contains(&next) || !assigned.insert(next) {
+                        break;
+                    }
+                    instructions.push(next);
+                    current = next;
+                }
+                blocks.insert(leader, BasicBlock { instructions });
+            }
+            // Unreachable cycles have no leader, so one of their instructions is picked.
+            match self
+                .nodes()
+                .map(|(pc, ())| pc)
+                .find(|it| !assigned.contains(it))
+            {
+                Some(pc) => {
+                    leaders.insert(pc);
+                }
+                None => break,
+            }
+        }
+
+        let inner = blocks
+            .into_iter()
+            .map(|(leader, block)| {
+                let edges = self
+                    .edges_from(block.terminator())
+                    .into_iter()
+                    .flatten()
+                    .map(|(_, dst, transfer)| (dst, transfer.clone()))
+                    .collect();
+                (leader, (block, edges))
+            })
+            .collect();
+        ControlFlowGraph { inner }
+    }
+}
+
+impl BasicBlockGraph {
+    /// Returns the leader and the basic block containing the instruction at the given program
+    /// counter.
+    #[must_use]
+    pub fn block_of(&self, pc: ProgramCounter) -> Option<(ProgramCounter, &BasicBlock)> {
+        self.nodes().find(|(_, block)| block.contains(pc))
+    }
+
+    /// Expands the basic blocks into a control flow graph with one node per instruction.
+    #[must_use]
+    pub fn instruction_graph(&self) -> ControlFlowGraph<(), ControlTransfer> {
+        let intra_block_edges = self.nodes().flat_map(|(_, block)| {
+            block
+                .instructions
+                .windows(2)
+                .map(|it| (it[0], it[1], ControlTransfer::Unconditional))
+        });
+        let inter_block_edges = self.edges().map(|(src, dst, transfer)| {
+            let terminator = self
+                .inner
+                .get(&src)
+                .map_or(src, |(block, _)| block.terminator());
+            (terminator, dst, transfer.clone())
+        });
+        let mut graph = ControlFlowGraph::from_edges(intra_block_edges.chain(inter_block_edges));
+        // Blocks without any edge, e.g., a method with a single block, are kept as nodes.
+        for (leader, _) in self.nodes() {
+            graph.inner.entry(leader).or_default();
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_cfg() -> ControlFlowGraph<(), ControlTransfer> {
+        use ControlTransfer::{Exception, SubroutineReturn, Unconditional};
+        let edges = [
+            (0.into(), 1.into(), Unconditional),
+            (1.into(), 2.into(), Unconditional),
+            (1.into(), 5.into(), SubroutineReturn),
+            (2.into(), 3.into(), Unconditional),
+            (2.into(), 8.into(), Exception(BTreeSet::new())),
+            (3.into(), 6.into(), Unconditional),
+            (5.into(), 6.into(), Unconditional),
+            (6.into(), 7.into(), Unconditional),
+            (8.into(), 9.into(), Unconditional),
+            (9.into(), 8.into(), Unconditional),
+            (10.into(), 11.into(), Unconditional),
+            (11.into(), 10.into(), Unconditional),
+        ];
+        ControlFlowGraph::from_edges(edges)
+    }
+
+    fn block(pcs: &[u16]) -> BasicBlock {
+        BasicBlock {
+            instructions: pcs.iter().copied().map(ProgramCounter::from).collect(),
+        }
+    }
+
+    #[test]
+    fn group_basic_blocks() {
+        let blocks = build_cfg().basic_blocks();
+
+        let nodes: Vec<_> = blocks
+            .nodes()
+            .map(|(pc, block)| (pc, block.clone()))
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![
+                (0.into(), block(&[0, 1])),
+                (2.into(), block(&[2])),
+                (3.into(), block(&[3])),
+                (5.into(), block(&[5])),
+                (6.into(), block(&[6, 7])),
+                (8.into(), block(&[8, 9])),
+                (10.into(), block(&[10, 11])),
+            ]
+        );
+        let edges: Vec<_> = blocks.edges().map(|(src, dst, _)| (src, dst)).collect();
+        assert_eq!(
+            edges,
+            [
+                (0, 2),
+                (0, 5),
+                (2, 3),
+                (2, 8),
+                (3, 6),
+                (5, 6),
+                (8, 8),
+                (10, 10)
+            ]
+            .map(|(src, dst)| (src.into(), dst.into()))
+        );
+        assert_eq!(blocks.block_of(7.into()), Some((6.into(), &block(&[6, 7]))));
+        assert_eq!(blocks.block_of(4.into()), None);
+    }
+
+    #[test]
+    fn restore_instruction_graph() {
+        let cfg = build_cfg();
+        let restored = cfg.basic_blocks().instruction_graph();
+
+        assert_eq!(
+            restored.nodes().collect::<Vec<_>>(),
+            cfg.nodes().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            restored.edges().collect::<Vec<_>>(),
+            cfg.edges().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "petgraph")]
+    fn petgraph_traversal() {
+        use petgraph::algo::has_path_connecting;
+
+        let blocks = build_cfg().basic_blocks();
+        assert!(has_path_connecting(&blocks, 0.into(), 8.into(), None));
+        assert!(!has_path_connecting(&blocks, 6.into(), 0.into(), None));
+    }
+}
@@ -0,0 +1,407 @@
+//! Dominator trees and dominance frontiers of control flow graphs.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{ir::ControlFlowGraph, jvm::code::ProgramCounter};
+
+/// A node in a post-dominator tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum PostDominanceNode {
+    /// A node of the control flow graph.
+    #[display("{_0}")]
+    Node(ProgramCounter),
+    /// The virtual exit succeeding all the exits of the control flow graph, so that a method
+    /// with multiple `return` or `throw` instructions has a single exit.
+    #[display("exit")]
+    VirtualExit,
+}
+
+/// A tree where the parent of each node is its immediate dominator.
+///
+/// A node `d` dominates a node `n` if every path from the root to `n` goes through `d`.
+/// The nodes not reachable from the root are not in the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DominatorTree<T> {
+    root: T,
+    immediate_dominators: BTreeMap<T, T>,
+}
+
+impl<T: Ord + Copy> DominatorTree<T> {
+    /// Returns the root of the tree, which dominates all the nodes.
+    #[must_use]
+    pub const fn root(&self) -> T {
+        self.root
+    }
+
+    /// Checks whether the node is in the tree, i.e., it is reachable from the root.
+    #[must_use]
+    pub fn contains(&self, node: T) -> bool {
+        node == self.root || self.immediate_dominators.contains_key(&node)
+    }
+
+    /// Returns the immediate dominator of a node, or `None` if it is the root or not in the tree.
+    #[must_use]
+    pub fn immediate_dominator(&self, node: T) -> Option<T> {
+        self.immediate_dominators.get(&node).copied()
+    }
+
+    /// Returns an iterator over the dominators of a node, starting from the node itself and
+    /// ending with the root.
+    /// It is empty if the node is not in the tree.
+    pub fn dominators(&self, node: T) -> impl Iterator<Item = T> + '_ {
+        let start = self.contains(node).then_some(node);
+        std::iter::successors(start, |it| self.immediate_dominator(*it))
+    }
+
+    /// Checks whether `dominator` dominates `node`.
+    /// Every node in the tree dominates itself.
+    #[must_use]
+    pub fn dominates(&self, dominator: T, node: T) -> bool {
+        self.dominators(node).any(|it| it == dominator)
+    }
+
+    /// Checks whether `dominator` dominates `node` and is not `node` itself.
+    #[must_use]
+    pub fn strictly_dominates(&self, dominator: T, node: T) -> bool {
+        dominator != node && self.dominates(dominator, node)
+    }
+
+    /// Returns the nodes immediately dominated by a node.
+    #[must_use]
+    pub fn children(&self, node: T) -> BTreeSet<T> {
+        self.immediate_dominators
+            .iter()
+            .filter(|(_, idom)| **idom == node)
+            .map(|(child, _)| *child)
+            .collect()
+    }
+
+    /// Returns an iterator over the nodes in the tree.
+    pub fn nodes(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::once(self.root).chain(self.immediate_dominators.keys().copied())
+    }
+}
+
+impl<N, E> ControlFlowGraph<N, E> {
+    /// Computes the dominator tree rooted at the entry point.
+    ///
+    /// All the edges are followed, including the ones to exception handlers, so that a handler
+    /// is dominated by the instructions dominating every instruction it covers.
+    #[must_use]
+    pub fn dominator_tree(&self) -> DominatorTree<ProgramCounter> {
+        let (immediate_dominators, _) = self.forward_dominance();
+        DominatorTree {
+            root: self.entry_point(),
+            immediate_dominators,
+        }
+    }
+
+    /// Computes the post-dominator tree, which is the dominator tree of the reversed graph
+    /// rooted at a [`PostDominanceNode::VirtualExit`] succeeding all the exits.
+    ///
+    /// As with [`Self::dominator_tree`], the edges to exception handlers are followed.
+    /// The nodes that cannot reach an exit, e.g., in an infinite loop, are not in the tree.
+    #[must_use]
+    pub fn post_dominator_tree(&self) -> DominatorTree<PostDominanceNode> {
+        let (immediate_dominators, _) = self.post_dominance();
+        DominatorTree {
+            root: PostDominanceNode::VirtualExit,
+            immediate_dominators,
+        }
+    }
+
+    /// Computes the dominance frontier of each node reachable from the entry point, i.e., the
+    /// nodes where its dominance ends.
+    /// A node `f` is in the dominance frontier of `n` if `n` dominates a predecessor of `f` but
+    /// does not strictly dominate `f`.
+    /// They are where SSA construction places the Phi functions.
+    #[must_use]
+    pub fn dominance_frontiers(&self) -> BTreeMap<ProgramCounter, BTreeSet<ProgramCounter>> {
+        let (_, frontiers) = self.forward_dominance();
+        frontiers
+    }
+
+    /// Computes the post-dominance frontier of each node that can reach an exit, i.e., the
+    /// dominance frontiers in the reversed graph.
+    /// A node is control dependent on the nodes in its post-dominance frontier.
+    #[must_use]
+    pub fn post_dominance_frontiers(
+        &self,
+    ) -> BTreeMap<PostDominanceNode, BTreeSet<PostDominanceNode>> {
+        let (_, frontiers) = self.post_dominance();
+        frontiers
+    }
+
+    fn predecessors(&self) -> BTreeMap<ProgramCounter, Vec<ProgramCounter>> {
+        let mut predecessors: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (src, dst, _) in self.edges() {
+            predecessors.entry(dst).or_default().push(src);
+        }
+        predecessors
+    }
+
+    fn forward_dominance(&self) -> Dominance<ProgramCounter> {
+        let predecessors = self.predecessors();
+        let successors = |node: ProgramCounter| {
+            self.edges_from(node)
+                .into_iter()
+                .flatten()
+                .map(|(_, dst, _)| dst)
+                .collect()
+        };
+        let predecessors =
+            |node: ProgramCounter| predecessors.get(&node).cloned().unwrap_or_default();
+        dominance(self.entry_point(), successors, &predecessors)
+    }
+
+    fn post_dominance(&self) -> Dominance<PostDominanceNode> {
+        use PostDominanceNode::{Node, VirtualExit};
+
+        let predecessors = self.predecessors();
+        let exits: Vec<_> = self.exits().map(Node).collect();
+        // The successors and predecessors in the reversed graph.
+        let successors = |node: PostDominanceNode| match node {
+            VirtualExit => exits.clone(),
+            Node(pc) => predecessors
+                .get(&pc)
+                .into_iter()
+                .flatten()
+                .copied()
+                .map(Node)
+                .collect(),
+        };
+        let predecessors = |node: PostDominanceNode| match node {
+            VirtualExit => Vec::new(),
+            Node(pc) => match self.edges_from(pc) {
+                Some(edges) => {
+                    let successors: Vec<_> = edges.map(|(_, dst, _)| Node(dst)).collect();
+                    if successors.is_empty() {
+                        vec![VirtualExit]
+                    } else {
+                        successors
+                    }
+                }
+                None => Vec::new(),
+            },
+        };
+        dominance(VirtualExit, successors, &predecessors)
+    }
+}
+
+type Dominance<T> = (BTreeMap<T, T>, BTreeMap<T, BTreeSet<T>>);
+
+/// Computes the immediate dominators and the dominance frontiers of the nodes reachable from
+/// the root, using the algorithm in "A Simple, Fast Dominance Algorithm" by Cooper, Harvey and
+/// Kennedy.
+fn dominance<T, S, P>(root: T, successors: S, predecessors: &P) -> Dominance<T>
+where
+    T: Ord + Copy,
+    S: Fn(T) -> Vec<T>,
+    P: Fn(T) -> Vec<T>,
+{
+    // Numbers the nodes in post-order with an iterative depth-first search.
+    let mut post_order = Vec::new();
+    let mut visited = BTreeSet::from([root]);
+    let mut stack = vec![(root, successors(root).into_iter())];
+    while let Some((node, children)) = stack.last_mut() {
+        if let Some(child) = children.next() {
+            if visited.insert(child) {
+                stack.push((child, successors(child).into_iter()));
+            }
+        } else {
+            post_order.push(*node);
+            stack.pop();
+        }
+    }
+    let post_order_index: BTreeMap<_, _> = post_order
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (*node, index))
+        .collect();
+
+    let mut immediate_dominators = BTreeMap::from([(root, root)]);
+    let intersect = |immediate_dominators: &BTreeMap<T, T>, mut lhs: T, mut rhs: T| {
+        while lhs != rhs {
+            while post_order_index[&lhs] < post_order_index[&rhs] {
+                lhs = immediate_dominators[&lhs];
+            }
+            while post_order_index[&rhs] < post_order_index[&lhs] {
+                rhs = immediate_dominators[&rhs];
+            }
+        }
+        lhs
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in post_order.iter().rev().filter(|it| **it != root) {
+            let new_idom = predecessors(node)
+                .into_iter()
+                .filter(|it| immediate_dominators.contains_key(it))
+                .reduce(|lhs, rhs| intersect(&immediate_dominators, lhs, rhs));
+            if let Some(new_idom) = new_idom {
+                if immediate_dominators.insert(node, new_idom) != Some(new_idom) {
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let mut frontiers: BTreeMap<_, BTreeSet<_>> =
+        post_order.iter().map(|it| (*it, BTreeSet::new())).collect();
+    for &node in &post_order {
+        let node_predecessors: Vec<_> = predecessors(node)
+            .into_iter()
+            .filter(|it| post_order_index.contains_key(it))
+            .collect();
+        if node_predecessors.len() < 2 {
+            continue;
+        }
+        let idom = immediate_dominators[&node];
+        for mut runner in node_predecessors {
+            while runner != idom {
+                frontiers.entry(runner).or_default().insert(node);
+                if runner == root {
+                    break;
+                }
+                runner = immediate_dominators[&runner];
+            }
+        }
+    }
+
+    immediate_dominators.remove(&root);
+    (immediate_dominators, frontiers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::control_flow::ControlTransfer;
+
+    /// Builds a graph with a loop from 4 back to 1, two exits 5 and 6, where 6 is an exception
+    /// handler, and an unreachable node 7.
+    fn build_cfg() -> ControlFlowGraph<(), ControlTransfer> {
+        use ControlTransfer::{Exception, Unconditional};
+        let edges = [
+            (0, 1, Unconditional),
+            (1, 2, Unconditional),
+            (1, 3, Unconditional),
+            (2, 4, Unconditional),
+            (3, 4, Unconditional),
+            (3, 6, Exception(BTreeSet::new())),
+            (4, 1, Unconditional),
+            (4, 5, Unconditional),
+            (7, 5, Unconditional),
+        ];
+        ControlFlowGraph::from_edges(
+            edges
+                .into_iter()
+                .map(|(src, dst, transfer)| (src.into(), dst.into(), transfer)),
+        )
+    }
+
+    fn pcs<const N: usize>(pcs: [u16; N]) -> BTreeSet<ProgramCounter> {
+        pcs.into_iter().map(ProgramCounter::from).collect()
+    }
+
+    #[test]
+    fn dominator_tree() {
+        let tree = build_cfg().dominator_tree();
+
+        assert_eq!(tree.root(), 0.into());
+        let idoms: Vec<_> = (0..=7)
+            .map(|it| tree.immediate_dominator(it.into()).map(u16::from))
+            .collect();
+        assert_eq!(
+            idoms,
+            [
+                None,
+                Some(0),
+                Some(1),
+                Some(1),
+                Some(1),
+                Some(4),
+                Some(3),
+                None
+            ]
+        );
+        assert!(!tree.contains(7.into()));
+        assert!(tree.dominates(1.into(), 5.into()));
+        assert!(tree.dominates(5.into(), 5.into()));
+        assert!(!tree.strictly_dominates(5.into(), 5.into()));
+        assert!(!tree.dominates(2.into(), 5.into()));
+        assert_eq!(tree.children(1.into()), pcs([2, 3, 4]));
+        assert_eq!(
+            tree.dominators(6.into()).collect::<Vec<_>>(),
+            [6, 3, 1, 0].map(ProgramCounter::from)
+        );
+    }
+
+    #[test]
+    fn dominance_frontiers() {
+        let frontiers = build_cfg().dominance_frontiers();
+
+        assert_eq!(
+            frontiers,
+            BTreeMap::from([
+                (0.into(), pcs([])),
+                (1.into(), pcs([1])),
+                (2.into(), pcs([4])),
+                (3.into(), pcs([4])),
+                (4.into(), pcs([1])),
+                (5.into(), pcs([])),
+                (6.into(), pcs([])),
+            ])
+        );
+    }
+
+    #[test]
+    fn post_dominator_tree() {
+        use PostDominanceNode::{Node, VirtualExit};
+
+        let tree = build_cfg().post_dominator_tree();
+
+        assert_eq!(tree.root(), VirtualExit);
+        let ipdoms: Vec<_> = (0..=7)
+            .map(|it| tree.immediate_dominator(Node(it.into())))
+            .collect();
+        assert_eq!(
+            ipdoms,
+            [
+                Some(Node(1.into())),
+                Some(VirtualExit),
+                Some(Node(4.into())),
+                Some(VirtualExit),
+                Some(VirtualExit),
+                Some(VirtualExit),
+                Some(VirtualExit),
+                Some(Node(5.into())),
+            ]
+        );
+        assert!(tree.dominates(Node(4.into()), Node(2.into())));
+    }
+
+    #[test]
+    fn post_dominance_frontiers() {
+        use PostDominanceNode::{Node, VirtualExit};
+
+        let frontiers = build_cfg().post_dominance_frontiers();
+
+        let node = |pc: u16| Node(pc.into());
+        let nodes = |pcs: &[u16]| pcs.iter().copied().map(node).collect::<BTreeSet<_>>();
+        assert_eq!(
+            frontiers,
+            BTreeMap::from([
+                (VirtualExit, nodes(&[])),
+                (node(0), nodes(&[])),
+                (node(1), nodes(&[4])),
+                (node(2), nodes(&[1])),
+                (node(3), nodes(&[1])),
+                (node(4), nodes(&[1, 3])),
+                (node(5), nodes(&[4])),
+                (node(6), nodes(&[3])),
+                (node(7), nodes(&[])),
+            ])
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! Control flow analysis
 
+mod basic_block;
+mod dominance;
 pub mod path_condition;
 
 use crate::{
@@ -10,6 +12,9 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use self::path_condition::{PathCondition, Predicate, Value};
 
+pub use basic_block::{BasicBlock, BasicBlockGraph};
+pub use dominance::{DominatorTree, PostDominanceNode};
+
 use super::ControlFlowGraph;
 
 /// The kind of a control transfer.
@@ -245,6 +245,7 @@ mod test {
     use itertools::Itertools;
     use proptest::collection::btree_set;
     use proptest::prelude::*;
+    use rand::Rng;
 
     use crate::ir::control_flow::path_condition::Conjunction;
 
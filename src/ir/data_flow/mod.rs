@@ -1,5 +1,7 @@
 //! Data flow analysis.
 
+pub mod type_inference;
+
 use std::collections::BTreeSet;
 
 use itertools::Itertools;
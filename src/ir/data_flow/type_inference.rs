@@ -0,0 +1,741 @@
+//! Type inference for the values in Moka IR.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    analysis::{ResolutionContext, JAVA_LANG_OBJECT},
+    ir::{
+        expression::{ArrayOperation, Conversion, Expression, FieldAccess, MathOperation},
+        Identifier, MokaIRMethod, MokaInstruction, Operand,
+    },
+    jvm::{
+        code::{StackMapFrame, VerificationType},
+        references::ClassRef,
+        ConstantValue,
+    },
+    macros::see_jvm_spec,
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::ReturnType,
+    },
+};
+
+const JAVA_LANG_THROWABLE: &str = "java/lang/Throwable";
+
+/// The type of a value in Moka IR.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum ValueType {
+    /// A value of a primitive or reference type.
+    #[display("{_0}")]
+    Typed(FieldType),
+    /// The `null` reference, which is assignable to any reference type.
+    #[display("null")]
+    Null,
+    /// A return address of a subroutine.
+    #[display("return_address")]
+    ReturnAddress,
+    /// The absence of a value, e.g., the result of a call to a `void` method or of a field write.
+    #[display("void")]
+    Void,
+    /// A value whose type cannot be determined, e.g., because it merges a primitive value with
+    /// a value of another type.
+    #[display("top")]
+    Top,
+}
+
+impl ValueType {
+    fn object(binary_name: &str) -> Self {
+        Self::Typed(FieldType::Object(ClassRef::new(binary_name)))
+    }
+
+    const fn primitive(primitive_type: PrimitiveType) -> Self {
+        Self::Typed(FieldType::Base(primitive_type))
+    }
+
+    /// Checks if the value is a reference, i.e., an object, an array or `null`.
+    #[must_use]
+    pub const fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            Self::Null | Self::Typed(FieldType::Object(_) | FieldType::Array(_))
+        )
+    }
+
+    /// Returns the type used by the JVM for computations, where `boolean`, `byte`, `char` and
+    /// `short` values are `int`s.
+    #[must_use]
+    pub fn computational_type(&self) -> Self {
+        use PrimitiveType::{Boolean, Byte, Char, Int, Short};
+        match self {
+            Self::Typed(FieldType::Base(Boolean | Byte | Char | Short)) => Self::primitive(Int),
+            _ => self.clone(),
+        }
+    }
+
+    /// Computes the least upper bound of two types.
+    /// The least upper bound of two reference types is computed in the way of the JVM verifier
+    /// (see [`ResolutionContext::verifier_lub`]), falling back to `java/lang/Object` if a class
+    /// needed to compute it is missing.
+    fn join(&self, other: &Self, context: &ResolutionContext) -> Self {
+        match (self, other) {
+            (lhs, rhs) if lhs == rhs => lhs.clone(),
+            (Self::Null, it) | (it, Self::Null) if it.is_reference() => it.clone(),
+            (Self::Typed(lhs), Self::Typed(rhs)) if self.is_reference() && other.is_reference() => {
+                context
+                    .verifier_lub(lhs, rhs)
+                    .ok()
+                    .flatten()
+                    .map_or_else(|| Self::object(JAVA_LANG_OBJECT), Self::Typed)
+            }
+            (lhs, rhs) if lhs.computational_type() == rhs.computational_type() => {
+                lhs.computational_type()
+            }
+            _ => Self::Top,
+        }
+    }
+
+    /// Refines an inferred type with the type declared for the same value in the
+    /// `StackMapTable`. The inferred type is kept if it is more specific than the declared one,
+    /// and is replaced by the declared type if it is unknown, or if it is a reference type that
+    /// is not known to be assignable to the declared type, e.g., because the least upper bound
+    /// fell back to `java/lang/Object`.
+    fn refine(self, declared: Option<&Self>, context: &ResolutionContext) -> Self {
+        let Some(declared) = declared else {
+            return self;
+        };
+        match (&self, declared) {
+            (Self::Top, _) => declared.clone(),
+            (Self::Typed(inferred), Self::Typed(declared_type))
+                if self.is_reference() && declared.is_reference() =>
+            {
+                if context
+                    .is_assignable(inferred, declared_type)
+                    .unwrap_or(false)
+                {
+                    self
+                } else {
+                    declared.clone()
+                }
+            }
+            _ => self,
+        }
+    }
+
+    /// Converts a verification type to the type of the value it describes, or `None` if it
+    /// does not describe an initialized value.
+    fn of_verification_type(verification_type: &VerificationType) -> Option<Self> {
+        let ty = match verification_type {
+            VerificationType::IntegerVariable => Self::primitive(PrimitiveType::Int),
+            VerificationType::FloatVariable => Self::primitive(PrimitiveType::Float),
+            VerificationType::LongVariable => Self::primitive(PrimitiveType::Long),
+            VerificationType::DoubleVariable => Self::primitive(PrimitiveType::Double),
+            VerificationType::NullVariable => Self::Null,
+            VerificationType::ObjectVariable(class_ref) => {
+                Self::Typed(array_element(&FieldType::Object(class_ref.clone())))
+            }
+            VerificationType::TopVariable
+            | VerificationType::UninitializedThisVariable
+            | VerificationType::UninitializedVariable { .. } => return None,
+        };
+        Some(ty)
+    }
+}
+
+/// The types of the values in a [`MokaIRMethod`].
+#[derive(Debug, Clone, Default)]
+pub struct ValueTypes {
+    types: BTreeMap<Identifier, ValueType>,
+}
+
+impl ValueTypes {
+    /// Infers the type of every [`Identifier`] in a method.
+    /// - `this` and the arguments have the types in the method descriptor.
+    /// - A locally defined value has the result type of its defining [`Expression`], where the
+    ///   result types of arithmetic operations and array reads depend on the types of their
+    ///   operands.
+    /// - The type of an [`Operand::Phi`] is the least upper bound of the types of the values it
+    ///   combines.
+    /// - The caught exception has the least upper bound of the types of the exceptions caught
+    ///   by the exception handlers.
+    ///
+    /// When the `StackMapTable` is present, the types it declares for the local variables and
+    /// the operand stack at each frame refine the types of the values found there (see
+    /// [`MokaIRMethod::frames`]). In particular, they give a type to the values that depend only
+    /// on themselves and to the values whose least upper bound needs a missing class.
+    ///
+    /// The types are computed iteratively until they reach a fixed point, since a value can
+    /// depend on itself through a loop.
+    #[must_use]
+    pub fn infer(
+        method: &MokaIRMethod,
+        stack_map_table: Option<&[StackMapFrame]>,
+        context: &ResolutionContext,
+    ) -> Self {
+        let declared = stack_map_table
+            .map(|it| declared_types(method, it, context))
+            .unwrap_or_default();
+        let mut inference = Self::default();
+        if !method.is_static() {
+            inference.types.insert(
+                Identifier::This,
+                ValueType::Typed(FieldType::Object(method.owner.clone())),
+            );
+        }
+        for (index, param_type) in (0..).zip(&method.descriptor.parameters_types) {
+            inference
+                .types
+                .insert(Identifier::Arg(index), ValueType::Typed(param_type.clone()));
+        }
+        if let Some(caught_exception) = caught_exception_type(method, context) {
+            inference
+                .types
+                .insert(Identifier::CaughtException, caught_exception);
+        }
+        for (id, ty) in &mut inference.types {
+            *ty = ty.clone().refine(declared.get(id), context);
+        }
+
+        let definitions: Vec<_> = method
+            .instructions
+            .iter()
+            .filter_map(|(_, insn)| match insn {
+                MokaInstruction::Definition { value, expr } => {
+                    Some((Identifier::from(*value), expr))
+                }
+                _ => None,
+            })
+            .collect();
+        loop {
+            inference.propagate(&definitions, &declared, context);
+            // The values depending only on themselves get their types from the
+            // `StackMapTable`, which may in turn give types to the values depending on them.
+            let undetermined: Vec<_> = definitions
+                .iter()
+                .filter(|(id, _)| !inference.types.contains_key(id))
+                .filter_map(|(id, _)| Some((*id, declared.get(id)?.clone())))
+                .collect();
+            if undetermined.is_empty() {
+                break;
+            }
+            inference.types.extend(undetermined);
+        }
+        // The values depending only on themselves and absent from the `StackMapTable` never
+        // get a type.
+        for (id, _) in definitions {
+            inference.types.entry(id).or_insert(ValueType::Top);
+        }
+        inference
+    }
+
+    /// Updates the types of the locally defined values until they reach a fixed point.
+    fn propagate(
+        &mut self,
+        definitions: &[(Identifier, &Expression)],
+        declared: &BTreeMap<Identifier, ValueType>,
+        context: &ResolutionContext,
+    ) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (id, expr) in definitions {
+                let Some(inferred) = self.expression_type(expr, context) else {
+                    continue;
+                };
+                let updated = match self.types.get(id) {
+                    Some(current) => current.join(&inferred, context),
+                    None => inferred,
+                }
+                .refine(declared.get(id), context);
+                if self.types.get(id) != Some(&updated) {
+                    self.types.insert(*id, updated);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// Returns the type of an [`Identifier`], or `None` if it is not used in the method.
+    #[must_use]
+    pub fn type_of(&self, id: &Identifier) -> Option<&ValueType> {
+        self.types.get(id)
+    }
+
+    /// Returns an iterator over the [`Identifier`]s and their types.
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &ValueType)> {
+        self.types.iter()
+    }
+
+    /// Computes the type of an [`Operand`], joining the types of the values it combines.
+    /// Returns `None` if the type of none of them is known yet.
+    fn operand_type(&self, operand: &Operand, context: &ResolutionContext) -> Option<ValueType> {
+        operand
+            .iter()
+            .filter_map(|it| self.types.get(it))
+            .fold(None, |acc, it| match acc {
+                None => Some(it.clone()),
+                Some(acc) => Some(acc.join(it, context)),
+            })
+    }
+
+    /// Computes the result type of an expression, or `None` if it depends on operands whose
+    /// types are not known yet.
+    fn expression_type(&self, expr: &Expression, context: &ResolutionContext) -> Option<ValueType> {
+        let ty = match expr {
+            Expression::Const(constant) => constant_type(constant),
+            Expression::Call { method, .. } => return_type(&method.descriptor.return_type),
+            Expression::Closure {
+                closure_descriptor, ..
+            } => return_type(&closure_descriptor.return_type),
+            Expression::Math(operation) => return self.math_type(operation, context),
+            Expression::Field(
+                FieldAccess::ReadStatic { field } | FieldAccess::ReadInstance { field, .. },
+            ) => ValueType::Typed(field.field_type.clone()),
+            Expression::Field(
+                FieldAccess::WriteStatic { .. } | FieldAccess::WriteInstance { .. },
+            )
+            | Expression::Array(ArrayOperation::Write { .. })
+            | Expression::Throw(_)
+            | Expression::Synchronization(_) => ValueType::Void,
+            Expression::Array(ArrayOperation::New { element_type, .. }) => {
+                ValueType::Typed(array_element(element_type).into_array_type())
+            }
+            // The type of a multidimensional array is the type of the array itself.
+            Expression::Array(ArrayOperation::NewMultiDim { element_type, .. }) => {
+                ValueType::Typed(element_type.clone())
+            }
+            Expression::Array(ArrayOperation::Read { array_ref, .. }) => {
+                match self.operand_type(array_ref, context)? {
+                    ValueType::Typed(FieldType::Array(element_type)) => {
+                        ValueType::Typed(*element_type)
+                    }
+                    ValueType::Null => ValueType::Null,
+                    _ => ValueType::Top,
+                }
+            }
+            Expression::Array(ArrayOperation::Length { .. }) => {
+                ValueType::primitive(PrimitiveType::Int)
+            }
+            Expression::Conversion(conversion) => conversion_type(conversion),
+            Expression::New(class_ref) => ValueType::Typed(FieldType::Object(class_ref.clone())),
+            Expression::Subroutine { .. } => ValueType::ReturnAddress,
+        };
+        Some(ty)
+    }
+
+    fn math_type(
+        &self,
+        operation: &MathOperation,
+        context: &ResolutionContext,
+    ) -> Option<ValueType> {
+        use MathOperation::{
+            Add, BitwiseAnd, BitwiseOr, BitwiseXor, Divide, FloatingPointComparison, Increment,
+            LogicalShiftRight, LongComparison, Multiply, Negate, Remainder, ShiftLeft, ShiftRight,
+            Subtract,
+        };
+        match operation {
+            Add(lhs, rhs)
+            | Subtract(lhs, rhs)
+            | Multiply(lhs, rhs)
+            | Divide(lhs, rhs)
+            | Remainder(lhs, rhs)
+            | BitwiseAnd(lhs, rhs)
+            | BitwiseOr(lhs, rhs)
+            | BitwiseXor(lhs, rhs) => self
+                .operand_type(lhs, context)
+                .or_else(|| self.operand_type(rhs, context))
+                .map(|it| it.computational_type()),
+            // The type of a shift is the type of the shifted value.
+            ShiftLeft(operand, _)
+            | ShiftRight(operand, _)
+            | LogicalShiftRight(operand, _)
+            | Negate(operand) => self
+                .operand_type(operand, context)
+                .map(|it| it.computational_type()),
+            Increment(..) | LongComparison(..) | FloatingPointComparison(..) => {
+                Some(ValueType::primitive(PrimitiveType::Int))
+            }
+        }
+    }
+}
+
+fn constant_type(constant: &ConstantValue) -> ValueType {
+    match constant {
+        ConstantValue::Null => ValueType::Null,
+        ConstantValue::Integer(_) => ValueType::primitive(PrimitiveType::Int),
+        ConstantValue::Float(_) => ValueType::primitive(PrimitiveType::Float),
+        ConstantValue::Long(_) => ValueType::primitive(PrimitiveType::Long),
+        ConstantValue::Double(_) => ValueType::primitive(PrimitiveType::Double),
+        ConstantValue::String(_) => ValueType::object("java/lang/String"),
+        ConstantValue::Class(_) => ValueType::object("java/lang/Class"),
+        ConstantValue::Handle(_) => ValueType::object("java/lang/invoke/MethodHandle"),
+        ConstantValue::MethodType(_) => ValueType::object("java/lang/invoke/MethodType"),
+        ConstantValue::Dynamic(_, _, field_type) => ValueType::Typed(field_type.clone()),
+    }
+}
+
+fn return_type(return_type: &ReturnType) -> ValueType {
+    match return_type {
+        ReturnType::Some(field_type) => ValueType::Typed(field_type.clone()),
+        ReturnType::Void => ValueType::Void,
+    }
+}
+
+fn conversion_type(conversion: &Conversion) -> ValueType {
+    use PrimitiveType::{Byte, Char, Double, Float, Int, Long, Short};
+    let primitive_type = match conversion {
+        Conversion::Long2Int(_)
+        | Conversion::Float2Int(_)
+        | Conversion::Double2Int(_)
+        | Conversion::InstanceOf(..) => Int,
+        Conversion::Int2Long(_) | Conversion::Float2Long(_) | Conversion::Double2Long(_) => Long,
+        Conversion::Int2Float(_) | Conversion::Long2Float(_) | Conversion::Double2Float(_) => Float,
+        Conversion::Int2Double(_) | Conversion::Long2Double(_) | Conversion::Float2Double(_) => {
+            Double
+        }
+        Conversion::Int2Byte(_) => Byte,
+        Conversion::Int2Char(_) => Char,
+        Conversion::Int2Short(_) => Short,
+        Conversion::CheckCast(_, target_type) => return ValueType::Typed(target_type.clone()),
+    };
+    ValueType::primitive(primitive_type)
+}
+
+/// Returns the element type of an `anewarray` instruction, whose class may be an array class.
+fn array_element(element_type: &FieldType) -> FieldType {
+    match element_type {
+        FieldType::Object(class_ref) if class_ref.binary_name.starts_with('[') => class_ref
+            .binary_name
+            .parse()
+            .unwrap_or_else(|_| element_type.clone()),
+        _ => element_type.clone(),
+    }
+}
+
+/// Computes the type of the caught exception, or `None` if the method has no exception handler.
+fn caught_exception_type(method: &MokaIRMethod, context: &ResolutionContext) -> Option<ValueType> {
+    method
+        .exception_table
+        .iter()
+        .map(|entry| {
+            entry.catch_type.as_ref().map_or_else(
+                || ValueType::object(JAVA_LANG_THROWABLE),
+                |it| ValueType::Typed(FieldType::Object(it.clone())),
+            )
+        })
+        .reduce(|acc, it| acc.join(&it, context))
+}
+
+/// Collects the types that the `StackMapTable` declares for the values in the local variables
+/// and on the operand stack of its frames.
+/// A value found in several frames gets the most specific of the types declared for it.
+fn declared_types(
+    method: &MokaIRMethod,
+    stack_map_table: &[StackMapFrame],
+    context: &ResolutionContext,
+) -> BTreeMap<Identifier, ValueType> {
+    let mut declared = BTreeMap::new();
+    let mut declare = |operand: &Operand, verification_type: &VerificationType| {
+        let Some(ty) = ValueType::of_verification_type(verification_type) else {
+            return;
+        };
+        for id in operand.iter() {
+            let refined = match declared.remove(id) {
+                Some(existing) => ValueType::refine(existing, Some(&ty), context),
+                None => ty.clone(),
+            };
+            declared.insert(*id, refined);
+        }
+    };
+    let mut locals = initial_locals(method);
+    for (pc, frame) in StackMapFrame::with_offsets(stack_map_table) {
+        let stack = match frame {
+            StackMapFrame::SameFrame { .. } => Vec::new(),
+            StackMapFrame::SameLocals1StackItemFrame { stack, .. } => vec![stack.clone()],
+            StackMapFrame::ChopFrame { chop_count, .. } => {
+                locals.truncate(locals.len().saturating_sub((*chop_count).into()));
+                Vec::new()
+            }
+            StackMapFrame::AppendFrame {
+                locals: appended, ..
+            } => {
+                locals.extend_from_slice(appended);
+                Vec::new()
+            }
+            StackMapFrame::FullFrame {
+                locals: full_locals,
+                stack,
+                ..
+            } => {
+                locals.clone_from(full_locals);
+                stack.clone()
+            }
+        };
+        let Some(values) = method.frames.get(&pc) else {
+            continue;
+        };
+        let mut slot = 0;
+        for verification_type in &locals {
+            if let Some(Some(operand)) = values.locals.get(slot) {
+                declare(operand, verification_type);
+            }
+            slot += match verification_type {
+                VerificationType::LongVariable | VerificationType::DoubleVariable => 2,
+                _ => 1,
+            };
+        }
+        if values.stack.len() == stack.len() {
+            for (operand, verification_type) in values.stack.iter().zip(&stack) {
+                declare(operand, verification_type);
+            }
+        }
+    }
+    declared
+}
+
+/// Computes the verification types of the local variables at the entry of a method, which the
+/// frames in the `StackMapTable` are relative to.
+#[doc = see_jvm_spec!(4, 10, 1, 6)]
+fn initial_locals(method: &MokaIRMethod) -> Vec<VerificationType> {
+    let this = if method.is_static() {
+        None
+    } else if method.name == "<init>" {
+        Some(VerificationType::UninitializedThisVariable)
+    } else {
+        Some(VerificationType::ObjectVariable(method.owner.clone()))
+    };
+    let params = method.descriptor.parameters_types.iter().map(|it| {
+        use PrimitiveType::{Boolean, Byte, Char, Double, Float, Int, Long, Short};
+        match it {
+            FieldType::Base(Boolean | Byte | Char | Short | Int) => {
+                VerificationType::IntegerVariable
+            }
+            FieldType::Base(Float) => VerificationType::FloatVariable,
+            FieldType::Base(Long) => VerificationType::LongVariable,
+            FieldType::Base(Double) => VerificationType::DoubleVariable,
+            FieldType::Object(class_ref) => VerificationType::ObjectVariable(class_ref.clone()),
+            FieldType::Array(_) => VerificationType::ObjectVariable(ClassRef::new(it.descriptor())),
+        }
+    });
+    this.into_iter().chain(params).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::{LocalValue, MokaIRMethodExt},
+        jvm::{
+            code::{ExceptionTableEntry, Instruction, MethodBody},
+            method, Method,
+        },
+        tests::{class_with_supertypes, context_with, method_named},
+    };
+
+    fn method_with_body(
+        descriptor: &str,
+        access_flags: method::AccessFlags,
+        instructions: impl IntoIterator<Item = (u16, Instruction)>,
+    ) -> Method {
+        let mut method = method_named("org/mokapot/Test", "test", descriptor, access_flags);
+        method.body = Some(MethodBody {
+            max_stack: 4,
+            max_locals: 4,
+            instructions: instructions
+                .into_iter()
+                .map(|(pc, insn)| (pc.into(), insn))
+                .collect::<BTreeMap<_, _>>()
+                .into(),
+            exception_table: Vec::new(),
+            line_number_table: None,
+            local_variable_table: None,
+            stack_map_table: None,
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+        });
+        method
+    }
+
+    fn local(id: u16) -> Identifier {
+        LocalValue::new(id).into()
+    }
+
+    #[test]
+    fn descriptor_and_expression_types() {
+        let method = method_with_body(
+            "(JB)J",
+            method::AccessFlags::PUBLIC,
+            [
+                (0, Instruction::LLoad1),
+                (1, Instruction::ILoad3),
+                (2, Instruction::I2L),
+                (3, Instruction::LAdd),
+                (4, Instruction::ILoad3),
+                (5, Instruction::ILoad3),
+                (6, Instruction::IAdd),
+                (7, Instruction::Pop),
+                (8, Instruction::LReturn),
+            ],
+        );
+        let ir = method.brew().unwrap();
+        let types = ValueTypes::infer(&ir, None, &context_with([]));
+
+        let long = ValueType::primitive(PrimitiveType::Long);
+        assert_eq!(
+            types.type_of(&Identifier::This),
+            Some(&ValueType::object("org/mokapot/Test"))
+        );
+        assert_eq!(types.type_of(&Identifier::Arg(0)), Some(&long));
+        assert_eq!(
+            types.type_of(&Identifier::Arg(1)),
+            Some(&ValueType::primitive(PrimitiveType::Byte))
+        );
+        assert_eq!(types.type_of(&local(2)), Some(&long));
+        assert_eq!(types.type_of(&local(3)), Some(&long));
+        assert_eq!(
+            types.type_of(&local(6)),
+            Some(&ValueType::primitive(PrimitiveType::Int))
+        );
+        assert_eq!(types.type_of(&Identifier::CaughtException), None);
+    }
+
+    #[test]
+    fn least_upper_bound_at_phi() {
+        let context = context_with([
+            class_with_supertypes("java/lang/Object", None, &[]),
+            class_with_supertypes("org/mokapot/Base", Some("java/lang/Object"), &[]),
+            class_with_supertypes("org/mokapot/A", Some("org/mokapot/Base"), &[]),
+            class_with_supertypes("org/mokapot/B", Some("org/mokapot/Base"), &[]),
+        ]);
+        let method = method_with_body(
+            "(Z)Ljava/lang/Object;",
+            method::AccessFlags::PUBLIC | method::AccessFlags::STATIC,
+            [
+                (0, Instruction::ILoad0),
+                (1, Instruction::IfEq(10.into())),
+                (4, Instruction::IConst1),
+                (5, Instruction::ANewArray(ClassRef::new("org/mokapot/A"))),
+                (8, Instruction::Goto(14.into())),
+                (10, Instruction::IConst1),
+                (11, Instruction::ANewArray(ClassRef::new("org/mokapot/B"))),
+                (14, Instruction::IConst0),
+                (15, Instruction::AALoad),
+                (16, Instruction::AReturn),
+            ],
+        );
+        let ir = method.brew().unwrap();
+        let types = ValueTypes::infer(&ir, None, &context);
+
+        assert_eq!(
+            types.type_of(&local(5)),
+            Some(&ValueType::Typed(
+                FieldType::Object(ClassRef::new("org/mokapot/A")).into_array_type()
+            ))
+        );
+        assert_eq!(
+            types.type_of(&local(15)),
+            Some(&ValueType::object("org/mokapot/Base"))
+        );
+    }
+
+    #[test]
+    fn refine_with_stack_map_table() {
+        let mut method = method_with_body(
+            "(Z)Ljava/lang/Object;",
+            method::AccessFlags::PUBLIC | method::AccessFlags::STATIC,
+            [
+                (0, Instruction::ILoad0),
+                (1, Instruction::IfEq(10.into())),
+                (4, Instruction::IConst1),
+                (5, Instruction::ANewArray(ClassRef::new("org/mokapot/A"))),
+                (8, Instruction::Goto(14.into())),
+                (10, Instruction::IConst1),
+                (11, Instruction::ANewArray(ClassRef::new("org/mokapot/B"))),
+                (14, Instruction::IConst0),
+                (15, Instruction::AALoad),
+                (16, Instruction::AReturn),
+            ],
+        );
+        let stack_map_table = vec![
+            StackMapFrame::SameFrame { offset_delta: 10 },
+            StackMapFrame::SameLocals1StackItemFrame {
+                offset_delta: 3,
+                stack: VerificationType::ObjectVariable(ClassRef::new("[Lorg/mokapot/Base;")),
+            },
+        ];
+        method.body.as_mut().unwrap().stack_map_table = Some(stack_map_table.clone());
+        let ir = method.brew().unwrap();
+        assert_eq!(
+            ir.frames.keys().copied().collect::<Vec<_>>(),
+            vec![10.into(), 14.into()]
+        );
+        assert_eq!(
+            ir.frames[&14.into()].stack,
+            vec![Operand::Phi([local(5), local(11)].into())]
+        );
+        // The classes are missing, so the least upper bound falls back to `java/lang/Object`.
+        let context = context_with([]);
+        let types = ValueTypes::infer(&ir, None, &context);
+        assert_eq!(types.type_of(&local(15)), Some(&ValueType::Top));
+
+        let types = ValueTypes::infer(&ir, Some(&stack_map_table), &context);
+        let base_array = FieldType::Object(ClassRef::new("org/mokapot/Base")).into_array_type();
+        assert_eq!(
+            types.type_of(&local(5)),
+            Some(&ValueType::Typed(base_array.clone()))
+        );
+        assert_eq!(
+            types.type_of(&local(11)),
+            Some(&ValueType::Typed(base_array))
+        );
+        assert_eq!(
+            types.type_of(&local(15)),
+            Some(&ValueType::object("org/mokapot/Base"))
+        );
+        assert_eq!(
+            types.type_of(&Identifier::Arg(0)),
+            Some(&ValueType::primitive(PrimitiveType::Boolean))
+        );
+    }
+
+    #[test]
+    fn caught_exception() {
+        let mut method = method_with_body(
+            "()Ljava/lang/Throwable;",
+            method::AccessFlags::PUBLIC | method::AccessFlags::STATIC,
+            [
+                (0, Instruction::AConstNull),
+                (1, Instruction::AThrow),
+                (2, Instruction::AReturn),
+            ],
+        );
+        let body = method.body.as_mut().unwrap();
+        body.exception_table.push(ExceptionTableEntry {
+            covered_pc: 0.into()..=1.into(),
+            handler_pc: 2.into(),
+            catch_type: None,
+        });
+        let ir = method.brew().unwrap();
+        let context = context_with([]);
+
+        let types = ValueTypes::infer(&ir, None, &context);
+        assert_eq!(
+            types.type_of(&Identifier::CaughtException),
+            Some(&ValueType::object(JAVA_LANG_THROWABLE))
+        );
+        assert_eq!(types.type_of(&local(0)), Some(&ValueType::Null));
+        assert_eq!(types.type_of(&local(1)), Some(&ValueType::Void));
+
+        let stack_map_table = vec![StackMapFrame::SameLocals1StackItemFrame {
+            offset_delta: 2,
+            stack: VerificationType::ObjectVariable(ClassRef::new("java/lang/Exception")),
+        }];
+        method.body.as_mut().unwrap().stack_map_table = Some(stack_map_table.clone());
+        let ir = method.brew().unwrap();
+        let types = ValueTypes::infer(&ir, Some(&stack_map_table), &context);
+        assert_eq!(
+            types.type_of(&Identifier::CaughtException),
+            Some(&ValueType::object("java/lang/Exception"))
+        );
+    }
+}
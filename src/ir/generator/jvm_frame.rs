@@ -1,7 +1,7 @@
 use std::{collections::BTreeSet, iter::once};
 
 use crate::{
-    ir::{Identifier, Operand},
+    ir::{FrameValues, Identifier, Operand},
     jvm::code::ProgramCounter,
     types::{
         field_type::{FieldType, PrimitiveType},
@@ -95,6 +95,18 @@ impl JvmStackFrame {
         })
     }
 
+    /// Returns the values in the local variables and on the operand stack.
+    pub(super) fn values(&self) -> FrameValues {
+        let value = |entry: &Entry| match entry {
+            Entry::Value(it) => Some(it.clone()),
+            Entry::Top | Entry::UninitializedLocal => None,
+        };
+        FrameValues {
+            locals: self.local_variables.iter().map(value).collect(),
+            stack: self.operand_stack.iter().filter_map(value).collect(),
+        }
+    }
+
     #[inline]
     fn push_raw(&mut self, value: Entry) -> Result<(), ExecutionError> {
         let stack_size =
@@ -544,7 +556,7 @@ mod test {
                 true,
                 &"()V".parse().expect("Invalid method desc"),
                 0,
-                (values.len() + (values.len() + 1) / 2).try_into().unwrap(),
+                (values.len() + values.len().div_ceil(2)).try_into().unwrap(),
             ).unwrap();
             for (i, value) in values.iter().enumerate() {
                 if i % 2 == 0 {
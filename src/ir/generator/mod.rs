@@ -10,7 +10,7 @@ use std::{
 use crate::{
     ir::control_flow::path_condition::{PathCondition, Predicate},
     jvm::{
-        code::{ExceptionTableEntry, InstructionList, MethodBody, ProgramCounter},
+        code::{ExceptionTableEntry, InstructionList, MethodBody, ProgramCounter, StackMapFrame},
         method,
         references::ClassRef,
         ConstantValue, Method,
@@ -253,35 +253,36 @@ pub trait MokaIRMethodExt {
 
 impl MokaIRMethodExt for Method {
     fn brew(&self) -> Result<MokaIRMethod, MokaIRBrewingError> {
-        let (instructions, control_flow_graph) = MokaIRGenerator::for_method(self)?.generate()?;
-        Ok(MokaIRMethod {
-            access_flags: self.access_flags,
-            name: self.name.clone(),
-            owner: self.owner.clone(),
-            descriptor: self.descriptor.clone(),
-            instructions,
-            exception_table: self.body.as_ref().unwrap().exception_table.clone(),
-            control_flow_graph,
-        })
+        MokaIRGenerator::for_method(self)?.generate()
     }
 }
 
 impl MokaIRGenerator<'_> {
-    fn generate(
-        mut self,
-    ) -> Result<
-        (
-            InstructionList<MokaInstruction>,
-            ControlFlowGraph<(), ControlTransfer>,
-        ),
-        MokaIRBrewingError,
-    > {
-        self.analyze()?;
-        let cfg = ControlFlowGraph::from_edges(
+    fn generate(mut self) -> Result<MokaIRMethod, MokaIRBrewingError> {
+        let mut facts = self.analyze()?;
+        let frames = self
+            .body
+            .stack_map_table
+            .as_deref()
+            .map(StackMapFrame::with_offsets)
+            .into_iter()
+            .flatten()
+            .filter_map(|(pc, _)| Some((pc, facts.remove(&pc)?.values())))
+            .collect();
+        let control_flow_graph = ControlFlowGraph::from_edges(
             self.control_flow_edges
                 .into_iter()
                 .map(|((src, dst), trx)| (src, dst, trx)),
         );
-        Ok((InstructionList::from(self.ir_instructions), cfg))
+        Ok(MokaIRMethod {
+            access_flags: self.method.access_flags,
+            name: self.method.name.clone(),
+            owner: self.method.owner.clone(),
+            descriptor: self.method.descriptor.clone(),
+            instructions: InstructionList::from(self.ir_instructions),
+            exception_table: self.body.exception_table.clone(),
+            control_flow_graph,
+            frames,
+        })
     }
 }
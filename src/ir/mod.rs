@@ -44,6 +44,20 @@ pub struct MokaIRMethod {
     pub exception_table: Vec<ExceptionTableEntry>,
     /// The control flow graph of the method.
     pub control_flow_graph: ControlFlowGraph<(), ControlTransfer>,
+    /// The values in the local variables and on the operand stack at the instructions where
+    /// the `StackMapTable` of the method has a frame.
+    pub frames: BTreeMap<ProgramCounter, FrameValues>,
+}
+
+/// The values in the local variables and on the operand stack at the entry of an instruction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameValues {
+    /// The values in the local variables, indexed by slot.
+    /// Unused slots and the second slots of `long` and `double` values are `None`.
+    pub locals: Vec<Option<Operand>>,
+    /// The values on the operand stack from the bottom, one for each value regardless of its
+    /// size.
+    pub stack: Vec<Operand>,
 }
 
 impl MokaIRMethod {
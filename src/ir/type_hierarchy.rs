@@ -14,20 +14,34 @@ impl ClassHierarchy {
     where
         I: IntoIterator<Item = &'a Class>,
     {
-        let mut inheritance: HashMap<ClassRef, HashSet<ClassRef>> = HashMap::new();
-        let mut super_classes: HashMap<ClassRef, ClassRef> = HashMap::new();
+        let mut hierarchy = Self {
+            inheritance: HashMap::new(),
+            super_classes: HashMap::new(),
+        };
         for class in classes {
-            if let Some(super_class) = class.super_class.as_ref() {
-                inheritance
-                    .entry(super_class.clone())
-                    .or_default()
-                    .insert(class.as_ref());
-                super_classes.insert(class.as_ref(), super_class.clone());
+            hierarchy.add_class(class);
+        }
+        hierarchy
+    }
+
+    /// Adds a class to the hierarchy.
+    /// If a class with the same name is already added, it is replaced.
+    pub fn add_class(&mut self, class: &Class) {
+        self.insert(class.as_ref(), class.super_class.as_ref());
+    }
+
+    pub(crate) fn insert(&mut self, class_ref: ClassRef, super_class: Option<&ClassRef>) {
+        if let Some(old_super_class) = self.super_classes.remove(&class_ref) {
+            if let Some(siblings) = self.inheritance.get_mut(&old_super_class) {
+                siblings.remove(&class_ref);
             }
         }
-        Self {
-            inheritance,
-            super_classes,
+        if let Some(super_class) = super_class {
+            self.inheritance
+                .entry(super_class.clone())
+                .or_default()
+                .insert(class_ref.clone());
+            self.super_classes.insert(class_ref, super_class.clone());
         }
     }
 
@@ -68,23 +82,37 @@ impl InterfaceImplHierarchy {
     where
         I: IntoIterator<Item = &'a Class>,
     {
-        let mut implementations: HashMap<ClassRef, HashSet<ClassRef>> = HashMap::new();
-        let mut implementors: HashMap<ClassRef, HashSet<ClassRef>> = HashMap::new();
+        let mut hierarchy = Self {
+            implementations: HashMap::new(),
+            implementors: HashMap::new(),
+        };
         for class in classes {
-            for interface in &class.interfaces {
-                implementations
-                    .entry(class.as_ref())
-                    .or_default()
-                    .insert(interface.clone());
-                implementors
-                    .entry(interface.clone())
-                    .or_default()
-                    .insert(class.as_ref());
+            hierarchy.add_class(class);
+        }
+        hierarchy
+    }
+
+    /// Adds a class to the hierarchy.
+    /// If a class with the same name is already added, it is replaced.
+    pub fn add_class(&mut self, class: &Class) {
+        self.insert(&class.as_ref(), &class.interfaces);
+    }
+
+    pub(crate) fn insert(&mut self, class_ref: &ClassRef, interfaces: &[ClassRef]) {
+        for old_interface in self.implementations.remove(class_ref).unwrap_or_default() {
+            if let Some(implementors) = self.implementors.get_mut(&old_interface) {
+                implementors.remove(class_ref);
             }
         }
-        Self {
-            implementations,
-            implementors,
+        for interface in interfaces {
+            self.implementations
+                .entry(class_ref.clone())
+                .or_default()
+                .insert(interface.clone());
+            self.implementors
+                .entry(interface.clone())
+                .or_default()
+                .insert(class_ref.clone());
         }
     }
 
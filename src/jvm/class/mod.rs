@@ -64,6 +64,19 @@ impl Class {
     pub const fn is_abstract(&self) -> bool {
         self.access_flags.contains(AccessFlags::ABSTRACT)
     }
+
+    /// Checks if the class is a `final` class.
+    #[must_use]
+    pub const fn is_final(&self) -> bool {
+        self.access_flags.contains(AccessFlags::FINAL)
+    }
+
+    /// Checks if the class is a `sealed` class or interface, i.e., it lists its permitted
+    /// subclasses.
+    #[must_use]
+    pub fn is_sealed(&self) -> bool {
+        !self.permitted_subclasses.is_empty()
+    }
 }
 
 impl Annotation {
@@ -460,7 +473,7 @@ mod tests {
         }
 
         #[test]
-        fn invalid_class_version(major in 46..=MAX_MAJOR_VERSION, minor in 1..=u16::MAX) {
+        fn invalid_class_version(major in 46..=MAX_MAJOR_VERSION, minor in 1..u16::MAX) {
             assert!(Version::from_versions(major, minor).is_err());
         }
     }
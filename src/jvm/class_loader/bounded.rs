@@ -0,0 +1,337 @@
+//! A class loader with a bounded cache.
+
+use std::{
+    borrow::Borrow,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    jvm::{Class, ClassLoader},
+    utils::LruCache,
+};
+
+use super::{ClassPath, Error};
+
+/// A class loader that caches the loaded classes up to a total size, evicting the least recently
+/// used ones when the cache is full.
+///
+/// The size of a class is estimated by the size of its class file.
+/// Classes from class paths that do not provide their class files as resources (see
+/// [`ClassPath::find_resource`]) are assumed to be 4 KiB each.
+///
+/// Unlike [`CachingClassLoader`](super::CachingClassLoader), which keeps every loaded class for
+/// its whole lifetime, the classes are returned as [`Arc`]s so that they can be evicted or
+/// invalidated while still in use.
+/// This is suitable for long-running services where the classes on the class path change over
+/// time.
+#[derive(Debug)]
+pub struct BoundedCachingClassLoader<P> {
+    class_loader: ClassLoader<P>,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    /// The cached classes with the indices of the class path entries defining them, weighted by
+    /// their sizes.
+    cache: LruCache<String, (usize, Arc<Class>)>,
+    /// Incremented on every invalidation so that loads racing with it are not cached.
+    generation: u64,
+}
+
+/// The size assumed for a class whose class file cannot be read as a resource.
+const UNKNOWN_CLASS_SIZE: usize = 4096;
+
+impl<P> BoundedCachingClassLoader<P> {
+    /// Creates a class loader that caches classes of at most `capacity` bytes in total.
+    #[must_use]
+    pub fn new(class_loader: ClassLoader<P>, capacity: NonZeroUsize) -> Self {
+        Self {
+            class_loader,
+            state: Mutex::new(CacheState {
+                cache: LruCache::new(capacity),
+                generation: 0,
+            }),
+        }
+    }
+
+    /// Returns the maximum total size of the cached classes in bytes.
+    #[must_use]
+    pub fn capacity(&self) -> NonZeroUsize {
+        self.lock().cache.capacity()
+    }
+
+    /// Returns the total size of the cached classes in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.lock().cache.weight()
+    }
+
+    /// Returns the number of cached classes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().cache.len()
+    }
+
+    /// Checks whether no class is cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the underlying class loader.
+    #[must_use]
+    pub fn class_loader(&self) -> &ClassLoader<P> {
+        &self.class_loader
+    }
+
+    /// Loads a class from the cache, or loads it from the class loader and caches it if it is
+    /// not cached.
+    /// Failures are not cached.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn load_class<N>(&self, binary_name: &N) -> Result<Arc<Class>, Error>
+    where
+        P: ClassPath,
+        N: ?Sized + Borrow<str>,
+    {
+        let binary_name = binary_name.borrow();
+        let generation = {
+            let mut state = self.lock();
+            if let Some((_, class)) = state.cache.get(binary_name) {
+                return Ok(Arc::clone(class));
+            }
+            state.generation
+        };
+        // The lock is released while loading so that other classes can be loaded concurrently.
+        let (entry, class, size) = self.load_sized(binary_name)?;
+        let class = Arc::new(class);
+        let mut state = self.lock();
+        if state.generation == generation {
+            state
+                .cache
+                .put(binary_name.to_owned(), (entry, Arc::clone(&class)), size);
+        }
+        Ok(class)
+    }
+
+    /// Loads a class with the index of the class path entry defining it and its size.
+    fn load_sized(&self, binary_name: &str) -> Result<(usize, Class, usize), Error>
+    where
+        P: ClassPath,
+    {
+        let class_file_name = format!("{binary_name}.class");
+        for (idx, class_path) in self.class_loader.class_path.iter().enumerate() {
+            match class_path.find_resource(&class_file_name) {
+                Ok(bytes) => {
+                    let class = Class::from_reader(bytes.as_slice())?;
+                    return Ok((idx, class, bytes.len()));
+                }
+                Err(Error::NotFound) => {}
+                Err(err) => return Err(err),
+            }
+            match class_path.find_class(binary_name) {
+                Ok(class) => return Ok((idx, class, UNKNOWN_CLASS_SIZE)),
+                Err(Error::NotFound) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Err(Error::NotFound)
+    }
+
+    /// Removes a class from the cache, so that it is loaded again on the next request.
+    /// Returns whether the class was cached.
+    pub fn invalidate(&self, binary_name: &str) -> bool {
+        let mut state = self.lock();
+        state.generation += 1;
+        state.cache.remove(binary_name).is_some()
+    }
+
+    /// Removes the classes loaded from the class path entry at `entry` from the cache, e.g.,
+    /// after the entry is rebuilt.
+    /// The classes loaded from the later entries are also removed, because the rebuilt entry may
+    /// now define classes that shadow them.
+    pub fn invalidate_entry(&self, entry: usize) {
+        let mut state = self.lock();
+        state.generation += 1;
+        state.cache.retain(|_, (idx, _)| *idx < entry);
+    }
+
+    /// Replaces the class path entry at `entry` and invalidates it as in
+    /// [`BoundedCachingClassLoader::invalidate_entry`].
+    /// Returns the replaced entry.
+    ///
+    /// # Panics
+    /// Panics if `entry` is out of bounds.
+    pub fn replace_entry(&mut self, entry: usize, class_path: P) -> P {
+        let replaced = std::mem::replace(&mut self.class_loader.class_path[entry], class_path);
+        self.invalidate_entry(entry);
+        replaced
+    }
+
+    /// Removes all the classes from the cache.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.generation += 1;
+        state.cache.clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheState> {
+        // The cache is left consistent even if a thread panics while holding the lock.
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{
+        jvm::class_loader::class_paths::InMemoryClassPath,
+        tests::{class_path_with, empty_class_named},
+    };
+
+    use super::*;
+
+    /// A class path counting the classes found in it.
+    struct CountingClassPath {
+        inner: InMemoryClassPath,
+        loads: AtomicUsize,
+    }
+
+    impl CountingClassPath {
+        fn new(classes: &[(&str, &str)]) -> Self {
+            Self {
+                inner: class_path_with(classes),
+                loads: AtomicUsize::new(0),
+            }
+        }
+
+        fn loads(&self) -> usize {
+            self.loads.load(Ordering::Relaxed)
+        }
+    }
+
+    impl ClassPath for CountingClassPath {
+        fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+            let class = self.inner.find_class(binary_name)?;
+            self.loads.fetch_add(1, Ordering::Relaxed);
+            Ok(class)
+        }
+
+        fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+            let bytes = self.inner.find_resource(name)?;
+            self.loads.fetch_add(1, Ordering::Relaxed);
+            Ok(bytes)
+        }
+    }
+
+    /// The size of the class files in the test class paths, which have names of the same length.
+    fn class_size() -> usize {
+        empty_class_named("org/mokapot/A", "java/lang/Object").len()
+    }
+
+    /// Creates a class loader caching up to `capacity` classes of [`class_size`].
+    fn class_loader(capacity: usize) -> BoundedCachingClassLoader<CountingClassPath> {
+        let class_path = [
+            CountingClassPath::new(&[
+                ("org/mokapot/A", "java/lang/Object"),
+                ("org/mokapot/B", "java/lang/Object"),
+            ]),
+            CountingClassPath::new(&[
+                ("org/mokapot/C", "java/lang/Object"),
+                ("org/mokapot/A", "java/lang/Number"),
+            ]),
+        ];
+        BoundedCachingClassLoader::new(
+            ClassLoader::new(class_path),
+            NonZeroUsize::new(capacity * class_size()).unwrap(),
+        )
+    }
+
+    fn loads(loader: &BoundedCachingClassLoader<CountingClassPath>) -> [usize; 2] {
+        let class_path = loader.class_loader().class_path();
+        [class_path[0].loads(), class_path[1].loads()]
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let loader = class_loader(2);
+        let a = loader.load_class("org/mokapot/A").unwrap();
+        loader.load_class("org/mokapot/B").unwrap();
+        assert!(Arc::ptr_eq(
+            &a,
+            &loader.load_class("org/mokapot/A").unwrap()
+        ));
+        loader.load_class("org/mokapot/C").unwrap();
+        assert_eq!(loader.len(), 2);
+        assert_eq!(loader.size(), 2 * class_size());
+        assert_eq!(loads(&loader), [2, 1]);
+        // `B` is evicted while `A` is kept as it is used more recently.
+        loader.load_class("org/mokapot/A").unwrap();
+        assert_eq!(loads(&loader), [2, 1]);
+        loader.load_class("org/mokapot/B").unwrap();
+        assert_eq!(loads(&loader), [3, 1]);
+        // The evicted classes are still usable.
+        assert_eq!(a.binary_name, "org/mokapot/A");
+    }
+
+    #[test]
+    fn bound_by_size() {
+        let big = empty_class_named("org/mokapot/Big", "org/mokapot/AVeryLongSuperClassName");
+        let class_path = InMemoryClassPath::from_iter([("org/mokapot/Big".to_owned(), big)]);
+        let loader = BoundedCachingClassLoader::new(
+            ClassLoader::new([class_path]),
+            NonZeroUsize::new(class_size()).unwrap(),
+        );
+        // A class larger than the capacity is loaded but not cached.
+        assert!(loader.load_class("org/mokapot/Big").is_ok());
+        assert!(loader.is_empty());
+        assert_eq!(loader.size(), 0);
+    }
+
+    #[test]
+    fn invalidate_class() {
+        let loader = class_loader(10);
+        let a = loader.load_class("org/mokapot/A").unwrap();
+        assert!(loader.invalidate("org/mokapot/A"));
+        assert!(!loader.invalidate("org/mokapot/A"));
+        let reloaded = loader.load_class("org/mokapot/A").unwrap();
+        assert!(!Arc::ptr_eq(&a, &reloaded));
+        assert_eq!(loads(&loader), [2, 0]);
+    }
+
+    #[test]
+    fn invalidate_entry() {
+        let loader = class_loader(10);
+        loader.load_class("org/mokapot/A").unwrap();
+        loader.load_class("org/mokapot/C").unwrap();
+        loader.invalidate_entry(1);
+        assert_eq!(loader.len(), 1);
+        loader.load_class("org/mokapot/A").unwrap();
+        loader.load_class("org/mokapot/C").unwrap();
+        assert_eq!(loads(&loader), [1, 2]);
+        loader.invalidate_entry(0);
+        assert!(loader.is_empty());
+    }
+
+    #[test]
+    fn replace_entry() {
+        let mut loader = class_loader(10);
+        let a = loader.load_class("org/mokapot/A").unwrap();
+        assert_eq!(
+            a.super_class.as_ref().unwrap().binary_name,
+            "java/lang/Object"
+        );
+        loader.replace_entry(0, CountingClassPath::new(&[]));
+        let a = loader.load_class("org/mokapot/A").unwrap();
+        assert_eq!(
+            a.super_class.as_ref().unwrap().binary_name,
+            "java/lang/Number"
+        );
+    }
+}
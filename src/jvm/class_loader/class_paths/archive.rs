@@ -0,0 +1,248 @@
+//! Shared utilities for class paths backed by ZIP archives.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    sync::Arc,
+};
+
+use zip::{result::ZipError, ZipArchive};
+
+use crate::jvm::{
+    class_loader::{
+        manifest::{Manifest, MANIFEST_PATH},
+        Error,
+    },
+    references::ClassRef,
+    Class,
+};
+
+/// The directory containing the versioned entries of a multi-release JAR.
+const VERSIONS_DIR: &str = "META-INF/versions/";
+
+/// The first Java release supporting multi-release JARs.
+pub(super) const MIN_MULTI_RELEASE: u16 = 9;
+
+/// The pseudo release of the entries at the root of an archive.
+const BASE_RELEASE: u16 = 0;
+
+pub(super) fn zip_error(error: ZipError) -> Error {
+    match error {
+        ZipError::FileNotFound => Error::NotFound,
+        ZipError::Io(io_err) => Error::IO(io_err),
+        e => Error::Other(Box::new(e)),
+    }
+}
+
+pub(super) fn read_manifest<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<Manifest> {
+    let mut manifest_file = archive.by_name(MANIFEST_PATH).ok()?;
+    let mut content = String::new();
+    manifest_file.read_to_string(&mut content).ok()?;
+    Some(Manifest::parse(&content))
+}
+
+/// Reads the whole content of an entry.
+pub(super) fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    index: usize,
+) -> Result<Vec<u8>, Error> {
+    let mut entry = archive.by_index(index).map_err(zip_error)?;
+    let mut content = Vec::new();
+    entry.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Splits a versioned entry name (e.g., `META-INF/versions/11/org/mokapot/Foo.class`) into
+/// the release and the path relative to the root of the JAR.
+fn split_versioned_entry(entry_name: &str) -> Option<(u16, &str)> {
+    let (release, path) = entry_name.strip_prefix(VERSIONS_DIR)?.split_once('/')?;
+    Some((release.parse().ok()?, path))
+}
+
+/// The class files in a ZIP archive indexed by their binary names.
+#[derive(Debug, Clone)]
+pub(super) struct ArchiveIndex<R> {
+    archive: ZipArchive<R>,
+    prefix: String,
+    multi_release: bool,
+    /// Maps binary names to the entry indices of each release providing the class.
+    classes: HashMap<String, BTreeMap<u16, usize>>,
+}
+
+impl<R: Read + Seek + Clone> ArchiveIndex<R> {
+    /// Indexes the class files under `prefix` (e.g., `WEB-INF/classes/`).
+    /// Versioned entries are only considered at the root of a multi-release JAR.
+    pub fn new(mut archive: ZipArchive<R>, prefix: &str) -> Self {
+        let multi_release = prefix.is_empty()
+            && read_manifest(&mut archive).is_some_and(|it| it.is_multi_release());
+        let mut classes: HashMap<String, BTreeMap<u16, usize>> = HashMap::new();
+        for index in 0..archive.len() {
+            let Some(path) = archive
+                .name_for_index(index)
+                .and_then(|it| it.strip_prefix(prefix))
+            else {
+                continue;
+            };
+            let (release, path) = match split_versioned_entry(path) {
+                Some((release, path)) if multi_release && release >= MIN_MULTI_RELEASE => {
+                    (release, path)
+                }
+                _ if path.starts_with("META-INF/") => continue,
+                _ => (BASE_RELEASE, path),
+            };
+            if let Some(binary_name) = path.strip_suffix(".class") {
+                classes
+                    .entry(binary_name.to_owned())
+                    .or_default()
+                    .insert(release, index);
+            }
+        }
+        Self {
+            archive,
+            prefix: prefix.to_owned(),
+            multi_release,
+            classes,
+        }
+    }
+
+    /// Reads the manifest of the archive if present.
+    pub fn manifest(&self) -> Option<Manifest> {
+        read_manifest(&mut self.archive.clone())
+    }
+
+    /// Finds the entry of a class visible to the given release.
+    fn entry_of(&self, binary_name: &str, release: u16) -> Option<usize> {
+        let versions = self.classes.get(binary_name)?;
+        versions.range(..=release).next_back().map(|(_, idx)| *idx)
+    }
+
+    /// Finds a class visible to the given release.
+    pub fn find_class(&self, binary_name: &str, release: u16) -> Result<Class, Error> {
+        let index = self.entry_of(binary_name, release).ok_or(Error::NotFound)?;
+        let class_bytes = read_entry(&mut self.archive.clone(), index)?;
+        Class::from_reader(class_bytes.as_slice()).map_err(Into::into)
+    }
+
+    /// Reads a resource visible to the given release.
+    pub fn find_resource(&self, name: &str, release: u16) -> Result<Vec<u8>, Error> {
+        let index = if let Some(binary_name) = name.strip_suffix(".class") {
+            self.entry_of(binary_name, release)
+        } else {
+            let versioned_names = (MIN_MULTI_RELEASE..=release)
+                .rev()
+                .filter(|_| self.multi_release)
+                .map(|it| format!("{}{VERSIONS_DIR}{it}/{name}", self.prefix));
+            versioned_names
+                .chain(std::iter::once(format!("{}{name}", self.prefix)))
+                .find_map(|it| self.archive.index_for_name(&it))
+        };
+        read_entry(&mut self.archive.clone(), index.ok_or(Error::NotFound)?)
+    }
+
+    /// Lists the resources directly in a directory visible to the given release.
+    pub fn list_resources(&self, directory: &str, release: u16) -> Vec<String> {
+        let directory = format!("{}/", directory.trim_end_matches('/'));
+        let names: BTreeSet<_> = self
+            .archive
+            .file_names()
+            .filter_map(|it| it.strip_prefix(self.prefix.as_str()))
+            .filter_map(|path| match split_versioned_entry(path) {
+                Some((entry_release, path)) if self.multi_release => (MIN_MULTI_RELEASE..=release)
+                    .contains(&entry_release)
+                    .then_some(path),
+                _ => Some(path),
+            })
+            .filter(|path| {
+                path.strip_prefix(directory.as_str())
+                    .is_some_and(|name| !name.is_empty() && !name.contains('/'))
+            })
+            .collect();
+        names.into_iter().map(ToOwned::to_owned).collect()
+    }
+
+    /// Returns the classes visible to the given release.
+    pub fn class_refs(&self, release: u16) -> HashSet<ClassRef> {
+        self.classes
+            .iter()
+            .filter(|(_, versions)| versions.range(..=release).next().is_some())
+            .map(|(binary_name, _)| ClassRef::new(binary_name))
+            .collect()
+    }
+}
+
+/// A file that can be read by multiple readers concurrently.
+/// Each clone keeps its own position and reads with positioned I/O, so the readers do not
+/// interfere with each other.
+/// On platforms without positioned I/O, the reads seek the file while holding a lock instead.
+#[derive(Debug, Clone)]
+pub(super) struct SharedFile {
+    #[cfg(any(unix, windows))]
+    file: Arc<File>,
+    #[cfg(not(any(unix, windows)))]
+    file: Arc<std::sync::Mutex<File>>,
+    len: u64,
+    position: u64,
+}
+
+impl SharedFile {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        #[cfg(not(any(unix, windows)))]
+        let file = std::sync::Mutex::new(file);
+        Ok(Self {
+            file: Arc::new(file),
+            len,
+            position: 0,
+        })
+    }
+
+    #[cfg(unix)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        std::os::unix::fs::FileExt::read_at(self.file.as_ref(), buf, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(self.file.as_ref(), buf, offset)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        // A reader panicking while holding the lock leaves the file usable, as every read seeks
+        // to its own position first.
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        file.seek(SeekFrom::Start(offset))?;
+        file.read(buf)
+    }
+}
+
+impl Read for SharedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.read_at(buf, self.position)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for SharedFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = new_position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seeking to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
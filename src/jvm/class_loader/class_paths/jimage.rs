@@ -0,0 +1,713 @@
+//! Support for the `jimage` container format used by the JDK runtime image (i.e., `lib/modules`).
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::ZlibDecoder;
+
+use crate::{
+    analysis::ClassRefs,
+    jvm::{
+        class_loader::ClassPath,
+        references::{package_of, ClassRef},
+        Class, Module,
+    },
+};
+
+use super::Error;
+
+const IMAGE_MAGIC: u32 = 0xCAFE_DADA;
+const IMAGE_MAJOR_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 7 * 4;
+const HASH_MULTIPLIER: i32 = 0x0100_0193;
+
+const COMPRESSED_RESOURCE_MAGIC: u32 = 0xCAFE_FAFA;
+const COMPRESSED_HEADER_SIZE: usize = 29;
+
+const ATTRIBUTE_END: u8 = 0;
+const ATTRIBUTE_MODULE: u8 = 1;
+const ATTRIBUTE_PARENT: u8 = 2;
+const ATTRIBUTE_BASE: u8 = 3;
+const ATTRIBUTE_EXTENSION: u8 = 4;
+const ATTRIBUTE_OFFSET: u8 = 5;
+const ATTRIBUTE_COMPRESSED: u8 = 6;
+const ATTRIBUTE_UNCOMPRESSED: u8 = 7;
+const ATTRIBUTE_COUNT: usize = 8;
+
+/// The pseudo modules holding the directory structure of the image.
+const DIRECTORY_MODULES: [&str; 2] = ["modules", "packages"];
+
+/// A class path that searches for classes in a JDK runtime image (i.e., `$JAVA_HOME/lib/modules`).
+///
+/// The classes in the image are keyed by module and package.
+/// A class is located by mapping its package to the module containing the package.
+#[derive(Debug)]
+pub struct JrtImageClassPath {
+    image_file: PathBuf,
+    index: ImageIndex,
+    modules: BTreeSet<String>,
+    package_modules: HashMap<String, String>,
+}
+
+impl JrtImageClassPath {
+    /// Opens a runtime image and reads its index.
+    ///
+    /// # Errors
+    /// - [`Error::IO`] if the image cannot be read.
+    /// - [`Error::Other`] if the file is not a valid `jimage`.
+    pub fn new(image_file: impl Into<PathBuf>) -> Result<Self, Error> {
+        let image_file = image_file.into();
+        let index = ImageIndex::read_from(&mut BufReader::new(File::open(&image_file)?))?;
+        let mut modules = BTreeSet::new();
+        let mut package_modules = HashMap::new();
+        for loc in index.locations() {
+            if loc.extension == "class" && loc.base != Module::INFO_CLASS_NAME {
+                package_modules.insert(loc.parent, loc.module.clone());
+            }
+            modules.insert(loc.module);
+        }
+        Ok(Self {
+            image_file,
+            index,
+            modules,
+            package_modules,
+        })
+    }
+
+    /// Opens the runtime image of the JDK installed at `java_home`.
+    ///
+    /// # Errors
+    /// See [`JrtImageClassPath::new`].
+    pub fn from_java_home(java_home: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::new(java_home.as_ref().join("lib").join("modules"))
+    }
+
+    /// Returns the path to the image file.
+    #[must_use]
+    pub fn image_file(&self) -> &Path {
+        &self.image_file
+    }
+
+    /// Returns the names of the modules in the image, including the ones without any package
+    /// (e.g., aggregator modules such as `java.se`).
+    #[must_use]
+    pub fn modules(&self) -> BTreeSet<&str> {
+        self.modules.iter().map(String::as_str).collect()
+    }
+
+    /// Returns the name of the module containing the given package (e.g., `java/lang`).
+    #[must_use]
+    pub fn module_of(&self, package: &str) -> Option<&str> {
+        self.package_modules.get(package).map(String::as_str)
+    }
+
+    /// Returns the packages in the given module.
+    #[must_use]
+    pub fn packages_of(&self, module: &str) -> BTreeSet<&str> {
+        self.package_modules
+            .iter()
+            .filter(|(_, it)| *it == module)
+            .map(|(pkg, _)| pkg.as_str())
+            .collect()
+    }
+
+    /// Finds the `module-info` class of the given module.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn find_module_info(&self, module: &str) -> Result<Class, Error> {
+        let bytes = self.read_resource(&format!("/{module}/{}.class", Module::INFO_CLASS_NAME))?;
+        Class::from_reader(bytes.as_slice()).map_err(Into::into)
+    }
+
+    /// Reads the content of the resource with the given full name (e.g., `/java.base/java/lang/Object.class`).
+    fn read_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let location = self.index.find_location(name)?.ok_or(Error::NotFound)?;
+        let mut image = File::open(&self.image_file)?;
+        image.seek(SeekFrom::Start(
+            self.index.index_size + location.content_offset,
+        ))?;
+        let stored_size = if location.compressed_size == 0 {
+            location.uncompressed_size
+        } else {
+            location.compressed_size
+        };
+        let mut content = Vec::new();
+        image.take(stored_size).read_to_end(&mut content)?;
+        if u64::try_from(content.len()).ok() != Some(stored_size) {
+            return Err(malformed("The resource is truncated"));
+        }
+        if location.compressed_size == 0 {
+            Ok(content)
+        } else {
+            self.index.decompress(content)
+        }
+    }
+}
+
+impl ClassPath for JrtImageClassPath {
+    fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+        let module = self
+            .module_of(package_of(binary_name))
+            .ok_or(Error::NotFound)?;
+        let bytes = self.read_resource(&format!("/{module}/{binary_name}.class"))?;
+        Class::from_reader(bytes.as_slice()).map_err(Into::into)
+    }
+
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let module = self.module_of(package_of(name)).ok_or(Error::NotFound)?;
+        self.read_resource(&format!("/{module}/{name}"))
+    }
+}
+
+impl ClassRefs for JrtImageClassPath {
+    fn class_refs(&self) -> HashSet<ClassRef> {
+        self.index
+            .locations()
+            .filter(|loc| loc.extension == "class" && loc.base != Module::INFO_CLASS_NAME)
+            .map(|loc| {
+                if loc.parent.is_empty() {
+                    ClassRef::new(loc.base)
+                } else {
+                    ClassRef::new(format!("{}/{}", loc.parent, loc.base))
+                }
+            })
+            .collect()
+    }
+}
+
+fn malformed(message: &'static str) -> Error {
+    Error::Other(message.into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn u32_at(self, bytes: &[u8], offset: usize) -> Option<u32> {
+        let buf = bytes.get(offset..offset + 4)?.try_into().ok()?;
+        Some(match self {
+            Self::Little => u32::from_le_bytes(buf),
+            Self::Big => u32::from_be_bytes(buf),
+        })
+    }
+
+    fn u64_at(self, bytes: &[u8], offset: usize) -> Option<u64> {
+        let buf = bytes.get(offset..offset + 8)?.try_into().ok()?;
+        Some(match self {
+            Self::Little => u64::from_le_bytes(buf),
+            Self::Big => u64::from_be_bytes(buf),
+        })
+    }
+}
+
+/// The index of a `jimage`, which consists of the header, the redirect table, the offsets table,
+/// the location attributes and the string table.
+#[derive(Debug)]
+struct ImageIndex {
+    byte_order: ByteOrder,
+    redirect: Vec<i32>,
+    offsets: Vec<u32>,
+    locations: Vec<u8>,
+    strings: Vec<u8>,
+    index_size: u64,
+}
+
+/// The decoded attributes of a resource in the image.
+#[derive(Debug, PartialEq, Eq)]
+struct Location {
+    module: String,
+    parent: String,
+    base: String,
+    extension: String,
+    content_offset: u64,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+impl Location {
+    fn full_name(&self) -> String {
+        let mut name = String::new();
+        if !self.module.is_empty() {
+            name.push('/');
+            name.push_str(&self.module);
+            name.push('/');
+        }
+        if !self.parent.is_empty() {
+            name.push_str(&self.parent);
+            name.push('/');
+        }
+        name.push_str(&self.base);
+        if !self.extension.is_empty() {
+            name.push('.');
+            name.push_str(&self.extension);
+        }
+        name
+    }
+}
+
+impl ImageIndex {
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let byte_order = match header[..4].try_into().map(u32::from_le_bytes) {
+            Ok(IMAGE_MAGIC) => ByteOrder::Little,
+            _ if header[..4] == IMAGE_MAGIC.to_be_bytes() => ByteOrder::Big,
+            _ => return Err(malformed("This is not a jimage file")),
+        };
+        let header_field = |idx: usize| {
+            byte_order
+                .u32_at(&header, idx * 4)
+                .ok_or_else(|| malformed("The jimage header is truncated"))
+        };
+        let version = header_field(1)?;
+        if version >> 16 != IMAGE_MAJOR_VERSION {
+            return Err(malformed("Unsupported jimage version"));
+        }
+        let table_length = to_usize(header_field(4)?)?;
+        let locations_size = to_usize(header_field(5)?)?;
+        let strings_size = to_usize(header_field(6)?)?;
+
+        let redirect = read_chunk(reader, table_length * 4)?
+            .chunks_exact(4)
+            .map(|it| byte_order.u32_at(it, 0).map(u32::cast_signed))
+            .collect::<Option<_>>()
+            .ok_or_else(|| malformed("The redirect table is truncated"))?;
+        let offsets = read_chunk(reader, table_length * 4)?
+            .chunks_exact(4)
+            .map(|it| byte_order.u32_at(it, 0))
+            .collect::<Option<_>>()
+            .ok_or_else(|| malformed("The offsets table is truncated"))?;
+        let locations = read_chunk(reader, locations_size)?;
+        let strings = read_chunk(reader, strings_size)?;
+        let index_size = HEADER_SIZE + table_length * 8 + locations_size + strings_size;
+        Ok(Self {
+            byte_order,
+            redirect,
+            offsets,
+            locations,
+            strings,
+            index_size: u64::try_from(index_size)
+                .map_err(|_| malformed("The index is too large"))?,
+        })
+    }
+
+    /// Iterates over all the resources in the image except the directory entries.
+    fn locations(&self) -> impl Iterator<Item = Location> + '_ {
+        self.offsets
+            .iter()
+            .filter_map(|&offset| self.location_at(offset).ok())
+            .filter(|loc| !DIRECTORY_MODULES.contains(&loc.module.as_str()))
+    }
+
+    fn find_location(&self, name: &str) -> Result<Option<Location>, Error> {
+        let table_length = self.redirect.len();
+        if table_length == 0 {
+            return Ok(None);
+        }
+        let bucket = to_usize(hash_code(name, HASH_MULTIPLIER))? % table_length;
+        let index = match self.redirect[bucket] {
+            0 => return Ok(None),
+            redirect if redirect < 0 => to_usize((-1 - redirect).cast_unsigned())?,
+            seed => to_usize(hash_code(name, seed))? % table_length,
+        };
+        let offset = *self
+            .offsets
+            .get(index)
+            .ok_or_else(|| malformed("The redirect table points to an invalid location"))?;
+        let location = self.location_at(offset)?;
+        Ok(Some(location).filter(|it| it.full_name() == name))
+    }
+
+    fn location_at(&self, offset: u32) -> Result<Location, Error> {
+        let mut attributes = [0u64; ATTRIBUTE_COUNT];
+        let mut bytes = self
+            .locations
+            .get(to_usize(offset)?..)
+            .ok_or_else(|| malformed("The location offset is out of bounds"))?
+            .iter();
+        loop {
+            let &head = bytes
+                .next()
+                .ok_or_else(|| malformed("The location attributes are not terminated"))?;
+            let kind = head >> 3;
+            if kind == ATTRIBUTE_END {
+                break;
+            }
+            let length = usize::from(head & 0x7) + 1;
+            let mut value = 0u64;
+            for _ in 0..length {
+                let &byte = bytes
+                    .next()
+                    .ok_or_else(|| malformed("The location attributes are truncated"))?;
+                value = (value << 8) | u64::from(byte);
+            }
+            *attributes
+                .get_mut(usize::from(kind))
+                .ok_or_else(|| malformed("Unknown location attribute"))? = value;
+        }
+        let string_at = |kind: u8| {
+            u32::try_from(attributes[usize::from(kind)])
+                .map_err(|_| malformed("The string offset is out of bounds"))
+                .and_then(|offset| self.string_at(offset))
+        };
+        Ok(Location {
+            module: string_at(ATTRIBUTE_MODULE)?,
+            parent: string_at(ATTRIBUTE_PARENT)?,
+            base: string_at(ATTRIBUTE_BASE)?,
+            extension: string_at(ATTRIBUTE_EXTENSION)?,
+            content_offset: attributes[usize::from(ATTRIBUTE_OFFSET)],
+            compressed_size: attributes[usize::from(ATTRIBUTE_COMPRESSED)],
+            uncompressed_size: attributes[usize::from(ATTRIBUTE_UNCOMPRESSED)],
+        })
+    }
+
+    fn string_at(&self, offset: u32) -> Result<String, Error> {
+        let bytes = self
+            .strings
+            .get(to_usize(offset)?..)
+            .ok_or_else(|| malformed("The string offset is out of bounds"))?;
+        let end = bytes
+            .iter()
+            .position(|&it| it == 0)
+            .ok_or_else(|| malformed("The string is not terminated"))?;
+        cesu8::from_java_cesu8(&bytes[..end])
+            .map(Into::into)
+            .map_err(|_| malformed("The string is not valid modified UTF-8"))
+    }
+
+    /// Decompresses a resource, which may be compressed by multiple decompressors in a row.
+    fn decompress(&self, mut content: Vec<u8>) -> Result<Vec<u8>, Error> {
+        while self.byte_order.u32_at(&content, 0) == Some(COMPRESSED_RESOURCE_MAGIC) {
+            let truncated = || malformed("The compressed resource header is truncated");
+            let uncompressed_size = self.byte_order.u64_at(&content, 12).ok_or_else(truncated)?;
+            let decompressor_name = self
+                .byte_order
+                .u32_at(&content, 20)
+                .ok_or_else(truncated)
+                .and_then(|offset| self.string_at(offset))?;
+            let payload = content
+                .get(COMPRESSED_HEADER_SIZE..)
+                .ok_or_else(truncated)?;
+            content = match decompressor_name.as_str() {
+                "zip" => {
+                    let mut decompressed = Vec::new();
+                    ZlibDecoder::new(payload)
+                        .take(uncompressed_size)
+                        .read_to_end(&mut decompressed)?;
+                    decompressed
+                }
+                _ => return Err(malformed("Unsupported jimage resource decompressor")),
+            };
+        }
+        Ok(content)
+    }
+}
+
+/// Computes the hash code of a resource name in the same way as `jdk.internal.jimage.ImageStringsReader`.
+fn hash_code(name: &str, seed: i32) -> u32 {
+    let hash = cesu8::to_java_cesu8(name).iter().fold(seed, |hash, &byte| {
+        hash.wrapping_mul(HASH_MULTIPLIER) ^ i32::from(byte)
+    });
+    hash.cast_unsigned() & 0x7FFF_FFFF
+}
+
+fn to_usize(value: u32) -> Result<usize, Error> {
+    usize::try_from(value).map_err(|_| malformed("The value does not fit in usize"))
+}
+
+fn read_chunk<R: Read>(reader: &mut R, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, io::Write};
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use crate::{jvm::class_loader::class_paths::ClassPathEntry, tests::empty_class_named};
+
+    use super::*;
+
+    /// Builds a little-endian `jimage` in the same layout as `jlink` does.
+    #[derive(Default)]
+    struct ImageBuilder {
+        resources: BTreeMap<String, (Vec<u8>, bool)>,
+    }
+
+    impl ImageBuilder {
+        fn resource(mut self, name: &str, content: Vec<u8>) -> Self {
+            self.resources.insert(name.to_owned(), (content, false));
+            self
+        }
+
+        fn compressed_resource(mut self, name: &str, content: Vec<u8>) -> Self {
+            self.resources.insert(name.to_owned(), (content, true));
+            self
+        }
+
+        fn build(self) -> Vec<u8> {
+            let mut strings = vec![0u8];
+            let mut string_offsets = HashMap::from([(String::new(), 0u32)]);
+            let mut intern = |s: &str| {
+                *string_offsets.entry(s.to_owned()).or_insert_with(|| {
+                    let offset = u32::try_from(strings.len()).unwrap();
+                    strings.extend_from_slice(s.as_bytes());
+                    strings.push(0);
+                    offset
+                })
+            };
+            let zip_name = intern("zip");
+
+            let names: Vec<_> = self.resources.keys().cloned().collect();
+            let mut locations = Vec::new();
+            let mut location_offsets = Vec::new();
+            let mut content = Vec::new();
+            for (name, (data, compressed)) in &self.resources {
+                let (module, path) = name[1..].split_once('/').unwrap();
+                let (parent, file) = path.rsplit_once('/').unwrap_or(("", path));
+                let (base, extension) = file.rsplit_once('.').unwrap_or((file, ""));
+                let stored = if *compressed {
+                    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                    encoder.write_all(data).unwrap();
+                    let payload = encoder.finish().unwrap();
+                    let mut stored = COMPRESSED_RESOURCE_MAGIC.to_le_bytes().to_vec();
+                    stored.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+                    stored.extend_from_slice(&(data.len() as u64).to_le_bytes());
+                    stored.extend_from_slice(&zip_name.to_le_bytes());
+                    stored.extend_from_slice(&0u32.to_le_bytes());
+                    stored.push(1);
+                    stored.extend_from_slice(&payload);
+                    stored
+                } else {
+                    data.clone()
+                };
+                let attributes = [
+                    (ATTRIBUTE_MODULE, u64::from(intern(module))),
+                    (ATTRIBUTE_PARENT, u64::from(intern(parent))),
+                    (ATTRIBUTE_BASE, u64::from(intern(base))),
+                    (ATTRIBUTE_EXTENSION, u64::from(intern(extension))),
+                    (ATTRIBUTE_OFFSET, content.len() as u64),
+                    (
+                        ATTRIBUTE_COMPRESSED,
+                        if *compressed { stored.len() as u64 } else { 0 },
+                    ),
+                    (ATTRIBUTE_UNCOMPRESSED, data.len() as u64),
+                ];
+                location_offsets.push(u32::try_from(locations.len()).unwrap());
+                for (kind, value) in attributes {
+                    let bytes = value.to_be_bytes();
+                    let skip = bytes.iter().take_while(|&&it| it == 0).count().min(7);
+                    let length = u8::try_from(8 - skip).unwrap();
+                    locations.push((kind << 3) | (length - 1));
+                    locations.extend_from_slice(&bytes[skip..]);
+                }
+                locations.push(ATTRIBUTE_END);
+                content.extend_from_slice(&stored);
+            }
+
+            let (redirect, slots) = perfect_hash(&names);
+            let mut image = Vec::new();
+            let header = [
+                IMAGE_MAGIC,
+                IMAGE_MAJOR_VERSION << 16,
+                0,
+                u32::try_from(names.len()).unwrap(),
+                u32::try_from(names.len()).unwrap(),
+                u32::try_from(locations.len()).unwrap(),
+                u32::try_from(strings.len()).unwrap(),
+            ];
+            for it in header {
+                image.extend_from_slice(&it.to_le_bytes());
+            }
+            for it in redirect {
+                image.extend_from_slice(&it.to_le_bytes());
+            }
+            for name_idx in slots {
+                image.extend_from_slice(&location_offsets[name_idx].to_le_bytes());
+            }
+            image.extend_from_slice(&locations);
+            image.extend_from_slice(&strings);
+            image.extend_from_slice(&content);
+            image
+        }
+    }
+
+    /// Computes the redirect table and the slot assignment like `jdk.tools.jlink.internal.PerfectHashBuilder`.
+    fn perfect_hash(names: &[String]) -> (Vec<i32>, Vec<usize>) {
+        let len = names.len();
+        let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); len];
+        for (idx, name) in names.iter().enumerate() {
+            buckets[hash_code(name, HASH_MULTIPLIER) as usize % len].push(idx);
+        }
+        let mut order: Vec<_> = (0..len).collect();
+        order.sort_by_key(|&it| std::cmp::Reverse(buckets[it].len()));
+        let mut redirect = vec![0i32; len];
+        let mut slots: Vec<Option<usize>> = vec![None; len];
+        for bucket in order {
+            match buckets[bucket].as_slice() {
+                [] => {}
+                [single] => {
+                    let free = slots.iter().position(Option::is_none).unwrap();
+                    slots[free] = Some(*single);
+                    redirect[bucket] = -1 - i32::try_from(free).unwrap();
+                }
+                entries => {
+                    let seed = (1..i32::MAX)
+                        .find(|&seed| {
+                            let positions: HashSet<_> = entries
+                                .iter()
+                                .map(|&it| hash_code(&names[it], seed) as usize % len)
+                                .collect();
+                            positions.len() == entries.len()
+                                && positions.iter().all(|&it| slots[it].is_none())
+                        })
+                        .unwrap();
+                    for &entry in entries {
+                        slots[hash_code(&names[entry], seed) as usize % len] = Some(entry);
+                    }
+                    redirect[bucket] = seed;
+                }
+            }
+        }
+        (redirect, slots.into_iter().map(Option::unwrap).collect())
+    }
+
+    fn test_image() -> (tempfile::TempDir, JrtImageClassPath) {
+        let image = ImageBuilder::default()
+            .resource(
+                "/java.base/java/lang/Object.class",
+                empty_class_named("java/lang/Object", "java/lang/Object"),
+            )
+            .compressed_resource(
+                "/java.base/java/lang/String.class",
+                empty_class_named("java/lang/String", "java/lang/Object"),
+            )
+            .resource(
+                "/java.base/module-info.class",
+                empty_class_named("module-info", "java/lang/Object"),
+            )
+            .resource(
+                "/java.se/module-info.class",
+                empty_class_named("module-info", "java/lang/Object"),
+            )
+            .resource(
+                "/java.sql/java/sql/Driver.class",
+                empty_class_named("java/sql/Driver", "java/lang/Object"),
+            )
+            .resource("/java.base/java/lang/uniName.dat", vec![0x42; 16])
+            .resource("/packages/java.lang/java.base", vec![0; 8])
+            .resource("/modules/java.base/java/lang", Vec::new())
+            .build();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("modules");
+        std::fs::write(&path, image).unwrap();
+        let class_path = JrtImageClassPath::new(path).unwrap();
+        (temp_dir, class_path)
+    }
+
+    #[test]
+    fn hash_code_matches_jdk() {
+        // Computed with `jdk.internal.jimage.ImageStringsReader.hashCode`.
+        assert_eq!(hash_code("", HASH_MULTIPLIER), 0x0100_0193);
+        assert_eq!(
+            hash_code("a", HASH_MULTIPLIER),
+            (0x0100_0193_i32.wrapping_mul(HASH_MULTIPLIER) ^ 0x61).cast_unsigned() & 0x7FFF_FFFF
+        );
+    }
+
+    #[test]
+    fn modules_and_packages() {
+        let (_temp_dir, class_path) = test_image();
+        assert_eq!(
+            class_path.modules(),
+            BTreeSet::from(["java.base", "java.se", "java.sql"])
+        );
+        assert_eq!(class_path.module_of("java/lang"), Some("java.base"));
+        assert_eq!(class_path.module_of("java/sql"), Some("java.sql"));
+        assert_eq!(
+            class_path.packages_of("java.base"),
+            BTreeSet::from(["java/lang"])
+        );
+        assert!(class_path.packages_of("java.se").is_empty());
+        assert_eq!(class_path.module_of(""), None);
+    }
+
+    #[test]
+    fn find_class() {
+        let (_temp_dir, class_path) = test_image();
+        let class = class_path.find_class("java/sql/Driver").unwrap();
+        assert_eq!(class.binary_name, "java/sql/Driver");
+    }
+
+    #[test]
+    fn find_compressed_class() {
+        let (_temp_dir, class_path) = test_image();
+        let class = class_path.find_class("java/lang/String").unwrap();
+        assert_eq!(class.binary_name, "java/lang/String");
+    }
+
+    #[test]
+    fn class_not_found() {
+        let (_temp_dir, class_path) = test_image();
+        assert!(matches!(
+            class_path.find_class("java/lang/Missing"),
+            Err(Error::NotFound)
+        ));
+        assert!(matches!(
+            class_path.find_class("org/mokapot/Missing"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn find_module_info() {
+        let (_temp_dir, class_path) = test_image();
+        let module_info = class_path.find_module_info("java.base").unwrap();
+        assert_eq!(module_info.binary_name, Module::INFO_CLASS_NAME);
+        assert!(class_path.find_module_info("java.se").is_ok());
+        assert!(matches!(
+            class_path.find_module_info("java.sql"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn class_refs() {
+        let (_temp_dir, class_path) = test_image();
+        assert_eq!(
+            class_path.class_refs(),
+            HashSet::from([
+                ClassRef::new("java/lang/Object"),
+                ClassRef::new("java/lang/String"),
+                ClassRef::new("java/sql/Driver"),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_spec_with_image() {
+        let (temp_dir, _) = test_image();
+        let entries = ClassPathEntry::parse_spec(temp_dir.path().join("modules"));
+        let [ClassPathEntry::JrtImage(class_path)] = entries.as_slice() else {
+            panic!("The image should be the only entry: {entries:?}");
+        };
+        assert!(class_path.find_class("java/sql/Driver").is_ok());
+    }
+
+    #[test]
+    fn not_a_jimage() {
+        let path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+        assert!(matches!(JrtImageClassPath::new(path), Err(Error::Other(_))));
+    }
+}
@@ -0,0 +1,454 @@
+//! Implementations of [`ClassPath`].
+
+use std::{collections::HashSet, fs::File, io::BufReader};
+
+#[cfg(feature = "jar")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "jar")]
+use zip::ZipArchive;
+
+#[cfg(feature = "jar")]
+use super::manifest::Manifest;
+#[cfg(feature = "jar")]
+use crate::jvm::class::MAX_MAJOR_VERSION;
+#[cfg(feature = "jar")]
+use archive::{zip_error, ArchiveIndex, SharedFile};
+
+use crate::{
+    analysis::ClassRefs,
+    jvm::{references::ClassRef, Class},
+};
+
+use super::{ClassPath, Error};
+
+#[cfg(feature = "jar")]
+mod archive;
+#[cfg(feature = "jar")]
+mod nested;
+#[cfg(feature = "jar")]
+pub use nested::{ArchiveLayout, NestedJarClassPath};
+
+mod spec;
+pub use spec::{ClassPathEntry, InMemoryClassPath};
+
+#[cfg(feature = "jimage")]
+mod jimage;
+#[cfg(feature = "jimage")]
+pub use jimage::JrtImageClassPath;
+
+/// A class path that searches for classes in a directory.
+#[derive(Debug)]
+pub struct DirectoryClassPath {
+    directory: std::path::PathBuf,
+}
+
+impl ClassPath for DirectoryClassPath {
+    fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+        let class_file_path = self.directory.join(binary_name).with_extension("class");
+        if class_file_path.exists() {
+            let class_file = File::open(class_file_path)?;
+            let buf_read = BufReader::new(class_file);
+            let class = Class::from_reader(buf_read)?;
+            Ok(class)
+        } else {
+            Err(Error::NotFound)
+        }
+    }
+
+    /// Names that are absolute or contain `.` or `..` components are not found, so that a
+    /// resource cannot be read from outside the directory.
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        if !is_confined(name) {
+            return Err(Error::NotFound);
+        }
+        let resource_path = self.directory.join(name);
+        if resource_path.is_file() {
+            std::fs::read(resource_path).map_err(Into::into)
+        } else {
+            Err(Error::NotFound)
+        }
+    }
+
+    /// Directories outside the directory are treated as empty.
+    fn list_resources(&self, directory: &str) -> Result<Vec<String>, Error> {
+        let directory = directory.trim_end_matches('/');
+        if !is_confined(directory) {
+            return Ok(Vec::new());
+        }
+        let entries = match std::fs::read_dir(self.directory.join(directory)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            if let Some(file_name) = entry.file_name().to_str() {
+                names.push(if directory.is_empty() {
+                    file_name.to_owned()
+                } else {
+                    format!("{directory}/{file_name}")
+                });
+            }
+        }
+        names.sort_unstable();
+        Ok(names)
+    }
+}
+
+/// Checks that a relative path has neither a root nor `.` or `..` components, so that it cannot
+/// point outside the directory it is joined to.
+fn is_confined(path: &str) -> bool {
+    std::path::Path::new(path)
+        .components()
+        .all(|it| matches!(it, std::path::Component::Normal(_)))
+}
+
+impl DirectoryClassPath {
+    /// Create a new directory class path.
+    pub fn new(directory: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Returns the directory of this class path.
+    #[must_use]
+    pub fn directory(&self) -> &std::path::Path {
+        &self.directory
+    }
+}
+
+impl ClassRefs for DirectoryClassPath {
+    fn class_refs(&self) -> HashSet<ClassRef> {
+        walkdir::WalkDir::new(&self.directory)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|it| it.path().extension().is_some_and(|it| it == "class"))
+            .map(|it| {
+                let binary_name = it
+                    .path()
+                    .strip_prefix(&self.directory)
+                    .expect("The directory should start with `self.directory`")
+                    .with_extension("")
+                    .to_str()
+                    .expect("The path name is not valid UTF-8")
+                    .to_owned();
+                ClassRef { binary_name }
+            })
+            .collect()
+    }
+}
+
+/// A class path that searches for classes in a JAR file.
+///
+/// If the JAR is a multi-release JAR (i.e., its manifest contains `Multi-Release: true`),
+/// the classes under `META-INF/versions/N/` take precedence over the ones at the root of the JAR
+/// for all `N` not greater than the target release.
+///
+/// The JAR is opened on the first lookup and kept open afterwards, together with an index of the
+/// class files in it. Lookups from multiple threads read the JAR concurrently.
+#[derive(Debug)]
+#[cfg(feature = "jar")]
+pub struct JarClassPath {
+    jar_file: std::path::PathBuf,
+    release: u16,
+    index: OnceLock<ArchiveIndex<SharedFile>>,
+}
+
+#[cfg(feature = "jar")]
+impl JarClassPath {
+    /// The first Java release supporting multi-release JARs.
+    pub const MIN_MULTI_RELEASE: u16 = archive::MIN_MULTI_RELEASE;
+
+    /// The latest Java release supported, which is used as the default target release.
+    pub const LATEST_RELEASE: u16 = MAX_MAJOR_VERSION - 44;
+
+    /// Create a new JAR class path targeting [`JarClassPath::LATEST_RELEASE`].
+    pub fn new(jar_file: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            jar_file: jar_file.into(),
+            release: Self::LATEST_RELEASE,
+            index: OnceLock::new(),
+        }
+    }
+
+    /// Sets the target Java release (e.g., `11`) used to select the versioned entries in
+    /// multi-release JARs.
+    #[must_use]
+    pub fn with_release(self, release: u16) -> Self {
+        Self { release, ..self }
+    }
+
+    /// Returns the target Java release.
+    #[must_use]
+    pub fn release(&self) -> u16 {
+        self.release
+    }
+
+    /// Returns the path of the JAR file.
+    #[must_use]
+    pub fn jar_file(&self) -> &std::path::Path {
+        &self.jar_file
+    }
+
+    /// Reads the manifest of the JAR, or returns `None` if the JAR does not have a manifest.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn manifest(&self) -> Result<Option<Manifest>, Error> {
+        Ok(self.index()?.manifest())
+    }
+
+    /// Opens the JAR and indexes its entries if it is not opened yet.
+    /// A failed attempt is not cached, so that the JAR is opened again on the next lookup.
+    fn index(&self) -> Result<&ArchiveIndex<SharedFile>, Error> {
+        if let Some(index) = self.index.get() {
+            return Ok(index);
+        }
+        let jar_file = SharedFile::open(&self.jar_file)?;
+        let jar_archive = ZipArchive::new(jar_file).map_err(zip_error)?;
+        Ok(self
+            .index
+            .get_or_init(|| ArchiveIndex::new(jar_archive, "")))
+    }
+}
+
+#[cfg(feature = "jar")]
+impl ClassPath for JarClassPath {
+    fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+        self.index()?.find_class(binary_name, self.release)
+    }
+
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        self.index()?.find_resource(name, self.release)
+    }
+
+    fn list_resources(&self, directory: &str) -> Result<Vec<String>, Error> {
+        Ok(self.index()?.list_resources(directory, self.release))
+    }
+}
+
+#[cfg(feature = "jar")]
+impl ClassRefs for JarClassPath {
+    fn class_refs(&self) -> HashSet<ClassRef> {
+        self.index()
+            .map(|it| it.class_refs(self.release))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(all(test, feature = "jar"))]
+mod tests {
+    use crate::{
+        jvm::class_loader::manifest::MANIFEST_PATH,
+        tests::{empty_class_named, jar_with_entries},
+    };
+
+    use super::*;
+
+    const MANIFEST: &[u8] = b"Manifest-Version: 1.0\r\nMulti-Release: true\r\n\r\n";
+
+    fn multi_release_jar() -> (tempfile::TempDir, std::path::PathBuf) {
+        let object = empty_class_named("org/mokapot/Foo", "java/lang/Object");
+        let number = empty_class_named("org/mokapot/Foo", "java/lang/Number");
+        let string = empty_class_named("org/mokapot/Foo", "java/lang/String");
+        let bar = empty_class_named("org/mokapot/Bar", "java/lang/Object");
+        let jar = jar_with_entries(&[
+            (MANIFEST_PATH, MANIFEST),
+            ("org/mokapot/Foo.class", &object),
+            ("META-INF/versions/11/org/mokapot/Foo.class", &number),
+            ("META-INF/versions/17/org/mokapot/Foo.class", &string),
+            ("META-INF/versions/17/org/mokapot/Bar.class", &bar),
+        ]);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let jar_path = temp_dir.path().join("multi-release.jar");
+        std::fs::write(&jar_path, jar).unwrap();
+        (temp_dir, jar_path)
+    }
+
+    fn super_class_of(class_path: &JarClassPath, binary_name: &str) -> Option<String> {
+        let class = class_path.find_class(binary_name).ok()?;
+        class.super_class.map(|it| it.binary_name)
+    }
+
+    #[test]
+    fn multi_release_selects_latest_visible_version() {
+        let (_temp_dir, jar_path) = multi_release_jar();
+        let latest = JarClassPath::new(&jar_path);
+        assert_eq!(
+            super_class_of(&latest, "org/mokapot/Foo").as_deref(),
+            Some("java/lang/String")
+        );
+        let java_11 = JarClassPath::new(&jar_path).with_release(11);
+        assert_eq!(
+            super_class_of(&java_11, "org/mokapot/Foo").as_deref(),
+            Some("java/lang/Number")
+        );
+        let java_8 = JarClassPath::new(&jar_path).with_release(8);
+        assert_eq!(
+            super_class_of(&java_8, "org/mokapot/Foo").as_deref(),
+            Some("java/lang/Object")
+        );
+    }
+
+    #[test]
+    fn multi_release_hides_newer_classes() {
+        let (_temp_dir, jar_path) = multi_release_jar();
+        let java_11 = JarClassPath::new(&jar_path).with_release(11);
+        assert!(matches!(
+            java_11.find_class("org/mokapot/Bar"),
+            Err(Error::NotFound)
+        ));
+        assert!(JarClassPath::new(&jar_path)
+            .find_class("org/mokapot/Bar")
+            .is_ok());
+    }
+
+    #[test]
+    fn multi_release_class_refs() {
+        let (_temp_dir, jar_path) = multi_release_jar();
+        assert_eq!(
+            JarClassPath::new(&jar_path).class_refs(),
+            HashSet::from([
+                ClassRef::new("org/mokapot/Foo"),
+                ClassRef::new("org/mokapot/Bar"),
+            ])
+        );
+        assert_eq!(
+            JarClassPath::new(&jar_path).with_release(11).class_refs(),
+            HashSet::from([ClassRef::new("org/mokapot/Foo")])
+        );
+    }
+
+    #[test]
+    fn versioned_entries_ignored_without_multi_release() {
+        let versioned = empty_class_named("org/mokapot/Foo", "java/lang/Number");
+        let jar = jar_with_entries(&[
+            (MANIFEST_PATH, b"Manifest-Version: 1.0\r\n\r\n"),
+            ("META-INF/versions/11/org/mokapot/Foo.class", &versioned),
+        ]);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let jar_path = temp_dir.path().join("plain.jar");
+        std::fs::write(&jar_path, jar).unwrap();
+        let class_path = JarClassPath::new(&jar_path);
+        assert!(matches!(
+            class_path.find_class("org/mokapot/Foo"),
+            Err(Error::NotFound)
+        ));
+        assert!(class_path.class_refs().is_empty());
+    }
+
+    #[test]
+    fn find_resource() {
+        let (_temp_dir, jar_path) = multi_release_jar();
+        let class_path = JarClassPath::new(&jar_path).with_release(11);
+        assert_eq!(class_path.find_resource(MANIFEST_PATH).unwrap(), MANIFEST);
+        assert_eq!(
+            class_path.find_resource("org/mokapot/Foo.class").unwrap(),
+            empty_class_named("org/mokapot/Foo", "java/lang/Number")
+        );
+        assert!(matches!(
+            class_path.find_resource("org/mokapot/Bar.class"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn directory_resource_outside_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let classes = temp_dir.path().join("classes");
+        std::fs::create_dir_all(classes.join("META-INF")).unwrap();
+        std::fs::write(classes.join("META-INF/MANIFEST.MF"), MANIFEST).unwrap();
+        std::fs::write(temp_dir.path().join("secret.txt"), b"secret").unwrap();
+        let class_path = DirectoryClassPath::new(&classes);
+        assert_eq!(class_path.find_resource(MANIFEST_PATH).unwrap(), MANIFEST);
+        let outside = temp_dir.path().join("secret.txt");
+        for name in [
+            "../secret.txt",
+            "META-INF/../../secret.txt",
+            "./META-INF/MANIFEST.MF",
+            outside.to_str().unwrap(),
+        ] {
+            assert!(
+                matches!(class_path.find_resource(name), Err(Error::NotFound)),
+                "{name} should not be found"
+            );
+        }
+    }
+
+    #[test]
+    fn list_resources() {
+        let jar = jar_with_entries(&[
+            (MANIFEST_PATH, MANIFEST),
+            ("META-INF/services/org.mokapot.A", b""),
+            ("META-INF/services/nested/org.mokapot.B", b""),
+            ("META-INF/versions/11/META-INF/services/org.mokapot.C", b""),
+            ("META-INF/versions/17/META-INF/services/org.mokapot.D", b""),
+        ]);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let jar_path = temp_dir.path().join("services.jar");
+        std::fs::write(&jar_path, jar).unwrap();
+        let class_path = JarClassPath::new(&jar_path).with_release(11);
+        assert_eq!(
+            class_path.list_resources("META-INF/services").unwrap(),
+            [
+                "META-INF/services/org.mokapot.A",
+                "META-INF/services/org.mokapot.C",
+            ]
+        );
+
+        let classes = temp_dir.path().join("classes");
+        std::fs::create_dir_all(classes.join("META-INF/services/nested")).unwrap();
+        std::fs::write(classes.join("META-INF/services/org.mokapot.A"), b"").unwrap();
+        let class_path = DirectoryClassPath::new(&classes);
+        assert_eq!(
+            class_path.list_resources("META-INF/services/").unwrap(),
+            ["META-INF/services/org.mokapot.A"]
+        );
+        assert!(class_path.list_resources("../classes").unwrap().is_empty());
+        assert!(class_path.list_resources("missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn concurrent_lookups() {
+        let (_temp_dir, jar_path) = multi_release_jar();
+        let class_path = JarClassPath::new(&jar_path);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..32 {
+                        let class = class_path.find_class("org/mokapot/Foo").unwrap();
+                        assert_eq!(class.binary_name, "org/mokapot/Foo");
+                        assert!(class_path.find_class("org/mokapot/Bar").is_ok());
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn jar_kept_open() {
+        let (_temp_dir, jar_path) = multi_release_jar();
+        let class_path = JarClassPath::new(&jar_path);
+        assert_eq!(class_path.class_refs().len(), 2);
+        std::fs::remove_file(&jar_path).unwrap();
+        assert!(class_path.find_class("org/mokapot/Foo").is_ok());
+    }
+
+    #[test]
+    fn missing_jar() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let class_path = JarClassPath::new(temp_dir.path().join("missing.jar"));
+        assert!(matches!(
+            class_path.find_class("org/mokapot/Foo"),
+            Err(Error::IO(_))
+        ));
+        assert!(class_path.class_refs().is_empty());
+    }
+}
@@ -0,0 +1,463 @@
+//! Class paths inside fat archives (e.g., Spring Boot executable JARs, WARs, and EARs).
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    analysis::ClassRefs,
+    jvm::{class_loader::ClassPath, references::ClassRef, Class},
+};
+
+use super::{
+    archive::{read_entry, zip_error, ArchiveIndex},
+    Error, JarClassPath,
+};
+
+/// An archive held in memory.
+type InMemoryArchive = ZipArchive<Cursor<Arc<[u8]>>>;
+
+/// The layout of a fat archive, which determines where the classes and libraries are located.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ArchiveLayout {
+    /// A Spring Boot executable JAR, with classes in `BOOT-INF/classes/` and libraries in
+    /// `BOOT-INF/lib/`.
+    /// The libraries are ordered by `BOOT-INF/classpath.idx` if present.
+    SpringBoot,
+    /// A web application archive, with classes in `WEB-INF/classes/` and libraries in
+    /// `WEB-INF/lib/` and `WEB-INF/lib-provided/`.
+    War,
+    /// An enterprise application archive, with libraries in `lib/`, and EJB modules (`*.jar`)
+    /// and web modules (`*.war`) at the root.
+    Ear,
+}
+
+impl ArchiveLayout {
+    /// Detects the layout of an archive from its entries.
+    fn detect<R: Read + Seek>(archive: &ZipArchive<R>) -> Option<Self> {
+        if archive.file_names().any(|it| it.starts_with("BOOT-INF/")) {
+            Some(Self::SpringBoot)
+        } else if archive.file_names().any(|it| it.starts_with("WEB-INF/")) {
+            Some(Self::War)
+        } else if archive.file_names().any(|it| {
+            it == "META-INF/application.xml"
+                || (!it.contains('/') && (has_extension(it, "war") || has_extension(it, "jar")))
+        }) {
+            Some(Self::Ear)
+        } else {
+            None
+        }
+    }
+}
+
+/// A class path entry inside a fat archive.
+#[derive(Debug)]
+struct NestedEntry {
+    /// The path of the entry, where `!/` separates the nesting levels
+    /// (e.g., `web.war!/WEB-INF/lib/foo.jar`).
+    name: String,
+    index: ArchiveIndex<Cursor<Arc<[u8]>>>,
+}
+
+/// A class path that searches for classes in the class directories and the nested JARs of a fat
+/// archive.
+/// The nested JARs are read into memory, so the archive does not need to be extracted.
+#[derive(Debug)]
+pub struct NestedJarClassPath {
+    archive_file: PathBuf,
+    layout: ArchiveLayout,
+    entries: Vec<NestedEntry>,
+    release: u16,
+}
+
+impl NestedJarClassPath {
+    /// Opens a fat archive with the given layout.
+    ///
+    /// # Errors
+    /// - [`Error::IO`] if the archive cannot be read.
+    /// - [`Error::Other`] if the archive or any of the nested JARs is not a valid ZIP archive.
+    pub fn new(archive_file: impl Into<PathBuf>, layout: ArchiveLayout) -> Result<Self, Error> {
+        let archive_file = archive_file.into();
+        let archive = read_archive(&archive_file)?;
+        Self::with_archive(archive_file, archive, layout)
+    }
+
+    /// Opens a fat archive and detects its layout.
+    ///
+    /// # Errors
+    /// - [`Error::Other`] if the layout cannot be detected.
+    /// - See [`NestedJarClassPath::new`] for other errors.
+    pub fn detect(archive_file: impl Into<PathBuf>) -> Result<Self, Error> {
+        let archive_file = archive_file.into();
+        let archive = read_archive(&archive_file)?;
+        let layout = ArchiveLayout::detect(&archive)
+            .ok_or_else(|| Error::Other("Unrecognized archive layout".into()))?;
+        Self::with_archive(archive_file, archive, layout)
+    }
+
+    fn with_archive(
+        archive_file: PathBuf,
+        mut archive: InMemoryArchive,
+        layout: ArchiveLayout,
+    ) -> Result<Self, Error> {
+        let mut entries = Vec::new();
+        match layout {
+            ArchiveLayout::SpringBoot => {
+                let libs = spring_boot_libs(&mut archive)?;
+                collect_entries(&mut entries, "", &archive, "BOOT-INF/classes/", &libs)?;
+            }
+            ArchiveLayout::War => {
+                let libs = war_libs(&archive);
+                collect_entries(&mut entries, "", &archive, "WEB-INF/classes/", &libs)?;
+            }
+            ArchiveLayout::Ear => {
+                let mut libs = jars_in(&archive, "lib/");
+                libs.extend(jars_in(&archive, ""));
+                collect_entries(&mut entries, "", &archive, "", &libs)?;
+                for war in entries_in(&archive, "", "war") {
+                    let war_archive = nested_archive(&mut archive, &war)?;
+                    let libs = war_libs(&war_archive);
+                    let prefix = format!("{war}!/");
+                    collect_entries(
+                        &mut entries,
+                        &prefix,
+                        &war_archive,
+                        "WEB-INF/classes/",
+                        &libs,
+                    )?;
+                }
+            }
+        }
+        Ok(Self {
+            archive_file,
+            layout,
+            entries,
+            release: JarClassPath::LATEST_RELEASE,
+        })
+    }
+
+    /// Sets the target Java release used to select the versioned entries in nested
+    /// multi-release JARs. See [`JarClassPath::with_release`].
+    #[must_use]
+    pub fn with_release(self, release: u16) -> Self {
+        Self { release, ..self }
+    }
+
+    /// Returns the path of the archive.
+    #[must_use]
+    pub fn archive_file(&self) -> &Path {
+        &self.archive_file
+    }
+
+    /// Returns the layout of the archive.
+    #[must_use]
+    pub fn layout(&self) -> ArchiveLayout {
+        self.layout
+    }
+
+    /// Returns the paths of the class path entries inside the archive in the order they are
+    /// searched, where `!/` separates the nesting levels.
+    /// Class directories end with `/`.
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|it| it.name.as_str())
+    }
+}
+
+impl ClassPath for NestedJarClassPath {
+    fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+        for entry in &self.entries {
+            match entry.index.find_class(binary_name, self.release) {
+                Ok(class) => return Ok(class),
+                Err(Error::NotFound) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Err(Error::NotFound)
+    }
+
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        for entry in &self.entries {
+            match entry.index.find_resource(name, self.release) {
+                Ok(bytes) => return Ok(bytes),
+                Err(Error::NotFound) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Err(Error::NotFound)
+    }
+
+    fn list_resources(&self, directory: &str) -> Result<Vec<String>, Error> {
+        let names: BTreeSet<_> = self
+            .entries
+            .iter()
+            .flat_map(|it| it.index.list_resources(directory, self.release))
+            .collect();
+        Ok(names.into_iter().collect())
+    }
+}
+
+impl ClassRefs for NestedJarClassPath {
+    fn class_refs(&self) -> HashSet<ClassRef> {
+        self.entries
+            .iter()
+            .flat_map(|it| it.index.class_refs(self.release))
+            .collect()
+    }
+}
+
+fn read_archive(archive_file: &Path) -> Result<InMemoryArchive, Error> {
+    let mut content = Vec::new();
+    BufReader::new(File::open(archive_file)?).read_to_end(&mut content)?;
+    ZipArchive::new(Cursor::new(Arc::from(content))).map_err(zip_error)
+}
+
+fn nested_archive(archive: &mut InMemoryArchive, name: &str) -> Result<InMemoryArchive, Error> {
+    let index = archive.index_for_name(name).ok_or(Error::NotFound)?;
+    let content = read_entry(archive, index)?;
+    ZipArchive::new(Cursor::new(Arc::from(content))).map_err(zip_error)
+}
+
+/// Adds the class directory (if present) followed by the given nested JARs.
+fn collect_entries(
+    entries: &mut Vec<NestedEntry>,
+    name_prefix: &str,
+    archive: &InMemoryArchive,
+    class_dir: &str,
+    libs: &[String],
+) -> Result<(), Error> {
+    if !class_dir.is_empty() && archive.file_names().any(|it| it.starts_with(class_dir)) {
+        entries.push(NestedEntry {
+            name: format!("{name_prefix}{class_dir}"),
+            index: ArchiveIndex::new(archive.clone(), class_dir),
+        });
+    }
+    let mut archive = archive.clone();
+    for lib in libs {
+        let lib_archive = nested_archive(&mut archive, lib)?;
+        entries.push(NestedEntry {
+            name: format!("{name_prefix}{lib}"),
+            index: ArchiveIndex::new(lib_archive, ""),
+        });
+    }
+    Ok(())
+}
+
+/// Lists the entries directly in `dir` with the given extension, sorted by name.
+fn entries_in<R: Read + Seek>(archive: &ZipArchive<R>, dir: &str, extension: &str) -> Vec<String> {
+    let mut entries: Vec<_> = archive
+        .file_names()
+        .filter(|it| {
+            it.strip_prefix(dir)
+                .is_some_and(|name| !name.contains('/') && has_extension(name, extension))
+        })
+        .map(ToOwned::to_owned)
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn jars_in<R: Read + Seek>(archive: &ZipArchive<R>, dir: &str) -> Vec<String> {
+    entries_in(archive, dir, "jar")
+}
+
+fn has_extension(name: &str, extension: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .is_some_and(|it| it.eq_ignore_ascii_case(extension))
+}
+
+fn war_libs<R: Read + Seek>(archive: &ZipArchive<R>) -> Vec<String> {
+    let mut libs = jars_in(archive, "WEB-INF/lib/");
+    libs.extend(jars_in(archive, "WEB-INF/lib-provided/"));
+    libs
+}
+
+/// Lists the libraries of a Spring Boot executable JAR, in the order of `BOOT-INF/classpath.idx`
+/// followed by the remaining ones sorted by name.
+fn spring_boot_libs(archive: &mut InMemoryArchive) -> Result<Vec<String>, Error> {
+    let mut libs = jars_in(archive, "BOOT-INF/lib/");
+    let Some(index) = archive.index_for_name("BOOT-INF/classpath.idx") else {
+        return Ok(libs);
+    };
+    let classpath_idx = String::from_utf8_lossy(&read_entry(archive, index)?).into_owned();
+    let mut ordered: Vec<String> = classpath_idx
+        .lines()
+        .filter_map(|line| {
+            let lib = line.trim().strip_prefix("- ")?.trim_matches('"');
+            Some(lib.to_owned())
+        })
+        .filter(|lib| libs.contains(lib))
+        .collect();
+    libs.retain(|it| !ordered.contains(it));
+    ordered.extend(libs);
+    Ok(ordered)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{empty_class_named, jar_with_entries};
+
+    use super::*;
+
+    fn write_archive(entries: &[(&str, &[u8])]) -> (tempfile::TempDir, PathBuf) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("app.archive");
+        std::fs::write(&path, jar_with_entries(entries)).unwrap();
+        (temp_dir, path)
+    }
+
+    fn super_class_of(class_path: &NestedJarClassPath, binary_name: &str) -> Option<String> {
+        let class = class_path.find_class(binary_name).ok()?;
+        class.super_class.map(|it| it.binary_name)
+    }
+
+    #[test]
+    fn spring_boot() {
+        let app = empty_class_named("org/mokapot/App", "java/lang/Object");
+        let lib_a = jar_with_entries(&[(
+            "org/mokapot/Lib.class",
+            &empty_class_named("org/mokapot/Lib", "java/lang/Number"),
+        )]);
+        let lib_b = jar_with_entries(&[
+            (
+                "org/mokapot/Lib.class",
+                &empty_class_named("org/mokapot/Lib", "java/lang/String"),
+            ),
+            (
+                "org/mokapot/Other.class",
+                &empty_class_named("org/mokapot/Other", "java/lang/Object"),
+            ),
+        ]);
+        let (_temp_dir, path) = write_archive(&[
+            ("BOOT-INF/classes/org/mokapot/App.class", &app),
+            ("BOOT-INF/classes/application.properties", b""),
+            ("BOOT-INF/lib/a.jar", &lib_a),
+            ("BOOT-INF/lib/b.jar", &lib_b),
+            (
+                "BOOT-INF/classpath.idx",
+                b"- \"BOOT-INF/lib/b.jar\"\n- \"BOOT-INF/lib/a.jar\"\n",
+            ),
+        ]);
+        let class_path = NestedJarClassPath::detect(&path).unwrap();
+        assert_eq!(class_path.layout(), ArchiveLayout::SpringBoot);
+        assert_eq!(
+            class_path.entries().collect::<Vec<_>>(),
+            [
+                "BOOT-INF/classes/",
+                "BOOT-INF/lib/b.jar",
+                "BOOT-INF/lib/a.jar"
+            ]
+        );
+        assert_eq!(
+            super_class_of(&class_path, "org/mokapot/Lib").as_deref(),
+            Some("java/lang/String")
+        );
+        assert_eq!(
+            class_path.class_refs(),
+            HashSet::from([
+                ClassRef::new("org/mokapot/App"),
+                ClassRef::new("org/mokapot/Lib"),
+                ClassRef::new("org/mokapot/Other"),
+            ])
+        );
+        assert!(matches!(
+            class_path.find_class("org/mokapot/Missing"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn war() {
+        let servlet = empty_class_named("org/mokapot/Servlet", "java/lang/Object");
+        let lib = jar_with_entries(&[
+            (
+                "org/mokapot/Servlet.class",
+                &empty_class_named("org/mokapot/Servlet", "java/lang/Number"),
+            ),
+            (
+                "org/mokapot/Lib.class",
+                &empty_class_named("org/mokapot/Lib", "java/lang/Object"),
+            ),
+        ]);
+        let (_temp_dir, path) = write_archive(&[
+            ("WEB-INF/classes/org/mokapot/Servlet.class", &servlet),
+            ("WEB-INF/lib/lib.jar", &lib),
+            ("index.html", b""),
+        ]);
+        let class_path = NestedJarClassPath::new(&path, ArchiveLayout::War).unwrap();
+        assert_eq!(
+            class_path.entries().collect::<Vec<_>>(),
+            ["WEB-INF/classes/", "WEB-INF/lib/lib.jar"]
+        );
+        assert_eq!(
+            super_class_of(&class_path, "org/mokapot/Servlet").as_deref(),
+            Some("java/lang/Object")
+        );
+        assert!(class_path.find_class("org/mokapot/Lib").is_ok());
+    }
+
+    #[test]
+    fn ear() {
+        let ejb = jar_with_entries(&[(
+            "org/mokapot/Bean.class",
+            &empty_class_named("org/mokapot/Bean", "java/lang/Object"),
+        )]);
+        let shared = jar_with_entries(&[(
+            "org/mokapot/Shared.class",
+            &empty_class_named("org/mokapot/Shared", "java/lang/Object"),
+        )]);
+        let war_lib = jar_with_entries(&[(
+            "org/mokapot/WebLib.class",
+            &empty_class_named("org/mokapot/WebLib", "java/lang/Object"),
+        )]);
+        let war = jar_with_entries(&[
+            (
+                "WEB-INF/classes/org/mokapot/Servlet.class",
+                &empty_class_named("org/mokapot/Servlet", "java/lang/Object"),
+            ),
+            ("WEB-INF/lib/web-lib.jar", &war_lib),
+        ]);
+        let (_temp_dir, path) = write_archive(&[
+            ("META-INF/application.xml", b"<application/>"),
+            ("lib/shared.jar", &shared),
+            ("ejb.jar", &ejb),
+            ("web.war", &war),
+        ]);
+        let class_path = NestedJarClassPath::detect(&path).unwrap();
+        assert_eq!(class_path.layout(), ArchiveLayout::Ear);
+        assert_eq!(
+            class_path.entries().collect::<Vec<_>>(),
+            [
+                "lib/shared.jar",
+                "ejb.jar",
+                "web.war!/WEB-INF/classes/",
+                "web.war!/WEB-INF/lib/web-lib.jar"
+            ]
+        );
+        assert_eq!(
+            class_path.class_refs(),
+            HashSet::from([
+                ClassRef::new("org/mokapot/Bean"),
+                ClassRef::new("org/mokapot/Shared"),
+                ClassRef::new("org/mokapot/Servlet"),
+                ClassRef::new("org/mokapot/WebLib"),
+            ])
+        );
+    }
+
+    #[test]
+    fn unrecognized_layout() {
+        let (_temp_dir, path) = write_archive(&[("org/mokapot/readme.txt", b"")]);
+        assert!(matches!(
+            NestedJarClassPath::detect(&path),
+            Err(Error::Other(_))
+        ));
+    }
+}
@@ -0,0 +1,487 @@
+//! Parsing of class path specifications (e.g., the value of `-classpath`).
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    analysis::ClassRefs,
+    jvm::{class_loader::ClassPath, references::ClassRef, Class},
+};
+
+#[cfg(feature = "jar")]
+use super::JarClassPath;
+#[cfg(feature = "jimage")]
+use super::JrtImageClassPath;
+use super::{DirectoryClassPath, Error};
+
+/// A class path that searches for classes in memory.
+/// The classes are stored as class file bytes keyed by their binary names.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryClassPath {
+    classes: HashMap<String, Vec<u8>>,
+}
+
+impl InMemoryClassPath {
+    /// Create a new in-memory class path with the given class file bytes keyed by binary names.
+    #[must_use]
+    pub fn new(classes: HashMap<String, Vec<u8>>) -> Self {
+        Self { classes }
+    }
+
+    /// Adds a class to the class path, and returns the bytes of the class previously added with
+    /// the same binary name if any.
+    pub fn insert(&mut self, binary_name: impl Into<String>, bytes: Vec<u8>) -> Option<Vec<u8>> {
+        self.classes.insert(binary_name.into(), bytes)
+    }
+
+    /// Removes a class from the class path, and returns its bytes if it was present.
+    pub fn remove(&mut self, binary_name: &str) -> Option<Vec<u8>> {
+        self.classes.remove(binary_name)
+    }
+}
+
+impl FromIterator<(String, Vec<u8>)> for InMemoryClassPath {
+    fn from_iter<T: IntoIterator<Item = (String, Vec<u8>)>>(iter: T) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl ClassPath for InMemoryClassPath {
+    fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+        let bytes = self.classes.get(binary_name).ok_or(Error::NotFound)?;
+        Class::from_reader(bytes.as_slice()).map_err(Into::into)
+    }
+
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        name.strip_suffix(".class")
+            .and_then(|binary_name| self.classes.get(binary_name))
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+}
+
+impl ClassRefs for InMemoryClassPath {
+    fn class_refs(&self) -> HashSet<ClassRef> {
+        self.classes.keys().map(ClassRef::new).collect()
+    }
+}
+
+/// An entry in a class path, which can be any of the class paths provided by this crate.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ClassPathEntry {
+    /// A directory.
+    Directory(DirectoryClassPath),
+    /// A JAR file.
+    #[cfg(feature = "jar")]
+    Jar(JarClassPath),
+    /// A JDK runtime image.
+    #[cfg(feature = "jimage")]
+    JrtImage(JrtImageClassPath),
+    /// Classes in memory.
+    InMemory(InMemoryClassPath),
+}
+
+impl ClassPathEntry {
+    /// Parses a class path specification (e.g., `a.jar:lib/*:build/classes`) into class path
+    /// entries in the order they are searched.
+    ///
+    /// The specification is interpreted in the same way as the `-classpath` option of `java`:
+    /// - The elements are separated by the platform-specific separator (i.e., `:` on Unix and `;`
+    ///   on Windows).
+    ///   An empty element denotes the current directory.
+    /// - An element ending with `*` is expanded to the JAR files in the directory, sorted by name.
+    /// - The JARs listed in the `Class-Path` attribute of the manifest of a JAR are resolved
+    ///   relative to the JAR and searched right after it, transitively.
+    /// - A file is a JAR or a JDK runtime image (i.e., `lib/modules`) depending on its content
+    ///   regardless of its extension.
+    ///   Other files are ignored.
+    /// - Elements that do not exist are ignored, and each path is included at most once.
+    ///
+    /// JARs are ignored if the `jar` feature is disabled, and runtime images are ignored if the
+    /// `jimage` feature is disabled.
+    #[must_use]
+    pub fn parse_spec(spec: impl AsRef<OsStr>) -> Vec<Self> {
+        let mut entries = Vec::new();
+        let mut visited = HashSet::new();
+        for element in std::env::split_paths(spec.as_ref()) {
+            let element = if element.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                element
+            };
+            if element.file_name().is_some_and(|it| it == "*") {
+                let dir = element
+                    .parent()
+                    .filter(|it| !it.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                for jar in jars_in(dir) {
+                    add_path(&mut entries, &mut visited, jar);
+                }
+            } else {
+                add_path(&mut entries, &mut visited, element);
+            }
+        }
+        entries
+    }
+}
+
+/// Lists the JAR files in a directory sorted by name.
+fn jars_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut jars: Vec<_> = read_dir
+        .filter_map(Result::ok)
+        .map(|it| it.path())
+        .filter(|it| {
+            it.is_file()
+                && it
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("jar"))
+        })
+        .collect();
+    jars.sort();
+    jars
+}
+
+fn add_path(entries: &mut Vec<ClassPathEntry>, visited: &mut HashSet<PathBuf>, path: PathBuf) {
+    let Ok(canonical_path) = path.canonicalize() else {
+        return;
+    };
+    if !visited.insert(canonical_path) {
+        return;
+    }
+    if path.is_dir() {
+        entries.push(ClassPathEntry::Directory(DirectoryClassPath::new(path)));
+        return;
+    }
+    match FileKind::of(&path) {
+        #[cfg(feature = "jar")]
+        Some(FileKind::Zip) => {
+            let jar = JarClassPath::new(&path);
+            let manifest_class_path = jar
+                .manifest()
+                .ok()
+                .flatten()
+                .and_then(|it| it.main_attribute("Class-Path").map(ToOwned::to_owned));
+            entries.push(ClassPathEntry::Jar(jar));
+            let base_dir = path.parent().unwrap_or(Path::new("."));
+            for url in manifest_class_path
+                .iter()
+                .flat_map(|it| it.split_whitespace())
+            {
+                if let Some(relative_path) = decode_relative_url(url) {
+                    add_path(entries, visited, base_dir.join(relative_path));
+                }
+            }
+        }
+        #[cfg(feature = "jimage")]
+        Some(FileKind::RuntimeImage) => {
+            if let Ok(image) = JrtImageClassPath::new(path) {
+                entries.push(ClassPathEntry::JrtImage(image));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The formats of the files that can be class path entries.
+enum FileKind {
+    /// A ZIP archive, e.g., a JAR.
+    Zip,
+    /// A JDK runtime image.
+    RuntimeImage,
+}
+
+impl FileKind {
+    /// Tells the format of a file by its magic number.
+    fn of(path: &Path) -> Option<Self> {
+        let mut magic = [0; 4];
+        std::fs::File::open(path)
+            .and_then(|mut it| it.read_exact(&mut magic))
+            .ok()?;
+        match magic {
+            // The magic numbers of an archive with entries and of an empty one.
+            [b'P', b'K', 3, 4] | [b'P', b'K', 5, 6] => Some(Self::Zip),
+            // The byte order of a runtime image is that of the platform building it.
+            [0xDA, 0xDA, 0xFE, 0xCA] | [0xCA, 0xFE, 0xDA, 0xDA] => Some(Self::RuntimeImage),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes a relative URL in the `Class-Path` attribute of a manifest into a path.
+/// Absolute URLs other than `file:` ones are not supported.
+#[cfg(feature = "jar")]
+fn decode_relative_url(url: &str) -> Option<PathBuf> {
+    let url = url.strip_prefix("file:").unwrap_or(url);
+    if url.contains("://") {
+        return None;
+    }
+    let bytes = url.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(idx + 1..idx + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            idx += 3;
+        } else {
+            decoded.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+impl ClassPathEntry {
+    /// Returns the path of the file or directory backing this entry, or `None` for classes in
+    /// memory.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Directory(it) => Some(it.directory()),
+            #[cfg(feature = "jar")]
+            Self::Jar(it) => Some(it.jar_file()),
+            #[cfg(feature = "jimage")]
+            Self::JrtImage(it) => Some(it.image_file()),
+            Self::InMemory(_) => None,
+        }
+    }
+}
+
+impl ClassPath for ClassPathEntry {
+    fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+        match self {
+            Self::Directory(it) => it.find_class(binary_name),
+            #[cfg(feature = "jar")]
+            Self::Jar(it) => it.find_class(binary_name),
+            #[cfg(feature = "jimage")]
+            Self::JrtImage(it) => it.find_class(binary_name),
+            Self::InMemory(it) => it.find_class(binary_name),
+        }
+    }
+
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Directory(it) => it.find_resource(name),
+            #[cfg(feature = "jar")]
+            Self::Jar(it) => it.find_resource(name),
+            #[cfg(feature = "jimage")]
+            Self::JrtImage(it) => it.find_resource(name),
+            Self::InMemory(it) => it.find_resource(name),
+        }
+    }
+
+    fn list_resources(&self, directory: &str) -> Result<Vec<String>, Error> {
+        match self {
+            Self::Directory(it) => it.list_resources(directory),
+            #[cfg(feature = "jar")]
+            Self::Jar(it) => it.list_resources(directory),
+            #[cfg(feature = "jimage")]
+            Self::JrtImage(it) => it.list_resources(directory),
+            Self::InMemory(it) => it.list_resources(directory),
+        }
+    }
+}
+
+impl ClassRefs for ClassPathEntry {
+    fn class_refs(&self) -> HashSet<ClassRef> {
+        match self {
+            Self::Directory(it) => it.class_refs(),
+            #[cfg(feature = "jar")]
+            Self::Jar(it) => it.class_refs(),
+            #[cfg(feature = "jimage")]
+            Self::JrtImage(it) => it.class_refs(),
+            Self::InMemory(it) => it.class_refs(),
+        }
+    }
+}
+
+impl From<DirectoryClassPath> for ClassPathEntry {
+    fn from(value: DirectoryClassPath) -> Self {
+        Self::Directory(value)
+    }
+}
+
+#[cfg(feature = "jar")]
+impl From<JarClassPath> for ClassPathEntry {
+    fn from(value: JarClassPath) -> Self {
+        Self::Jar(value)
+    }
+}
+
+#[cfg(feature = "jimage")]
+impl From<JrtImageClassPath> for ClassPathEntry {
+    fn from(value: JrtImageClassPath) -> Self {
+        Self::JrtImage(value)
+    }
+}
+
+impl From<InMemoryClassPath> for ClassPathEntry {
+    fn from(value: InMemoryClassPath) -> Self {
+        Self::InMemory(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::empty_class_named;
+
+    use super::*;
+
+    #[test]
+    fn in_memory_class_path() {
+        let mut class_path: InMemoryClassPath = [(
+            "org/mokapot/Foo".to_owned(),
+            empty_class_named("org/mokapot/Foo", "java/lang/Object"),
+        )]
+        .into_iter()
+        .collect();
+        let class = class_path.find_class("org/mokapot/Foo").unwrap();
+        assert_eq!(class.binary_name, "org/mokapot/Foo");
+        assert!(matches!(
+            class_path.find_class("org/mokapot/Bar"),
+            Err(Error::NotFound)
+        ));
+
+        class_path.insert(
+            "org/mokapot/Bar",
+            empty_class_named("org/mokapot/Bar", "java/lang/Object"),
+        );
+        assert!(class_path.remove("org/mokapot/Foo").is_some());
+        assert_eq!(
+            class_path.class_refs(),
+            HashSet::from([ClassRef::new("org/mokapot/Bar")])
+        );
+    }
+
+    #[test]
+    fn in_memory_malformed_class() {
+        let class_path = InMemoryClassPath::new(HashMap::from([(
+            "org/mokapot/Foo".to_owned(),
+            vec![0xCA, 0xFE],
+        )]));
+        assert!(matches!(
+            class_path.find_class("org/mokapot/Foo"),
+            Err(Error::Malformed(_))
+        ));
+    }
+
+    #[cfg(feature = "jar")]
+    mod spec {
+        use crate::{
+            jvm::class_loader::manifest::MANIFEST_PATH,
+            tests::{empty_class_named, jar_with_entries},
+        };
+
+        use super::super::*;
+
+        fn entry_paths(entries: &[ClassPathEntry], base: &Path) -> Vec<String> {
+            entries
+                .iter()
+                .map(|it| {
+                    let path = match it {
+                        ClassPathEntry::Directory(dir) => dir.directory(),
+                        ClassPathEntry::Jar(jar) => jar.jar_file(),
+                        _ => unreachable!(),
+                    };
+                    path.strip_prefix(base)
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .replace('\\', "/")
+                })
+                .collect()
+        }
+
+        fn write_jar(path: &Path, class_path_attribute: Option<&str>, class: &str) {
+            let manifest = class_path_attribute.map_or_else(
+                || "Manifest-Version: 1.0\r\n\r\n".to_owned(),
+                |it| format!("Manifest-Version: 1.0\r\nClass-Path: {it}\r\n\r\n"),
+            );
+            let class_bytes = empty_class_named(class, "java/lang/Object");
+            let jar = jar_with_entries(&[
+                (MANIFEST_PATH, manifest.as_bytes()),
+                (&format!("{class}.class"), &class_bytes),
+            ]);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, jar).unwrap();
+        }
+
+        fn join_spec(elements: &[PathBuf]) -> std::ffi::OsString {
+            std::env::join_paths(elements).unwrap()
+        }
+
+        #[test]
+        fn parse_spec() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let base = temp_dir.path().canonicalize().unwrap();
+            std::fs::create_dir_all(base.join("classes")).unwrap();
+            write_jar(&base.join("app.jar"), None, "org/mokapot/App");
+            write_jar(&base.join("lib/b.jar"), None, "org/mokapot/B");
+            write_jar(&base.join("lib/a.jar"), None, "org/mokapot/A");
+            std::fs::write(base.join("lib/readme.txt"), "").unwrap();
+            std::fs::write(base.join("not-a.jar"), "PK").unwrap();
+            std::fs::write(base.join("notes.txt"), "Not a JAR").unwrap();
+
+            let spec = join_spec(&[
+                base.join("app.jar"),
+                base.join("lib/*"),
+                base.join("classes"),
+                base.join("missing.jar"),
+                base.join("lib/a.jar"),
+                base.join("not-a.jar"),
+                base.join("notes.txt"),
+            ]);
+            let entries = ClassPathEntry::parse_spec(spec);
+            assert_eq!(
+                entry_paths(&entries, &base),
+                ["app.jar", "lib/a.jar", "lib/b.jar", "classes"]
+            );
+        }
+
+        #[test]
+        fn manifest_class_path() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let base = temp_dir.path().canonicalize().unwrap();
+            write_jar(
+                &base.join("app.jar"),
+                Some("lib/dep%20one.jar lib/classes/ app.jar"),
+                "org/mokapot/App",
+            );
+            write_jar(
+                &base.join("lib/dep one.jar"),
+                Some("transitive.jar"),
+                "org/mokapot/Dep",
+            );
+            write_jar(
+                &base.join("lib/transitive.jar"),
+                Some("../app.jar"),
+                "org/mokapot/Transitive",
+            );
+            std::fs::create_dir_all(base.join("lib/classes")).unwrap();
+            write_jar(&base.join("other.jar"), None, "org/mokapot/Other");
+
+            let spec = join_spec(&[base.join("app.jar"), base.join("other.jar")]);
+            let entries = ClassPathEntry::parse_spec(spec);
+            assert_eq!(
+                entry_paths(&entries, &base),
+                [
+                    "app.jar",
+                    "lib/dep one.jar",
+                    "lib/transitive.jar",
+                    "lib/classes",
+                    "other.jar"
+                ]
+            );
+            let class_refs: HashSet<_> = entries.iter().flat_map(ClassRefs::class_refs).collect();
+            assert!(class_refs.contains(&ClassRef::new("org/mokapot/Transitive")));
+        }
+    }
+}
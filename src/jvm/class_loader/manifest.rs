@@ -0,0 +1,117 @@
+//! Parsing of JAR manifests (i.e., `META-INF/MANIFEST.MF`).
+
+use std::collections::HashMap;
+
+/// The path of the manifest in a JAR file.
+pub const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+/// A JAR manifest.
+/// See the [JAR File Specification](https://docs.oracle.com/en/java/javase/23/docs/specs/jar/jar.html#jar-manifest) for details.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    main_attributes: HashMap<String, String>,
+    entries: HashMap<String, HashMap<String, String>>,
+}
+
+impl Manifest {
+    /// Parses a manifest from its textual content.
+    /// Malformed lines are ignored.
+    #[must_use]
+    pub fn parse(content: &str) -> Self {
+        let mut manifest = Self::default();
+        let mut lines: Vec<String> = Vec::new();
+        for line in content.lines() {
+            match (line.strip_prefix(' '), lines.last_mut()) {
+                (Some(continuation), Some(last)) => last.push_str(continuation),
+                _ => lines.push(line.to_owned()),
+            }
+        }
+        let mut sections = lines.split(String::is_empty).filter(|it| !it.is_empty());
+        if let Some(main_section) = sections.next() {
+            manifest.main_attributes = parse_section(main_section);
+        }
+        for section in sections {
+            let mut attributes = parse_section(section);
+            if let Some(name) = attributes.remove("name") {
+                manifest.entries.insert(name, attributes);
+            }
+        }
+        manifest
+    }
+
+    /// Gets the value of a main attribute.
+    /// Attribute names are case-insensitive.
+    #[must_use]
+    pub fn main_attribute(&self, name: &str) -> Option<&str> {
+        self.main_attributes
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Gets the value of an attribute in the section of the given entry.
+    /// Attribute names are case-insensitive.
+    #[must_use]
+    pub fn entry_attribute(&self, entry: &str, name: &str) -> Option<&str> {
+        self.entries
+            .get(entry)
+            .and_then(|it| it.get(&name.to_ascii_lowercase()))
+            .map(String::as_str)
+    }
+
+    /// Checks whether the JAR is a multi-release JAR, i.e., it has `Multi-Release: true`.
+    #[must_use]
+    pub fn is_multi_release(&self) -> bool {
+        self.main_attribute("Multi-Release")
+            .is_some_and(|it| it.trim().eq_ignore_ascii_case("true"))
+    }
+}
+
+fn parse_section(lines: &[String]) -> HashMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| {
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            (name.trim().to_ascii_lowercase(), value.to_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_main_attributes() {
+        let manifest = Manifest::parse(
+            "Manifest-Version: 1.0\r\nMulti-Release: true\r\nCreated-By: 17 (Oracle)\r\n\r\n",
+        );
+        assert_eq!(manifest.main_attribute("manifest-version"), Some("1.0"));
+        assert_eq!(manifest.main_attribute("Created-By"), Some("17 (Oracle)"));
+        assert!(manifest.is_multi_release());
+    }
+
+    #[test]
+    fn parse_continuation_lines() {
+        let manifest =
+            Manifest::parse("Manifest-Version: 1.0\nClass-Path: lib/a.jar lib/b\n .jar\n");
+        assert_eq!(
+            manifest.main_attribute("Class-Path"),
+            Some("lib/a.jar lib/b.jar")
+        );
+        assert!(!manifest.is_multi_release());
+    }
+
+    #[test]
+    fn parse_entry_sections() {
+        let manifest = Manifest::parse(
+            "Manifest-Version: 1.0\n\nName: org/mokapot/\nSealed: true\n\nName: Foo.class\nX: y\n",
+        );
+        assert_eq!(manifest.main_attribute("Sealed"), None);
+        assert_eq!(
+            manifest.entry_attribute("org/mokapot/", "sealed"),
+            Some("true")
+        );
+        assert_eq!(manifest.entry_attribute("Foo.class", "X"), Some("y"));
+    }
+}
@@ -0,0 +1,1276 @@
+//! Resolving class paths from a local Maven repository.
+//!
+//! The resolution works fully offline against the repository layout of `~/.m2/repository`.
+//! Given a list of artifacts or a `pom.xml`, the transitive dependencies are resolved following
+//! the rules of Maven:
+//! - The version nearest to the root wins when an artifact is reachable through several paths.
+//! - The scopes propagate along the dependency paths, and dependencies in the `test` and
+//!   `provided` scopes, as well as optional dependencies, are not transitive.
+//! - Exclusions apply to the whole subtree below the dependency declaring them.
+//! - Versions and scopes in `dependencyManagement` of the root project (including imported BOMs)
+//!   override those of the transitive dependencies.
+//!
+//! Parent POMs and properties are resolved as in Maven, while profiles, version ranges and
+//! remote repositories are not supported.
+//!
+//! # Examples
+//! ```no_run
+//! use mokapot::jvm::{
+//!     class_loader::maven::{ClassPathScope, Coordinates, MavenRepository},
+//!     ClassLoader,
+//! };
+//!
+//! let repository = MavenRepository::user_default().unwrap();
+//! let root: Coordinates = "com.google.guava:guava:33.0.0-jre".parse().unwrap();
+//! let artifacts = repository.resolve(&[root], ClassPathScope::Runtime).unwrap();
+//! let class_loader = ClassLoader::new(artifacts.iter().map(|it| it.class_path()));
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use super::class_paths::JarClassPath;
+
+/// The maximum depth of parent POMs and nested property references, which guards against
+/// cycles.
+const MAX_NESTING: usize = 32;
+
+/// The coordinates of an artifact in a Maven repository.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coordinates {
+    /// The group ID (e.g., `org.apache.commons`).
+    pub group_id: String,
+    /// The artifact ID (e.g., `commons-lang3`).
+    pub artifact_id: String,
+    /// The version.
+    pub version: String,
+    /// The extension of the artifact file, which is `jar` by default.
+    pub extension: String,
+    /// The classifier (e.g., `sources` or `tests`).
+    pub classifier: Option<String>,
+}
+
+impl Coordinates {
+    /// Creates the coordinates of a JAR artifact without classifier.
+    pub fn new(
+        group_id: impl Into<String>,
+        artifact_id: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        Self {
+            group_id: group_id.into(),
+            artifact_id: artifact_id.into(),
+            version: version.into(),
+            extension: "jar".to_owned(),
+            classifier: None,
+        }
+    }
+
+    /// Returns the path of the artifact relative to the root of a repository.
+    #[must_use]
+    pub fn repository_path(&self) -> PathBuf {
+        let classifier = self
+            .classifier
+            .as_ref()
+            .map_or_else(String::new, |it| format!("-{it}"));
+        self.version_directory().join(format!(
+            "{}-{}{classifier}.{}",
+            self.artifact_id, self.version, self.extension
+        ))
+    }
+
+    /// Returns the path of the POM of the artifact relative to the root of a repository.
+    #[must_use]
+    pub fn pom_path(&self) -> PathBuf {
+        self.version_directory()
+            .join(format!("{}-{}.pom", self.artifact_id, self.version))
+    }
+
+    fn version_directory(&self) -> PathBuf {
+        self.group_id
+            .split('.')
+            .chain([self.artifact_id.as_str(), self.version.as_str()])
+            .collect()
+    }
+
+    fn key(&self) -> ArtifactKey {
+        ArtifactKey {
+            group_id: self.group_id.clone(),
+            artifact_id: self.artifact_id.clone(),
+            extension: self.extension.clone(),
+            classifier: self.classifier.clone(),
+        }
+    }
+}
+
+impl FromStr for Coordinates {
+    type Err = MavenError;
+
+    /// Parses coordinates in the form of `groupId:artifactId[:type[:classifier]]:version`, where
+    /// the type (e.g., `test-jar`) is mapped to the extension and the classifier in the same way
+    /// as the type of a dependency.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split(':').collect();
+        if parts.iter().any(|it| it.trim().is_empty()) {
+            return Err(MavenError::InvalidCoordinates(s.to_owned()));
+        }
+        let (artifact_type, classifier) = match parts.len() {
+            3 => ("jar", None),
+            4 => (parts[2], None),
+            5 => (parts[2], Some(parts[3].to_owned())),
+            _ => return Err(MavenError::InvalidCoordinates(s.to_owned())),
+        };
+        let (extension, classifier) = artifact_file_of(artifact_type.to_owned(), classifier);
+        Ok(Self {
+            group_id: parts[0].to_owned(),
+            artifact_id: parts[1].to_owned(),
+            version: parts[parts.len() - 1].to_owned(),
+            extension,
+            classifier,
+        })
+    }
+}
+
+impl Display for Coordinates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.group_id, self.artifact_id, self.extension
+        )?;
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{classifier}")?;
+        }
+        write!(f, ":{}", self.version)
+    }
+}
+
+/// Maps the type of an artifact (e.g., `test-jar`) to the extension and the classifier of its file.
+fn artifact_file_of(artifact_type: String, classifier: Option<String>) -> (String, Option<String>) {
+    match artifact_type.as_str() {
+        "test-jar" => ("jar".to_owned(), classifier.or(Some("tests".to_owned()))),
+        "ejb-client" => ("jar".to_owned(), classifier.or(Some("client".to_owned()))),
+        "pom" | "war" | "ear" | "rar" => (artifact_type, classifier),
+        // Types such as `bundle`, `ejb` and `maven-plugin` are packaged as JARs.
+        _ => ("jar".to_owned(), classifier),
+    }
+}
+
+/// The identity of an artifact regardless of its version, which is used for version mediation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ArtifactKey {
+    group_id: String,
+    artifact_id: String,
+    extension: String,
+    classifier: Option<String>,
+}
+
+/// The scope of a dependency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Scope {
+    /// Available in all class paths. This is the default scope.
+    #[default]
+    Compile,
+    /// Available when compiling, but expected to be provided at runtime.
+    Provided,
+    /// Available at runtime but not when compiling.
+    Runtime,
+    /// Available only for compiling and running the tests.
+    Test,
+    /// Like [`Scope::Provided`], but located by an explicit path instead of the repository.
+    System,
+    /// Imports the `dependencyManagement` of a BOM.
+    Import,
+}
+
+impl FromStr for Scope {
+    type Err = MavenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "compile" => Ok(Self::Compile),
+            "provided" => Ok(Self::Provided),
+            "runtime" => Ok(Self::Runtime),
+            "test" => Ok(Self::Test),
+            "system" => Ok(Self::System),
+            "import" => Ok(Self::Import),
+            _ => Err(MavenError::InvalidScope(s.to_owned())),
+        }
+    }
+}
+
+impl Scope {
+    /// Returns the scope of a transitive dependency declared with `self` under a dependency
+    /// with scope `parent`.
+    const fn under(self, parent: Self) -> Self {
+        match (parent, self) {
+            (Self::Compile, it) => it,
+            (parent, _) => parent,
+        }
+    }
+
+    /// Checks whether dependencies with this scope are inherited by the dependents.
+    const fn is_transitive(self) -> bool {
+        matches!(self, Self::Compile | Self::Runtime)
+    }
+}
+
+/// The class path to resolve, which determines the scopes of the dependencies included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ClassPathScope {
+    /// The class path for compiling, with the `compile`, `provided` and `system` dependencies.
+    Compile,
+    /// The class path for running, with the `compile` and `runtime` dependencies.
+    #[default]
+    Runtime,
+    /// The class path for testing, with the dependencies in all scopes.
+    Test,
+}
+
+impl ClassPathScope {
+    /// Checks whether the dependencies with the given scope are included in this class path.
+    #[must_use]
+    pub const fn includes(self, scope: Scope) -> bool {
+        match self {
+            Self::Compile => matches!(scope, Scope::Compile | Scope::Provided | Scope::System),
+            Self::Runtime => matches!(scope, Scope::Compile | Scope::Runtime),
+            Self::Test => !matches!(scope, Scope::Import),
+        }
+    }
+}
+
+/// An artifact in a resolved class path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedArtifact {
+    /// The coordinates of the artifact.
+    pub coordinates: Coordinates,
+    /// The effective scope of the artifact.
+    pub scope: Scope,
+    /// The path to the artifact file.
+    pub path: PathBuf,
+    /// The distance from the root, where the direct dependencies have a depth of `0`.
+    pub depth: usize,
+}
+
+impl ResolvedArtifact {
+    /// Creates a class path for the artifact.
+    #[must_use]
+    pub fn class_path(&self) -> JarClassPath {
+        JarClassPath::new(&self.path)
+    }
+}
+
+/// An error that occurs while resolving dependencies.
+#[derive(Debug, thiserror::Error)]
+pub enum MavenError {
+    /// The coordinates are not in the form of `groupId:artifactId[:extension[:classifier]]:version`.
+    #[error("Invalid coordinates: {0}")]
+    InvalidCoordinates(String),
+    /// The scope is not one of the Maven scopes.
+    #[error("Invalid scope: {0}")]
+    InvalidScope(String),
+    /// The version is missing, unresolved, or a version range.
+    #[error("Unsupported version of {artifact}: {version}")]
+    UnsupportedVersion {
+        /// The artifact, in the form of `groupId:artifactId`.
+        artifact: String,
+        /// The version declared.
+        version: String,
+    },
+    /// The artifact is not in the local repository.
+    #[error("Artifact not found in the local repository: {0}")]
+    ArtifactNotFound(Coordinates),
+    /// The POM is not in the local repository.
+    #[error("POM not found in the local repository: {0}")]
+    PomNotFound(Coordinates),
+    /// The POM is not a valid XML or lacks required elements.
+    #[error("Malformed POM {}: {reason}", .path.display())]
+    MalformedPom {
+        /// The path to the POM.
+        path: PathBuf,
+        /// The cause.
+        reason: String,
+    },
+    /// The parent POMs, imported BOMs or property references are nested too deep, which is
+    /// usually a cycle.
+    #[error("Cyclic parents, imports or properties in {0}")]
+    Cycle(String),
+    /// The home directory cannot be determined.
+    #[error("The home directory cannot be determined")]
+    NoHomeDirectory,
+    /// Error occurred while reading a file.
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+}
+
+/// A Maven repository in the local file system.
+#[derive(Debug, Clone)]
+pub struct MavenRepository {
+    root: PathBuf,
+}
+
+impl MavenRepository {
+    /// Creates a repository rooted at the given directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Opens the default local repository of the current user, i.e., `~/.m2/repository`.
+    ///
+    /// # Errors
+    /// [`MavenError::NoHomeDirectory`] if the home directory cannot be determined.
+    pub fn user_default() -> Result<Self, MavenError> {
+        let home = std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .ok_or(MavenError::NoHomeDirectory)?;
+        Ok(Self::new(
+            PathBuf::from(home).join(".m2").join("repository"),
+        ))
+    }
+
+    /// Returns the root directory of the repository.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the path to an artifact in the repository, whether or not it exists.
+    #[must_use]
+    pub fn artifact_path(&self, coordinates: &Coordinates) -> PathBuf {
+        self.root.join(coordinates.repository_path())
+    }
+
+    /// Resolves the class path of the given artifacts, which are treated as dependencies in the
+    /// `compile` scope.
+    /// The artifacts are ordered breadth-first, i.e., the given artifacts first, followed by
+    /// their dependencies in declaration order.
+    ///
+    /// # Errors
+    /// See [`MavenError`].
+    pub fn resolve(
+        &self,
+        artifacts: &[Coordinates],
+        scope: ClassPathScope,
+    ) -> Result<Vec<ResolvedArtifact>, MavenError> {
+        let roots = artifacts
+            .iter()
+            .map(|it| Dependency {
+                coordinates: it.clone(),
+                scope: Scope::Compile,
+                explicit_scope: false,
+                optional: false,
+                exclusions: Vec::new(),
+                system_path: None,
+            })
+            .collect();
+        Resolver::new(self).resolve(roots, &HashMap::new(), scope)
+    }
+
+    /// Resolves the class path of the dependencies of a project described by a `pom.xml`.
+    /// The parent POM is searched at its `relativePath` first, and then in the repository.
+    /// The project itself is not included.
+    ///
+    /// # Errors
+    /// See [`MavenError`].
+    pub fn resolve_pom(
+        &self,
+        pom: impl AsRef<Path>,
+        scope: ClassPathScope,
+    ) -> Result<Vec<ResolvedArtifact>, MavenError> {
+        let mut resolver = Resolver::new(self);
+        let model = resolver.load_file(pom.as_ref(), 0)?;
+        let project = resolver.effective(&model, 0)?;
+        resolver.resolve(project.dependencies, &project.management, scope)
+    }
+
+    /// Resolves the class path of the given artifacts as [`JarClassPath`]s.
+    /// See [`MavenRepository::resolve`].
+    ///
+    /// # Errors
+    /// See [`MavenError`].
+    pub fn class_path(
+        &self,
+        artifacts: &[Coordinates],
+        scope: ClassPathScope,
+    ) -> Result<Vec<JarClassPath>, MavenError> {
+        self.resolve(artifacts, scope)
+            .map(|it| it.iter().map(ResolvedArtifact::class_path).collect())
+    }
+}
+
+/// A dependency with all properties and managed values resolved.
+#[derive(Debug, Clone)]
+struct Dependency {
+    coordinates: Coordinates,
+    scope: Scope,
+    /// Whether the scope is declared explicitly rather than defaulted.
+    explicit_scope: bool,
+    optional: bool,
+    exclusions: Vec<Exclusion>,
+    system_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+struct Exclusion {
+    group_id: String,
+    artifact_id: String,
+}
+
+impl Exclusion {
+    fn matches(&self, coordinates: &Coordinates) -> bool {
+        (self.group_id == "*" || self.group_id == coordinates.group_id)
+            && (self.artifact_id == "*" || self.artifact_id == coordinates.artifact_id)
+    }
+}
+
+/// A dependency as declared in a POM, before interpolation.
+#[derive(Debug, Clone, Default)]
+struct DeclaredDependency {
+    group_id: String,
+    artifact_id: String,
+    version: Option<String>,
+    dependency_type: Option<String>,
+    classifier: Option<String>,
+    scope: Option<String>,
+    optional: Option<String>,
+    exclusions: Vec<(String, String)>,
+    system_path: Option<String>,
+}
+
+/// A POM merged with its parents, before interpolation.
+#[derive(Debug, Clone)]
+struct Model {
+    path: PathBuf,
+    coordinates: Coordinates,
+    properties: HashMap<String, String>,
+    management: Vec<DeclaredDependency>,
+    dependencies: Vec<DeclaredDependency>,
+}
+
+/// The dependencies of a project with properties and managed values resolved.
+struct EffectiveProject {
+    dependencies: Vec<Dependency>,
+    management: HashMap<ArtifactKey, Dependency>,
+}
+
+struct Resolver<'r> {
+    repository: &'r MavenRepository,
+    models: HashMap<Coordinates, Option<Model>>,
+}
+
+impl<'r> Resolver<'r> {
+    fn new(repository: &'r MavenRepository) -> Self {
+        Self {
+            repository,
+            models: HashMap::new(),
+        }
+    }
+
+    fn resolve(
+        &mut self,
+        roots: Vec<Dependency>,
+        root_management: &HashMap<ArtifactKey, Dependency>,
+        class_path_scope: ClassPathScope,
+    ) -> Result<Vec<ResolvedArtifact>, MavenError> {
+        let mut resolved = Vec::new();
+        let mut selected = HashSet::new();
+        let mut queue: VecDeque<_> = roots
+            .into_iter()
+            .filter(|it| class_path_scope.includes(it.scope))
+            .map(|it| (it, 0, Vec::new()))
+            .collect();
+        while let Some((dependency, depth, mut exclusions)) = queue.pop_front() {
+            // The nearest declaration wins, and the first one wins among the same depth.
+            if !selected.insert(dependency.coordinates.key()) {
+                continue;
+            }
+            let coordinates = &dependency.coordinates;
+            let path = match &dependency.system_path {
+                Some(path) => path.clone(),
+                None => self.repository.artifact_path(coordinates),
+            };
+            if coordinates.extension != "pom" {
+                if !path.is_file() {
+                    return Err(MavenError::ArtifactNotFound(coordinates.clone()));
+                }
+                resolved.push(ResolvedArtifact {
+                    coordinates: coordinates.clone(),
+                    scope: dependency.scope,
+                    path,
+                    depth,
+                });
+            }
+            if dependency.system_path.is_some() {
+                continue;
+            }
+            // An artifact without POM has no dependency information.
+            let Some(model) = self.load_artifact(coordinates, 0, false)? else {
+                continue;
+            };
+            let project = self.effective(&model, 0)?;
+            exclusions.extend(dependency.exclusions);
+            for mut child in project.dependencies {
+                if child.optional
+                    || !child.scope.is_transitive()
+                    || exclusions.iter().any(|it| it.matches(&child.coordinates))
+                {
+                    continue;
+                }
+                child.scope = child.scope.under(dependency.scope);
+                if let Some(managed) = root_management.get(&child.coordinates.key()) {
+                    child
+                        .coordinates
+                        .version
+                        .clone_from(&managed.coordinates.version);
+                    if managed.explicit_scope {
+                        child.scope = managed.scope;
+                    }
+                }
+                if class_path_scope.includes(child.scope) {
+                    queue.push_back((child, depth + 1, exclusions.clone()));
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Loads the model of an artifact in the repository, or `None` if its POM does not exist
+    /// and `required` is `false`.
+    fn load_artifact(
+        &mut self,
+        coordinates: &Coordinates,
+        nesting: usize,
+        required: bool,
+    ) -> Result<Option<Model>, MavenError> {
+        let pom_coordinates = Coordinates {
+            extension: "pom".to_owned(),
+            classifier: None,
+            ..coordinates.clone()
+        };
+        if let Some(model) = self.models.get(&pom_coordinates) {
+            return match model {
+                None if required => Err(MavenError::PomNotFound(pom_coordinates)),
+                it => Ok(it.clone()),
+            };
+        }
+        let path = self.repository.root.join(pom_coordinates.pom_path());
+        let model = if path.is_file() {
+            Some(self.load_file(&path, nesting)?)
+        } else if required {
+            return Err(MavenError::PomNotFound(pom_coordinates));
+        } else {
+            None
+        };
+        self.models.insert(pom_coordinates, model.clone());
+        Ok(model)
+    }
+
+    fn load_file(&mut self, path: &Path, nesting: usize) -> Result<Model, MavenError> {
+        if nesting > MAX_NESTING {
+            return Err(MavenError::Cycle(path.display().to_string()));
+        }
+        let content = std::fs::read_to_string(path)?;
+        let pom = Pom::parse(&content).map_err(|reason| MavenError::MalformedPom {
+            path: path.to_owned(),
+            reason,
+        })?;
+        let parent = match &pom.parent {
+            Some(parent) => Some(self.load_parent(path, parent, nesting + 1)?),
+            None => None,
+        };
+        let group_id = pom
+            .group_id
+            .clone()
+            .or_else(|| parent.as_ref().map(|it| it.coordinates.group_id.clone()));
+        let version = pom
+            .version
+            .clone()
+            .or_else(|| parent.as_ref().map(|it| it.coordinates.version.clone()));
+        let (Some(group_id), Some(version)) = (group_id, version) else {
+            return Err(MavenError::MalformedPom {
+                path: path.to_owned(),
+                reason: "Missing groupId or version".to_owned(),
+            });
+        };
+        let coordinates = Coordinates {
+            extension: pom.packaging.clone().unwrap_or_else(|| "jar".to_owned()),
+            ..Coordinates::new(group_id, pom.artifact_id.clone(), version)
+        };
+
+        let (mut properties, mut management, mut dependencies) = match parent {
+            Some(parent) => {
+                let mut properties = parent.properties;
+                for (key, value) in [
+                    ("groupId", &parent.coordinates.group_id),
+                    ("artifactId", &parent.coordinates.artifact_id),
+                    ("version", &parent.coordinates.version),
+                ] {
+                    properties.insert(format!("project.parent.{key}"), value.clone());
+                    properties.insert(format!("parent.{key}"), value.clone());
+                }
+                (properties, parent.management, parent.dependencies)
+            }
+            None => Default::default(),
+        };
+        properties.extend(pom.properties);
+        for (key, value) in [
+            ("groupId", &coordinates.group_id),
+            ("artifactId", &coordinates.artifact_id),
+            ("version", &coordinates.version),
+            ("packaging", &coordinates.extension),
+        ] {
+            for prefix in ["project.", "pom.", ""] {
+                properties.insert(format!("{prefix}{key}"), value.clone());
+            }
+        }
+        if let Some(dir) = path.parent() {
+            properties.insert("basedir".to_owned(), dir.display().to_string());
+            properties.insert("project.basedir".to_owned(), dir.display().to_string());
+        }
+        // Declarations in the child override the inherited ones for the same artifact.
+        for declared in pom.management {
+            management.retain(|it| !it.same_artifact(&declared));
+            management.push(declared);
+        }
+        for declared in pom.dependencies {
+            dependencies.retain(|it| !it.same_artifact(&declared));
+            dependencies.push(declared);
+        }
+        Ok(Model {
+            path: path.to_owned(),
+            coordinates,
+            properties,
+            management,
+            dependencies,
+        })
+    }
+
+    fn load_parent(
+        &mut self,
+        child_path: &Path,
+        parent: &ParentRef,
+        nesting: usize,
+    ) -> Result<Model, MavenError> {
+        let relative_path = parent.relative_path.as_deref().unwrap_or("../pom.xml");
+        if !relative_path.is_empty() {
+            if let Some(dir) = child_path.parent() {
+                let mut candidate = dir.join(relative_path);
+                if candidate.is_dir() {
+                    candidate = candidate.join("pom.xml");
+                }
+                if candidate.is_file() {
+                    let model = self.load_file(&candidate, nesting)?;
+                    let coordinates = &model.coordinates;
+                    if coordinates.group_id == parent.group_id
+                        && coordinates.artifact_id == parent.artifact_id
+                        && coordinates.version == parent.version
+                    {
+                        return Ok(model);
+                    }
+                }
+            }
+        }
+        let coordinates = Coordinates::new(
+            parent.group_id.clone(),
+            parent.artifact_id.clone(),
+            parent.version.clone(),
+        );
+        self.load_artifact(&coordinates, nesting, true)
+            .map(|it| it.expect("A required model is always loaded"))
+    }
+
+    fn effective(&mut self, model: &Model, nesting: usize) -> Result<EffectiveProject, MavenError> {
+        if nesting > MAX_NESTING {
+            return Err(MavenError::Cycle(model.path.display().to_string()));
+        }
+        let mut management = HashMap::new();
+        let mut imports = Vec::new();
+        for declared in &model.management {
+            let dependency = model.interpolate(declared, None)?;
+            if dependency.scope == Scope::Import {
+                imports.push(dependency);
+            } else {
+                management
+                    .entry(dependency.coordinates.key())
+                    .or_insert(dependency);
+            }
+        }
+        // The BOMs are imported in declaration order, and never override explicit declarations.
+        for bom in imports {
+            let bom_coordinates = bom.coordinates;
+            let bom_model = self
+                .load_artifact(&bom_coordinates, 0, true)?
+                .expect("A required model is always loaded");
+            for (key, dependency) in self.effective(&bom_model, nesting + 1)?.management {
+                management.entry(key).or_insert(dependency);
+            }
+        }
+        let dependencies = model
+            .dependencies
+            .iter()
+            .map(|declared| {
+                let managed = management.get(&declared.key(model));
+                model.interpolate(declared, managed)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(EffectiveProject {
+            dependencies,
+            management,
+        })
+    }
+}
+
+impl Model {
+    fn substitute(&self, value: &str) -> Result<String, MavenError> {
+        let mut value = value.to_owned();
+        for _ in 0..MAX_NESTING {
+            let mut changed = false;
+            let mut result = String::with_capacity(value.len());
+            let mut rest = value.as_str();
+            while let Some(start) = rest.find("${") {
+                result.push_str(&rest[..start]);
+                let Some(len) = rest[start..].find('}') else {
+                    break;
+                };
+                let name = &rest[start + 2..start + len];
+                if let Some(replacement) = self.properties.get(name) {
+                    result.push_str(replacement);
+                    changed = true;
+                } else {
+                    result.push_str(&rest[start..=start + len]);
+                }
+                rest = &rest[start + len + 1..];
+            }
+            result.push_str(rest);
+            if !changed {
+                return Ok(result);
+            }
+            value = result;
+        }
+        Err(MavenError::Cycle(self.path.display().to_string()))
+    }
+
+    fn interpolate(
+        &self,
+        declared: &DeclaredDependency,
+        managed: Option<&Dependency>,
+    ) -> Result<Dependency, MavenError> {
+        let group_id = self.substitute(&declared.group_id)?;
+        let artifact_id = self.substitute(&declared.artifact_id)?;
+        let version = match &declared.version {
+            Some(version) => Some(self.substitute(version)?),
+            None => managed.map(|it| it.coordinates.version.clone()),
+        };
+        let version = version.unwrap_or_default();
+        if version.is_empty() || version.contains("${") || version.starts_with(['[', '(']) {
+            return Err(MavenError::UnsupportedVersion {
+                artifact: format!("{group_id}:{artifact_id}"),
+                version,
+            });
+        }
+        let (extension, classifier) = declared.extension_and_classifier(self)?;
+        let explicit_scope =
+            declared.scope.is_some() || managed.is_some_and(|it| it.explicit_scope);
+        let scope = match &declared.scope {
+            Some(scope) => self.substitute(scope)?.parse()?,
+            None => managed.map(|it| it.scope).unwrap_or_default(),
+        };
+        let optional = match &declared.optional {
+            Some(optional) => self.substitute(optional)? == "true",
+            None => false,
+        };
+        let exclusions = if declared.exclusions.is_empty() {
+            managed.map(|it| it.exclusions.clone()).unwrap_or_default()
+        } else {
+            declared
+                .exclusions
+                .iter()
+                .map(|(group_id, artifact_id)| {
+                    Ok(Exclusion {
+                        group_id: self.substitute(group_id)?,
+                        artifact_id: self.substitute(artifact_id)?,
+                    })
+                })
+                .collect::<Result<_, MavenError>>()?
+        };
+        let system_path = declared
+            .system_path
+            .as_ref()
+            .map(|it| self.substitute(it).map(PathBuf::from))
+            .transpose()?;
+        Ok(Dependency {
+            coordinates: Coordinates {
+                group_id,
+                artifact_id,
+                version,
+                extension,
+                classifier,
+            },
+            scope,
+            explicit_scope,
+            optional,
+            exclusions,
+            system_path,
+        })
+    }
+}
+
+impl DeclaredDependency {
+    fn same_artifact(&self, other: &Self) -> bool {
+        self.group_id == other.group_id
+            && self.artifact_id == other.artifact_id
+            && self.dependency_type == other.dependency_type
+            && self.classifier == other.classifier
+    }
+
+    /// Maps the dependency type to the extension and classifier of the artifact.
+    fn extension_and_classifier(
+        &self,
+        model: &Model,
+    ) -> Result<(String, Option<String>), MavenError> {
+        let dependency_type = match &self.dependency_type {
+            Some(it) => model.substitute(it)?,
+            None => "jar".to_owned(),
+        };
+        let classifier = self
+            .classifier
+            .as_ref()
+            .map(|it| model.substitute(it))
+            .transpose()?;
+        Ok(artifact_file_of(dependency_type, classifier))
+    }
+
+    fn key(&self, model: &Model) -> ArtifactKey {
+        let (extension, classifier) = self
+            .extension_and_classifier(model)
+            .unwrap_or_else(|_| ("jar".to_owned(), None));
+        ArtifactKey {
+            group_id: model
+                .substitute(&self.group_id)
+                .unwrap_or_else(|_| self.group_id.clone()),
+            artifact_id: model
+                .substitute(&self.artifact_id)
+                .unwrap_or_else(|_| self.artifact_id.clone()),
+            extension,
+            classifier,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ParentRef {
+    group_id: String,
+    artifact_id: String,
+    version: String,
+    relative_path: Option<String>,
+}
+
+/// The contents of a `pom.xml` relevant to dependency resolution.
+#[derive(Debug)]
+struct Pom {
+    group_id: Option<String>,
+    artifact_id: String,
+    version: Option<String>,
+    packaging: Option<String>,
+    parent: Option<ParentRef>,
+    properties: Vec<(String, String)>,
+    management: Vec<DeclaredDependency>,
+    dependencies: Vec<DeclaredDependency>,
+}
+
+impl Pom {
+    fn parse(content: &str) -> Result<Self, String> {
+        let document = roxmltree::Document::parse(content).map_err(|it| it.to_string())?;
+        let project = document.root_element();
+        if project.tag_name().name() != "project" {
+            return Err("The root element is not <project>".to_owned());
+        }
+        let parent = child(project, "parent")
+            .map(|it| {
+                Ok::<_, String>(ParentRef {
+                    group_id: required_text(it, "groupId")?,
+                    artifact_id: required_text(it, "artifactId")?,
+                    version: required_text(it, "version")?,
+                    relative_path: text(it, "relativePath"),
+                })
+            })
+            .transpose()?;
+        let properties = child(project, "properties")
+            .into_iter()
+            .flat_map(|it| it.children().filter(roxmltree::Node::is_element))
+            .map(|it| {
+                (
+                    it.tag_name().name().to_owned(),
+                    it.text().unwrap_or_default().trim().to_owned(),
+                )
+            })
+            .collect();
+        let management = child(project, "dependencyManagement")
+            .map(|it| parse_dependencies(it))
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Self {
+            group_id: text(project, "groupId"),
+            artifact_id: required_text(project, "artifactId")?,
+            version: text(project, "version"),
+            packaging: text(project, "packaging"),
+            parent,
+            properties,
+            management,
+            dependencies: parse_dependencies(project)?,
+        })
+    }
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children()
+        .find(|it| it.is_element() && it.tag_name().name() == name)
+}
+
+fn text(node: roxmltree::Node<'_, '_>, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|it| it.text())
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+fn required_text(node: roxmltree::Node<'_, '_>, name: &str) -> Result<String, String> {
+    text(node, name).ok_or_else(|| format!("Missing <{name}> in <{}>", node.tag_name().name()))
+}
+
+/// Parses the `<dependencies>` under the given node.
+fn parse_dependencies(node: roxmltree::Node<'_, '_>) -> Result<Vec<DeclaredDependency>, String> {
+    let Some(dependencies) = child(node, "dependencies") else {
+        return Ok(Vec::new());
+    };
+    dependencies
+        .children()
+        .filter(|it| it.is_element() && it.tag_name().name() == "dependency")
+        .map(|it| {
+            let exclusions = child(it, "exclusions")
+                .into_iter()
+                .flat_map(|it| it.children().filter(roxmltree::Node::is_element))
+                .map(|it| {
+                    Ok::<_, String>((
+                        required_text(it, "groupId")?,
+                        required_text(it, "artifactId")?,
+                    ))
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(DeclaredDependency {
+                group_id: required_text(it, "groupId")?,
+                artifact_id: required_text(it, "artifactId")?,
+                version: text(it, "version"),
+                dependency_type: text(it, "type"),
+                classifier: text(it, "classifier"),
+                scope: text(it, "scope"),
+                optional: text(it, "optional"),
+                exclusions,
+                system_path: text(it, "systemPath"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        jvm::ClassLoader,
+        tests::{empty_class_named, jar_with_entries},
+    };
+
+    use super::*;
+
+    /// A fixture repository in a temporary directory.
+    struct Fixture {
+        dir: tempfile::TempDir,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                dir: tempfile::tempdir().unwrap(),
+            }
+        }
+
+        fn repository(&self) -> MavenRepository {
+            MavenRepository::new(self.dir.path().join("repository"))
+        }
+
+        /// Installs an artifact with a JAR containing a class named after it.
+        fn install(&self, coordinates: &str, dependencies: &str) {
+            let coordinates: Coordinates = coordinates.parse().unwrap();
+            let repository = self.repository();
+            self.install_pom(&coordinates, "", dependencies);
+            let class_name = format!("fixture/{}", coordinates.artifact_id);
+            let jar = jar_with_entries(&[(
+                &format!("{class_name}.class"),
+                &empty_class_named(&class_name, "java/lang/Object"),
+            )]);
+            std::fs::write(repository.artifact_path(&coordinates), jar).unwrap();
+        }
+
+        fn install_pom(&self, coordinates: &Coordinates, extra: &str, dependencies: &str) {
+            let path = self.repository().root().join(coordinates.pom_path());
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, pom(coordinates, extra, dependencies)).unwrap();
+        }
+    }
+
+    fn pom(coordinates: &Coordinates, extra: &str, dependencies: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <project xmlns="http://maven.apache.org/POM/4.0.0">
+                <modelVersion>4.0.0</modelVersion>
+                <groupId>{}</groupId>
+                <artifactId>{}</artifactId>
+                <version>{}</version>
+                {extra}
+                <dependencies>{dependencies}</dependencies>
+            </project>"#,
+            coordinates.group_id, coordinates.artifact_id, coordinates.version
+        )
+    }
+
+    fn dependency(coordinates: &str, extra: &str) -> String {
+        let coordinates: Coordinates = coordinates.parse().unwrap();
+        format!(
+            "<dependency><groupId>{}</groupId><artifactId>{}</artifactId>\
+             <version>{}</version>{extra}</dependency>",
+            coordinates.group_id, coordinates.artifact_id, coordinates.version
+        )
+    }
+
+    fn names(artifacts: &[ResolvedArtifact]) -> Vec<String> {
+        artifacts
+            .iter()
+            .map(|it| it.coordinates.to_string())
+            .collect()
+    }
+
+    /// Installs `app -> lib -> {util (runtime), junit (test), opt (optional)}` and
+    /// `app -> util:1.0 -> log`.
+    fn install_graph(fixture: &Fixture) {
+        fixture.install("org.mokapot:log:1.0", "");
+        fixture.install(
+            "org.mokapot:util:1.0",
+            &dependency("org.mokapot:log:1.0", ""),
+        );
+        fixture.install("org.mokapot:util:2.0", "");
+        fixture.install("org.mokapot:junit:1.0", "");
+        fixture.install("org.mokapot:opt:1.0", "");
+        fixture.install(
+            "org.mokapot:lib:1.0",
+            &[
+                dependency("org.mokapot:util:2.0", "<scope>runtime</scope>"),
+                dependency("org.mokapot:junit:1.0", "<scope>test</scope>"),
+                dependency("org.mokapot:opt:1.0", "<optional>true</optional>"),
+            ]
+            .concat(),
+        );
+    }
+
+    #[test]
+    fn parse_coordinates() {
+        let coordinates: Coordinates = "org.mokapot:lib:test-jar:1.0".parse().unwrap();
+        assert_eq!(coordinates.classifier.as_deref(), Some("tests"));
+        assert_eq!(coordinates.extension, "jar");
+        assert_eq!(coordinates.to_string(), "org.mokapot:lib:jar:tests:1.0");
+        assert_eq!(
+            coordinates.repository_path(),
+            Path::new("org/mokapot/lib/1.0/lib-1.0-tests.jar")
+        );
+        let coordinates: Coordinates = "org.mokapot:lib:war:1.0".parse().unwrap();
+        assert_eq!(coordinates.extension, "war");
+        assert_eq!(
+            Coordinates::new("org.mokapot", "lib", "1.0").repository_path(),
+            Path::new("org/mokapot/lib/1.0/lib-1.0.jar")
+        );
+        assert!(matches!(
+            "org.mokapot:lib".parse::<Coordinates>(),
+            Err(MavenError::InvalidCoordinates(_))
+        ));
+        assert!(matches!(
+            "org.mokapot::1.0".parse::<Coordinates>(),
+            Err(MavenError::InvalidCoordinates(_))
+        ));
+    }
+
+    #[test]
+    fn transitive_scopes() {
+        let fixture = Fixture::new();
+        install_graph(&fixture);
+        let repository = fixture.repository();
+        let root = ["org.mokapot:lib:1.0".parse().unwrap()];
+
+        let compile = repository.resolve(&root, ClassPathScope::Compile).unwrap();
+        assert_eq!(names(&compile), ["org.mokapot:lib:jar:1.0"]);
+
+        let runtime = repository.resolve(&root, ClassPathScope::Runtime).unwrap();
+        assert_eq!(
+            names(&runtime),
+            ["org.mokapot:lib:jar:1.0", "org.mokapot:util:jar:2.0"]
+        );
+        assert_eq!(runtime[1].scope, Scope::Runtime);
+        assert_eq!(runtime[1].depth, 1);
+    }
+
+    #[test]
+    fn nearest_wins_and_exclusions() {
+        let fixture = Fixture::new();
+        install_graph(&fixture);
+        let app = Coordinates::new("org.mokapot", "app", "1.0");
+        fixture.install_pom(
+            &app,
+            "",
+            &[
+                dependency("org.mokapot:lib:1.0", ""),
+                dependency(
+                    "org.mokapot:util:1.0",
+                    "<exclusions><exclusion><groupId>*</groupId>\
+                     <artifactId>log</artifactId></exclusion></exclusions>",
+                ),
+            ]
+            .concat(),
+        );
+        let pom = fixture.repository().root().join(app.pom_path());
+        let artifacts = fixture
+            .repository()
+            .resolve_pom(pom, ClassPathScope::Runtime)
+            .unwrap();
+        assert_eq!(
+            names(&artifacts),
+            ["org.mokapot:lib:jar:1.0", "org.mokapot:util:jar:1.0"]
+        );
+        let class_loader = ClassLoader::new(artifacts.iter().map(ResolvedArtifact::class_path));
+        assert!(class_loader.load_class("fixture/util").is_ok());
+    }
+
+    #[test]
+    fn parent_properties_and_management() {
+        let fixture = Fixture::new();
+        install_graph(&fixture);
+        let bom = Coordinates {
+            extension: "pom".to_owned(),
+            ..Coordinates::new("org.mokapot", "bom", "1.0")
+        };
+        fixture.install_pom(
+            &bom,
+            &format!(
+                "<dependencyManagement><dependencies>{}</dependencies></dependencyManagement>",
+                dependency("org.mokapot:util:1.0", "")
+            ),
+            "",
+        );
+        let project = fixture.dir.path().join("project");
+        std::fs::create_dir_all(project.join("module")).unwrap();
+        std::fs::write(
+            project.join("pom.xml"),
+            pom(
+                &Coordinates::new("org.mokapot", "parent", "1.0"),
+                "<packaging>pom</packaging>\
+                 <properties><lib.version>1.0</lib.version></properties>\
+                 <dependencyManagement><dependencies>\
+                 <dependency><groupId>org.mokapot</groupId><artifactId>bom</artifactId>\
+                 <version>${project.version}</version><type>pom</type><scope>import</scope>\
+                 </dependency></dependencies></dependencyManagement>",
+                "",
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            project.join("module").join("pom.xml"),
+            r"<project>
+                <parent>
+                    <groupId>org.mokapot</groupId>
+                    <artifactId>parent</artifactId>
+                    <version>1.0</version>
+                </parent>
+                <artifactId>module</artifactId>
+                <dependencies>
+                    <dependency>
+                        <groupId>${project.groupId}</groupId>
+                        <artifactId>lib</artifactId>
+                        <version>${lib.version}</version>
+                    </dependency>
+                </dependencies>
+            </project>",
+        )
+        .unwrap();
+        let artifacts = fixture
+            .repository()
+            .resolve_pom(project.join("module").join("pom.xml"), ClassPathScope::Test)
+            .unwrap();
+        // The version of `util` is managed by the imported BOM, and `log` comes with it.
+        assert_eq!(
+            names(&artifacts),
+            [
+                "org.mokapot:lib:jar:1.0",
+                "org.mokapot:util:jar:1.0",
+                "org.mokapot:log:jar:1.0"
+            ]
+        );
+        assert_eq!(artifacts[1].scope, Scope::Runtime);
+    }
+
+    #[test]
+    fn cyclic_bom_imports() {
+        let fixture = Fixture::new();
+        let import = |artifact_id: &str| {
+            format!(
+                "<dependencyManagement><dependencies>\
+                 <dependency><groupId>org.mokapot</groupId><artifactId>{artifact_id}</artifactId>\
+                 <version>1.0</version><type>pom</type><scope>import</scope>\
+                 </dependency></dependencies></dependencyManagement>"
+            )
+        };
+        let bom = |artifact_id: &str| Coordinates {
+            extension: "pom".to_owned(),
+            ..Coordinates::new("org.mokapot", artifact_id, "1.0")
+        };
+        fixture.install_pom(&bom("self"), &import("self"), "");
+        fixture.install_pom(&bom("ping"), &import("pong"), "");
+        fixture.install_pom(&bom("pong"), &import("ping"), "");
+        for artifact_id in ["self", "ping"] {
+            let root = [bom(artifact_id)];
+            assert!(matches!(
+                fixture.repository().resolve(&root, ClassPathScope::Runtime),
+                Err(MavenError::Cycle(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn missing_artifact() {
+        let fixture = Fixture::new();
+        let repository = fixture.repository();
+        let root = ["org.mokapot:missing:1.0".parse().unwrap()];
+        assert!(matches!(
+            repository.resolve(&root, ClassPathScope::Runtime),
+            Err(MavenError::ArtifactNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn artifact_without_pom() {
+        let fixture = Fixture::new();
+        let coordinates = Coordinates::new("org.mokapot", "bare", "1.0");
+        let path = fixture.repository().artifact_path(&coordinates);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, jar_with_entries(&[])).unwrap();
+        let class_path = fixture
+            .repository()
+            .class_path(&[coordinates], ClassPathScope::Runtime)
+            .unwrap();
+        assert_eq!(class_path.len(), 1);
+        assert_eq!(class_path[0].jar_file(), path);
+    }
+}
@@ -1,10 +1,11 @@
 //! Discovering and loading classes.
 
-use std::{borrow::Borrow, ops::Deref};
+use std::{borrow::Borrow, ffi::OsStr, ops::Deref};
 
 use crate::utils::Cache;
 
 use super::{Class, ClassLoader};
+use class_paths::ClassPathEntry;
 
 /// An error that can occur while loading a class.
 #[derive(thiserror::Error, Debug)]
@@ -30,6 +31,32 @@ pub trait ClassPath {
     /// # Errors
     /// See [`Error`].
     fn find_class(&self, binary_name: &str) -> Result<Class, Error>;
+
+    /// Reads the bytes of a resource by its path relative to the root of the class path
+    /// (e.g., `org/mokapot/Foo.class` or `META-INF/MANIFEST.MF`).
+    ///
+    /// The default implementation returns [`Error::NotFound`], which is suitable for class paths
+    /// not backed by files.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let _ = name;
+        Err(Error::NotFound)
+    }
+
+    /// Lists the resources directly in a directory of the class path (e.g., `META-INF/services`)
+    /// by their paths relative to the root of the class path.
+    ///
+    /// The default implementation returns an empty list, which is suitable for class paths
+    /// not backed by files.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    fn list_resources(&self, directory: &str) -> Result<Vec<String>, Error> {
+        let _ = directory;
+        Ok(Vec::new())
+    }
 }
 
 impl<T> ClassPath for T
@@ -40,6 +67,14 @@ where
     fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
         self.deref().find_class(binary_name)
     }
+
+    fn find_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        self.deref().find_resource(name)
+    }
+
+    fn list_resources(&self, directory: &str) -> Result<Vec<String>, Error> {
+        self.deref().list_resources(directory)
+    }
 }
 
 impl<P> ClassLoader<P> {
@@ -51,16 +86,31 @@ impl<P> ClassLoader<P> {
     where
         P: ClassPath,
     {
-        for class_path in &self.class_path {
+        self.load_class_with_entry(binary_name)
+            .map(|(_, class)| class)
+    }
+
+    /// Loads a class together with the index of the class path entry it is found in.
+    pub(crate) fn load_class_with_entry(&self, binary_name: &str) -> Result<(usize, Class), Error>
+    where
+        P: ClassPath,
+    {
+        for (idx, class_path) in self.class_path.iter().enumerate() {
             match class_path.find_class(binary_name) {
-                Ok(class) => return Ok(class),
-                Err(Error::NotFound) => continue,
+                Ok(class) => return Ok((idx, class)),
+                Err(Error::NotFound) => {}
                 Err(err) => return Err(err),
             }
         }
         Err(Error::NotFound)
     }
 
+    /// Returns the class path entries in the order they are searched.
+    #[must_use]
+    pub fn class_path(&self) -> &[P] {
+        &self.class_path
+    }
+
     /// Create a new class loader with the given class paths.
     #[must_use]
     pub fn new<C: IntoIterator<Item = P>>(class_path: C) -> Self {
@@ -76,7 +126,26 @@ impl<P> ClassLoader<P> {
     }
 }
 
+impl ClassLoader<ClassPathEntry> {
+    /// Create a new class loader from a class path specification (e.g., `a.jar:lib/*:classes`).
+    /// See [`ClassPathEntry::parse_spec`] for how the specification is interpreted.
+    #[must_use]
+    pub fn from_class_path_spec(spec: impl AsRef<OsStr>) -> Self {
+        Self::new(ClassPathEntry::parse_spec(spec))
+    }
+}
+
+mod bounded;
 pub mod class_paths;
+pub mod manifest;
+#[cfg(feature = "maven")]
+pub mod maven;
+mod tree;
+
+pub use bounded::BoundedCachingClassLoader;
+pub use tree::{
+    ClassIdentity, ClassLoaderTree, DelegationPolicy, LoadedClass, LoaderId, ProhibitedPackage,
+};
 
 /// A class loader that caches loaded classes.
 #[derive(Debug)]
@@ -0,0 +1,522 @@
+//! Hierarchical class loaders.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{PoisonError, RwLock},
+};
+
+use crate::{
+    jvm::{references::ClassRef, Class, ClassLoader},
+    macros::see_jvm_spec,
+    utils::Cache,
+};
+
+use super::{ClassPath, Error};
+
+/// The identifier of a class loader in a [`ClassLoaderTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LoaderId(usize);
+
+impl LoaderId {
+    /// The bootstrap class loader, which is the root of every [`ClassLoaderTree`].
+    pub const BOOTSTRAP: Self = Self(0);
+}
+
+impl Display for LoaderId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "loader#{}", self.0)
+    }
+}
+
+/// The order in which a class loader searches itself and its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DelegationPolicy {
+    /// Delegates to the parent first, and searches its own class path only if the parent cannot
+    /// find the class. This is the default policy of the JDK class loaders.
+    #[default]
+    ParentFirst,
+    /// Searches its own class path first, and delegates to the parent only if the class is not
+    /// found, which is common in web containers and plugin systems.
+    /// Classes in the `java` package and its subpackages are always delegated to the parent first.
+    ChildFirst,
+}
+
+/// An error indicating that a class loader other than the bootstrap class loader attempts to
+/// define a class in the `java` package or its subpackages, which the JVM rejects with a
+/// `SecurityException`.
+///
+/// It is returned as [`Error::Other`] by [`ClassLoaderTree::load_class`].
+#[derive(Debug, thiserror::Error)]
+#[error("{class_ref} in a prohibited package cannot be defined by {loader}")]
+pub struct ProhibitedPackage {
+    /// The class.
+    pub class_ref: ClassRef,
+    /// The class loader attempting to define the class.
+    pub loader: LoaderId,
+}
+
+/// The identity of a class at runtime, which is determined by its binary name together with
+/// its defining loader.
+#[doc = see_jvm_spec!(5, 3)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClassIdentity {
+    /// The class loader that defines the class.
+    pub defining_loader: LoaderId,
+    /// The binary name of the class.
+    pub class_ref: ClassRef,
+}
+
+impl Display for ClassIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.class_ref, self.defining_loader)
+    }
+}
+
+/// A class loaded by a [`ClassLoaderTree`].
+#[derive(Debug, Clone, Copy)]
+pub struct LoadedClass<'a> {
+    /// The class loader that defines the class, i.e., whose class path contains the class.
+    pub defining_loader: LoaderId,
+    /// The class loader that the class is requested from.
+    pub initiating_loader: LoaderId,
+    /// The loaded class, which is shared by all the initiating loaders of the class.
+    pub class: &'a Class,
+}
+
+impl LoadedClass<'_> {
+    /// Returns the runtime identity of the class.
+    #[must_use]
+    pub fn identity(&self) -> ClassIdentity {
+        ClassIdentity {
+            defining_loader: self.defining_loader,
+            class_ref: self.class.as_ref(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LoaderNode<P> {
+    name: String,
+    parent: Option<LoaderId>,
+    policy: DelegationPolicy,
+    class_loader: ClassLoader<P>,
+    /// The classes defined by this loader.
+    defined: Cache<String, Class>,
+    /// The defining loaders of the classes for which this loader is an initiating loader.
+    initiated: RwLock<HashMap<String, LoaderId>>,
+}
+
+impl<P> LoaderNode<P> {
+    fn new(
+        name: String,
+        parent: Option<LoaderId>,
+        policy: DelegationPolicy,
+        class_loader: ClassLoader<P>,
+    ) -> Self {
+        Self {
+            name,
+            parent,
+            policy,
+            class_loader,
+            defined: Cache::new(),
+            initiated: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Defines a class with this loader, whose identifier is `loader`.
+    fn define_class(&self, loader: LoaderId, binary_name: &str) -> Result<(LoaderId, &Class), Error>
+    where
+        P: ClassPath,
+    {
+        if loader != LoaderId::BOOTSTRAP && is_prohibited(binary_name) {
+            // The class path is searched so that a missing class is still reported as not found.
+            self.class_loader.load_class(binary_name)?;
+            return Err(Error::Other(Box::new(ProhibitedPackage {
+                class_ref: ClassRef::new(binary_name),
+                loader,
+            })));
+        }
+        self.defined
+            .get_or_try_put(binary_name, |it| self.class_loader.load_class(it))
+            .map(|class| (loader, class))
+    }
+}
+
+fn is_prohibited(binary_name: &str) -> bool {
+    binary_name.starts_with("java/")
+}
+
+/// A tree of class loaders, where each class loader has its own class path and delegates to
+/// its parent according to its [`DelegationPolicy`].
+///
+/// Each class loader defines a class at most once, and records itself as an initiating loader of
+/// the classes it loads, so that it keeps returning the same class for the same name.
+/// Only the bootstrap class loader can define classes in the `java` package and its
+/// subpackages.
+#[doc = see_jvm_spec!(5, 3)]
+///
+/// # Examples
+/// ```no_run
+/// use mokapot::jvm::{
+///     class_loader::{
+///         class_paths::DirectoryClassPath, ClassLoaderTree, DelegationPolicy, LoaderId,
+///     },
+///     ClassLoader,
+/// };
+///
+/// let mut tree = ClassLoaderTree::new(ClassLoader::new([DirectoryClassPath::new("jdk")]));
+/// let platform = tree.add_loader(
+///     "platform",
+///     LoaderId::BOOTSTRAP,
+///     DelegationPolicy::ParentFirst,
+///     ClassLoader::new([]),
+/// );
+/// let app = tree.add_loader(
+///     "app",
+///     platform,
+///     DelegationPolicy::ParentFirst,
+///     ClassLoader::new([DirectoryClassPath::new("classes")]),
+/// );
+/// let loaded = tree.load_class(app, "org/mokapot/test/MyClass").unwrap();
+/// assert_eq!(loaded.defining_loader, app);
+/// ```
+#[derive(Debug)]
+pub struct ClassLoaderTree<P> {
+    loaders: Vec<LoaderNode<P>>,
+}
+
+impl<P> ClassLoaderTree<P> {
+    /// Creates a tree with only the bootstrap class loader.
+    #[must_use]
+    pub fn new(bootstrap: ClassLoader<P>) -> Self {
+        let root = LoaderNode::new(
+            "bootstrap".to_owned(),
+            None,
+            DelegationPolicy::ParentFirst,
+            bootstrap,
+        );
+        Self {
+            loaders: vec![root],
+        }
+    }
+
+    /// Adds a class loader as a child of `parent`.
+    ///
+    /// # Panics
+    /// Panics if `parent` does not belong to this tree.
+    pub fn add_loader(
+        &mut self,
+        name: impl Into<String>,
+        parent: LoaderId,
+        policy: DelegationPolicy,
+        class_loader: ClassLoader<P>,
+    ) -> LoaderId {
+        assert!(
+            parent.0 < self.loaders.len(),
+            "The parent loader does not belong to this tree"
+        );
+        let id = LoaderId(self.loaders.len());
+        self.loaders.push(LoaderNode::new(
+            name.into(),
+            Some(parent),
+            policy,
+            class_loader,
+        ));
+        id
+    }
+
+    /// Returns the identifiers of all the class loaders in the tree, parents before children.
+    pub fn loaders(&self) -> impl Iterator<Item = LoaderId> {
+        (0..self.loaders.len()).map(LoaderId)
+    }
+
+    /// Returns the name of a class loader.
+    #[must_use]
+    pub fn name(&self, loader: LoaderId) -> Option<&str> {
+        self.loaders.get(loader.0).map(|it| it.name.as_str())
+    }
+
+    /// Returns the parent of a class loader, or `None` for the bootstrap class loader.
+    #[must_use]
+    pub fn parent(&self, loader: LoaderId) -> Option<LoaderId> {
+        self.loaders.get(loader.0).and_then(|it| it.parent)
+    }
+
+    /// Returns the delegation policy of a class loader.
+    #[must_use]
+    pub fn policy(&self, loader: LoaderId) -> Option<DelegationPolicy> {
+        self.loaders.get(loader.0).map(|it| it.policy)
+    }
+
+    /// Returns the class loader with its own class path.
+    #[must_use]
+    pub fn class_loader(&self, loader: LoaderId) -> Option<&ClassLoader<P>> {
+        self.loaders.get(loader.0).map(|it| &it.class_loader)
+    }
+
+    /// Loads a class with `loader` as the initiating loader, following the delegation policies
+    /// along the path to the bootstrap class loader.
+    /// A class already loaded by `loader` is returned without delegating again.
+    ///
+    /// # Errors
+    /// - [`Error::NotFound`] if the class cannot be found or `loader` does not belong to this tree.
+    /// - [`Error::Other`] with [`ProhibitedPackage`] if the class is in the `java` package or its
+    ///   subpackages and would be defined by a class loader other than the bootstrap class loader.
+    /// - See [`Error`] for other errors.
+    pub fn load_class(&self, loader: LoaderId, binary_name: &str) -> Result<LoadedClass<'_>, Error>
+    where
+        P: ClassPath,
+    {
+        let node = self.loaders.get(loader.0).ok_or(Error::NotFound)?;
+        let initiated = node
+            .initiated
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(binary_name)
+            .copied();
+        let (defining_loader, class) = if let Some(defining_loader) = initiated {
+            self.loaders[defining_loader.0].define_class(defining_loader, binary_name)?
+        } else {
+            let found = self.delegate(loader, node, binary_name)?;
+            node.initiated
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(binary_name.to_owned(), found.0);
+            found
+        };
+        Ok(LoadedClass {
+            defining_loader,
+            initiating_loader: loader,
+            class,
+        })
+    }
+
+    /// Finds the loader that defines the class when it is loaded by `loader`.
+    ///
+    /// # Errors
+    /// See [`ClassLoaderTree::load_class`].
+    pub fn defining_loader(&self, loader: LoaderId, binary_name: &str) -> Result<LoaderId, Error>
+    where
+        P: ClassPath,
+    {
+        self.load_class(loader, binary_name)
+            .map(|it| it.defining_loader)
+    }
+
+    /// Returns the classes loaded so far with `loader` as an initiating loader, sorted by their
+    /// names.
+    #[must_use]
+    pub fn initiated_classes(&self, loader: LoaderId) -> Vec<ClassIdentity> {
+        let Some(node) = self.loaders.get(loader.0) else {
+            return Vec::new();
+        };
+        let mut classes: Vec<_> = node
+            .initiated
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(binary_name, defining_loader)| ClassIdentity {
+                defining_loader: *defining_loader,
+                class_ref: ClassRef::new(binary_name),
+            })
+            .collect();
+        classes.sort_by(|lhs, rhs| lhs.class_ref.cmp(&rhs.class_ref));
+        classes
+    }
+
+    fn delegate<'a>(
+        &'a self,
+        loader: LoaderId,
+        node: &'a LoaderNode<P>,
+        binary_name: &str,
+    ) -> Result<(LoaderId, &'a Class), Error>
+    where
+        P: ClassPath,
+    {
+        let parent_first =
+            node.policy == DelegationPolicy::ParentFirst || is_prohibited(binary_name);
+        if parent_first {
+            self.load_from_parent(node, binary_name)
+                .or_else(|err| match err {
+                    Error::NotFound => node.define_class(loader, binary_name),
+                    err => Err(err),
+                })
+        } else {
+            node.define_class(loader, binary_name)
+                .or_else(|err| match err {
+                    Error::NotFound => self.load_from_parent(node, binary_name),
+                    err => Err(err),
+                })
+        }
+    }
+
+    fn load_from_parent(
+        &self,
+        node: &LoaderNode<P>,
+        binary_name: &str,
+    ) -> Result<(LoaderId, &Class), Error>
+    where
+        P: ClassPath,
+    {
+        match node.parent {
+            Some(parent) => self
+                .load_class(parent, binary_name)
+                .map(|it| (it.defining_loader, it.class)),
+            None => Err(Error::NotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{jvm::class_loader::class_paths::InMemoryClassPath, tests::class_path_with};
+
+    use super::*;
+
+    fn class_loader(classes: &[(&str, &str)]) -> ClassLoader<InMemoryClassPath> {
+        ClassLoader::new([class_path_with(classes)])
+    }
+
+    fn tree() -> (
+        ClassLoaderTree<InMemoryClassPath>,
+        LoaderId,
+        LoaderId,
+        LoaderId,
+    ) {
+        let mut tree = ClassLoaderTree::new(class_loader(&[
+            ("java/lang/Object", "java/lang/Object"),
+            ("org/mokapot/Shared", "java/lang/Object"),
+        ]));
+        let app = tree.add_loader(
+            "app",
+            LoaderId::BOOTSTRAP,
+            DelegationPolicy::ParentFirst,
+            class_loader(&[("org/mokapot/Shared", "java/lang/Number")]),
+        );
+        let web_a = tree.add_loader(
+            "web-a",
+            app,
+            DelegationPolicy::ChildFirst,
+            class_loader(&[
+                ("org/mokapot/Shared", "java/lang/String"),
+                ("org/mokapot/Plugin", "java/lang/Object"),
+                ("java/lang/Object", "java/lang/Object"),
+                ("java/lang/Forged", "java/lang/Object"),
+            ]),
+        );
+        let web_b = tree.add_loader(
+            "web-b",
+            app,
+            DelegationPolicy::ChildFirst,
+            class_loader(&[("org/mokapot/Plugin", "java/lang/Object")]),
+        );
+        (tree, web_a, web_b, app)
+    }
+
+    #[test]
+    fn parent_first() {
+        let (tree, _, _, app) = tree();
+        let loaded = tree.load_class(app, "org/mokapot/Shared").unwrap();
+        assert_eq!(loaded.defining_loader, LoaderId::BOOTSTRAP);
+        assert_eq!(
+            loaded.class.super_class,
+            Some(ClassRef::new("java/lang/Object"))
+        );
+    }
+
+    #[test]
+    fn child_first() {
+        let (tree, web_a, web_b, _) = tree();
+        let loaded = tree.load_class(web_a, "org/mokapot/Shared").unwrap();
+        assert_eq!(loaded.defining_loader, web_a);
+        assert_eq!(
+            loaded.class.super_class,
+            Some(ClassRef::new("java/lang/String"))
+        );
+        let loaded = tree.load_class(web_b, "org/mokapot/Shared").unwrap();
+        assert_eq!(loaded.defining_loader, LoaderId::BOOTSTRAP);
+    }
+
+    #[test]
+    fn java_classes_always_parent_first() {
+        let (tree, web_a, _, _) = tree();
+        assert_eq!(
+            tree.defining_loader(web_a, "java/lang/Object").unwrap(),
+            LoaderId::BOOTSTRAP
+        );
+    }
+
+    #[test]
+    fn prohibited_package() {
+        let (tree, web_a, _, _) = tree();
+        let err = tree.load_class(web_a, "java/lang/Forged").unwrap_err();
+        let Error::Other(err) = err else {
+            panic!("Unexpected error: {err}");
+        };
+        let err = err.downcast::<ProhibitedPackage>().unwrap();
+        assert_eq!(err.class_ref, ClassRef::new("java/lang/Forged"));
+        assert_eq!(err.loader, web_a);
+        assert!(matches!(
+            tree.load_class(web_a, "java/lang/Missing"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn cache_per_defining_loader() {
+        let (tree, web_a, web_b, app) = tree();
+        let first = tree.load_class(web_a, "org/mokapot/Plugin").unwrap();
+        let second = tree.load_class(web_a, "org/mokapot/Plugin").unwrap();
+        assert!(std::ptr::eq(first.class, second.class));
+        let from_a = tree.load_class(web_a, "java/lang/Object").unwrap();
+        let from_b = tree.load_class(web_b, "java/lang/Object").unwrap();
+        assert!(std::ptr::eq(from_a.class, from_b.class));
+        assert_eq!(from_b.initiating_loader, web_b);
+
+        let shared = ClassIdentity {
+            defining_loader: LoaderId::BOOTSTRAP,
+            class_ref: ClassRef::new("java/lang/Object"),
+        };
+        // The loaders delegated to are initiating loaders as well.
+        for loader in [web_b, app, LoaderId::BOOTSTRAP] {
+            assert_eq!(
+                tree.initiated_classes(loader),
+                std::slice::from_ref(&shared)
+            );
+        }
+        assert_eq!(tree.initiated_classes(web_a).len(), 2);
+    }
+
+    #[test]
+    fn identity_per_defining_loader() {
+        let (tree, web_a, web_b, _) = tree();
+        let plugin_a = tree.load_class(web_a, "org/mokapot/Plugin").unwrap();
+        let plugin_b = tree.load_class(web_b, "org/mokapot/Plugin").unwrap();
+        assert_eq!(plugin_a.class.binary_name, plugin_b.class.binary_name);
+        assert_ne!(plugin_a.identity(), plugin_b.identity());
+    }
+
+    #[test]
+    fn not_visible_from_parent() {
+        let (tree, _, _, app) = tree();
+        assert!(matches!(
+            tree.load_class(app, "org/mokapot/Plugin"),
+            Err(Error::NotFound)
+        ));
+        assert!(matches!(
+            tree.load_class(LoaderId(42), "java/lang/Object"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn tree_structure() {
+        let (tree, web_a, _, app) = tree();
+        assert_eq!(tree.loaders().count(), 4);
+        assert_eq!(tree.parent(web_a), Some(app));
+        assert_eq!(tree.parent(LoaderId::BOOTSTRAP), None);
+        assert_eq!(tree.name(web_a), Some("web-a"));
+        assert_eq!(tree.policy(web_a), Some(DelegationPolicy::ChildFirst));
+    }
+}
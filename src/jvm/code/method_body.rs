@@ -407,3 +407,32 @@ pub enum StackMapFrame {
         stack: Vec<VerificationType>,
     },
 }
+
+impl StackMapFrame {
+    /// Returns the offset delta of the frame.
+    #[must_use]
+    pub const fn offset_delta(&self) -> u16 {
+        match self {
+            Self::SameFrame { offset_delta }
+            | Self::SameLocals1StackItemFrame { offset_delta, .. }
+            | Self::ChopFrame { offset_delta, .. }
+            | Self::AppendFrame { offset_delta, .. }
+            | Self::FullFrame { offset_delta, .. } => *offset_delta,
+        }
+    }
+
+    /// Pairs the frames in a stack map table with the offsets where they apply.
+    /// The offset of a frame is the offset delta plus one after the offset of the previous
+    /// frame. The frames from the first one whose offset exceeds the range of a
+    /// [`ProgramCounter`] are dropped.
+    pub fn with_offsets(frames: &[Self]) -> impl Iterator<Item = (ProgramCounter, &Self)> {
+        frames.iter().scan(None, |offset: &mut Option<u16>, frame| {
+            let current = match *offset {
+                None => frame.offset_delta(),
+                Some(previous) => previous.checked_add(frame.offset_delta())?.checked_add(1)?,
+            };
+            *offset = Some(current);
+            Some((current.into(), frame))
+        })
+    }
+}
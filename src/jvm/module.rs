@@ -3,7 +3,15 @@ use bitflags::bitflags;
 
 use crate::macros::see_jvm_spec;
 
-use super::references::{ClassRef, ModuleRef, PackageRef};
+use super::{
+    references::{ClassRef, ModuleRef, PackageRef},
+    Module,
+};
+
+impl Module {
+    /// The binary name of the class declaring a module.
+    pub const INFO_CLASS_NAME: &'static str = "module-info";
+}
 
 /// A service provided by a module.
 #[doc = see_jvm_spec!(4, 7, 25)]
@@ -289,7 +289,7 @@ impl Instruction {
             } => {
                 let targets = jump_offsets
                     .into_iter()
-                    .map(|offset| (pc + offset))
+                    .map(|offset| pc + offset)
                     .try_collect()?;
                 Self::TableSwitch {
                     default: (pc + default)?,
@@ -310,7 +310,7 @@ impl RawInstruction {
             0x69 => LMul,
             0x75 => LNeg,
             0xab => {
-                while reader.position() % 4 != 0 {
+                while !reader.position().is_multiple_of(4) {
                     let _padding_byte: u8 = reader.read_value()?;
                 }
                 let default = reader.read_value()?;
@@ -328,7 +328,7 @@ impl RawInstruction {
                 }
             }
             0xaa => {
-                while reader.position() % 4 != 0 {
+                while !reader.position().is_multiple_of(4) {
                     let _padding_byte: u8 = reader.read_value()?;
                 }
                 let default = reader.read_value()?;
@@ -219,10 +219,10 @@ impl Entry {
         let tag: u8 = reader.read_value()?;
         match tag {
             1 => Self::parse_utf8(reader),
-            3 => reader.read_value().map(Self::Integer).map_err(Into::into),
-            4 => reader.read_value().map(Self::Float).map_err(Into::into),
-            5 => reader.read_value().map(Self::Long).map_err(Into::into),
-            6 => reader.read_value().map(Self::Double).map_err(Into::into),
+            3 => reader.read_value().map(Self::Integer),
+            4 => reader.read_value().map(Self::Float),
+            5 => reader.read_value().map(Self::Long),
+            6 => reader.read_value().map(Self::Double),
             7 => Ok(Self::Class {
                 name_index: reader.read_value()?,
             }),
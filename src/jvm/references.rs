@@ -22,6 +22,19 @@ impl ClassRef {
             binary_name: binary_name.into(),
         }
     }
+
+    /// Returns the binary name of the package containing the class, which is empty for the
+    /// unnamed package.
+    #[must_use]
+    pub fn package(&self) -> &str {
+        package_of(&self.binary_name)
+    }
+}
+
+/// Returns the binary name of the package containing the class or resource with the given
+/// name (e.g., `org/mokapot/Test` or `org/mokapot/test.properties`).
+pub(crate) fn package_of(name: &str) -> &str {
+    name.rsplit_once('/').map_or("", |(package, _)| package)
 }
 
 /// A reference to a [`Field`](crate::jvm::Field).
@@ -124,4 +137,10 @@ pub(crate) mod tests {
             assert!(method.is_static_initializer_block());
         }
     }
+
+    #[test]
+    fn package() {
+        assert_eq!(ClassRef::new("org/mokapot/Test").package(), "org/mokapot");
+        assert_eq!(ClassRef::new("Test").package(), "");
+    }
 }
@@ -1,10 +1,19 @@
+//! Shared utilities for unit tests.
 use proptest::prelude::*;
 
 use crate::{
-    jvm::{class, references::ClassRef, Class},
+    analysis::ResolutionContext,
+    jvm::{
+        self, class,
+        class_loader::class_paths::InMemoryClassPath,
+        field, method,
+        references::{ClassRef, MethodRef},
+        Class, Field, Method,
+    },
     types::field_type::{FieldType, PrimitiveType},
 };
 
+/// Creates the bytes of an empty class file with the given version.
 #[rustfmt::skip]
 #[must_use]
 pub const fn empty_class_with_version(major: u16, minor: u16) -> [u8;40] {
@@ -29,6 +38,298 @@ pub const fn empty_class_with_version(major: u16, minor: u16) -> [u8;40] {
     ]
 }
 
+/// Creates the bytes of an empty class file with the given binary name and super class.
+///
+/// # Panics
+/// Panics if any of the names is longer than [`u16::MAX`] bytes.
+#[must_use]
+pub fn empty_class_named(binary_name: &str, super_class: &str) -> Vec<u8> {
+    let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x34];
+    // Constant pool count 4+1
+    bytes.extend_from_slice(&[0x00, 0x05]);
+    for (name_index, name) in [(2u16, binary_name), (4, super_class)] {
+        bytes.push(0x07);
+        bytes.extend_from_slice(&name_index.to_be_bytes());
+        bytes.push(0x01);
+        let len = u16::try_from(name.len()).expect("The name is too long");
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+    bytes.extend_from_slice(&[
+        0x00, 0x21, // Access flags: public super
+        0x00, 0x01, // This class index
+        0x00, 0x03, // Super class index
+        0x00, 0x00, // Interfaces count
+        0x00, 0x00, // Fields count
+        0x00, 0x00, // Methods count
+        0x00, 0x00, // Attributes count
+    ]);
+    bytes
+}
+
+/// Creates the bytes of a `module-info` class declaring a module with the given exports, where
+/// each export lists the modules it is qualified to.
+///
+/// # Panics
+/// Panics if any of the names is longer than [`u16::MAX`] bytes.
+#[must_use]
+pub fn module_info_named(name: &str, exports: &[(&str, &[&str])]) -> Vec<u8> {
+    #[derive(Default)]
+    struct ConstantPool {
+        bytes: Vec<u8>,
+        count: u16,
+    }
+
+    impl ConstantPool {
+        fn utf8(&mut self, value: &str) -> u16 {
+            self.bytes.push(0x01);
+            let len = u16::try_from(value.len()).expect("The name is too long");
+            self.bytes.extend_from_slice(&len.to_be_bytes());
+            self.bytes.extend_from_slice(value.as_bytes());
+            self.count += 1;
+            self.count
+        }
+
+        /// Adds an entry of the given tag referring to a `CONSTANT_Utf8` entry of the name.
+        fn named(&mut self, tag: u8, value: &str) -> u16 {
+            let name_index = self.utf8(value);
+            self.bytes.push(tag);
+            self.bytes.extend_from_slice(&name_index.to_be_bytes());
+            self.count += 1;
+            self.count
+        }
+    }
+
+    let mut pool = ConstantPool::default();
+    let this_class = pool.named(0x07, "module-info");
+    let attribute_name = pool.utf8("Module");
+    let module_name = pool.named(0x13, name);
+    let mut attribute = Vec::new();
+    attribute.extend_from_slice(&module_name.to_be_bytes());
+    attribute.extend_from_slice(&[
+        0x00, 0x00, // Module flags
+        0x00, 0x00, // Module version index
+        0x00, 0x00, // Requires count
+    ]);
+    let exports_count = u16::try_from(exports.len()).expect("Too many exports");
+    attribute.extend_from_slice(&exports_count.to_be_bytes());
+    for (package, to) in exports {
+        attribute.extend_from_slice(&pool.named(0x14, package).to_be_bytes());
+        attribute.extend_from_slice(&[0x00, 0x00]); // Exports flags
+        let to_count = u16::try_from(to.len()).expect("Too many modules");
+        attribute.extend_from_slice(&to_count.to_be_bytes());
+        for module in *to {
+            attribute.extend_from_slice(&pool.named(0x13, module).to_be_bytes());
+        }
+    }
+    attribute.extend_from_slice(&[
+        0x00, 0x00, // Opens count
+        0x00, 0x00, // Uses count
+        0x00, 0x00, // Provides count
+    ]);
+
+    let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x35];
+    bytes.extend_from_slice(&(pool.count + 1).to_be_bytes());
+    bytes.extend_from_slice(&pool.bytes);
+    bytes.extend_from_slice(&[0x80, 0x00]); // Access flags: module
+    bytes.extend_from_slice(&this_class.to_be_bytes());
+    bytes.extend_from_slice(&[
+        0x00, 0x00, // Super class index
+        0x00, 0x00, // Interfaces count
+        0x00, 0x00, // Fields count
+        0x00, 0x00, // Methods count
+        0x00, 0x01, // Attributes count
+    ]);
+    bytes.extend_from_slice(&attribute_name.to_be_bytes());
+    let attribute_len = u32::try_from(attribute.len()).expect("The attribute is too long");
+    bytes.extend_from_slice(&attribute_len.to_be_bytes());
+    bytes.extend_from_slice(&attribute);
+    bytes
+}
+
+/// Creates an in-memory class path with an empty class for each pair of binary name and super
+/// class name.
+#[must_use]
+pub fn class_path_with(classes: &[(&str, &str)]) -> InMemoryClassPath {
+    classes
+        .iter()
+        .map(|(name, super_class)| ((*name).to_owned(), empty_class_named(name, super_class)))
+        .collect()
+}
+
+/// Creates the bytes of a JAR file containing the given entries.
+///
+/// # Panics
+/// Panics if the entries cannot be written.
+#[cfg(feature = "jar")]
+#[must_use]
+pub fn jar_with_entries(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, content) in entries {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// Creates a public class with the given superclass and interfaces.
+#[must_use]
+pub fn class_with_supertypes(
+    binary_name: &str,
+    super_class: Option<&str>,
+    interfaces: &[&str],
+) -> Class {
+    Class {
+        access_flags: class::AccessFlags::PUBLIC | class::AccessFlags::SUPER,
+        binary_name: binary_name.to_owned(),
+        super_class: super_class.map(ClassRef::new),
+        interfaces: interfaces.iter().copied().map(ClassRef::new).collect(),
+        ..Default::default()
+    }
+}
+
+/// Creates a public interface with the given superinterfaces.
+#[must_use]
+pub fn interface_named(binary_name: &str, super_interfaces: &[&str]) -> Class {
+    Class {
+        access_flags: class::AccessFlags::PUBLIC
+            | class::AccessFlags::INTERFACE
+            | class::AccessFlags::ABSTRACT,
+        ..class_with_supertypes(binary_name, Some("java/lang/Object"), super_interfaces)
+    }
+}
+
+/// Creates a resolution context with the given classes as the library classes.
+#[must_use]
+pub fn context_with(classes: impl IntoIterator<Item = Class>) -> ResolutionContext {
+    let library_classes = classes.into_iter().map(|it| (it.as_ref(), it)).collect();
+    ResolutionContext::from_classes(std::collections::HashMap::new(), library_classes)
+}
+
+/// Creates a method without a body.
+///
+/// # Panics
+/// Panics if the descriptor is invalid.
+#[must_use]
+pub fn method_named(
+    owner: &str,
+    name: &str,
+    descriptor: &str,
+    access_flags: method::AccessFlags,
+) -> Method {
+    Method {
+        access_flags,
+        name: name.to_owned(),
+        descriptor: descriptor.parse().unwrap(),
+        owner: ClassRef::new(owner),
+        body: None,
+        exceptions: Vec::new(),
+        runtime_visible_annotations: Vec::new(),
+        runtime_invisible_annotations: Vec::new(),
+        runtime_visible_type_annotations: Vec::new(),
+        runtime_invisible_type_annotations: Vec::new(),
+        runtime_visible_parameter_annotations: Vec::new(),
+        runtime_invisible_parameter_annotations: Vec::new(),
+        annotation_default: None,
+        parameters: Vec::new(),
+        is_synthetic: false,
+        is_deprecated: false,
+        signature: None,
+        free_attributes: Vec::new(),
+    }
+}
+
+/// Replaces the methods of a class with methods without bodies.
+///
+/// # Panics
+/// Panics if any descriptor is invalid.
+#[must_use]
+pub fn with_methods(mut class: Class, methods: &[(&str, &str, method::AccessFlags)]) -> Class {
+    class.methods = methods
+        .iter()
+        .map(|(name, descriptor, flags)| method_named(&class.binary_name, name, descriptor, *flags))
+        .collect();
+    class
+}
+
+/// Creates a reference to a method.
+///
+/// # Panics
+/// Panics if the descriptor is invalid.
+#[must_use]
+pub fn method_ref(owner: &str, name: &str, descriptor: &str) -> MethodRef {
+    MethodRef {
+        owner: ClassRef::new(owner),
+        name: name.to_owned(),
+        descriptor: descriptor.parse().unwrap(),
+    }
+}
+
+/// Creates a field of type `int`.
+#[must_use]
+pub fn field_named(owner: &str, name: &str, access_flags: field::AccessFlags) -> Field {
+    Field {
+        access_flags,
+        name: name.to_owned(),
+        owner: ClassRef::new(owner),
+        field_type: FieldType::Base(PrimitiveType::Int),
+        constant_value: None,
+        is_synthetic: false,
+        is_deperecated: false,
+        signature: None,
+        runtime_visible_annotations: Vec::new(),
+        runtime_invisible_annotations: Vec::new(),
+        runtime_visible_type_annotations: Vec::new(),
+        runtime_invisible_type_annotations: Vec::new(),
+        free_attributes: Vec::new(),
+    }
+}
+
+/// Creates a `module-info` class declaring a module with the given packages and exports, where
+/// each export lists the modules it is qualified to.
+#[must_use]
+pub fn module_info(name: &str, packages: &[&str], exports: &[(&str, &[&str])]) -> Class {
+    Class {
+        binary_name: "module-info".to_owned(),
+        access_flags: class::AccessFlags::MODULE,
+        module: Some(jvm::Module {
+            name: name.to_owned(),
+            flags: jvm::module::Flags::empty(),
+            version: None,
+            requires: Vec::new(),
+            exports: exports
+                .iter()
+                .map(|(package, to)| jvm::module::Export {
+                    package: jvm::references::PackageRef {
+                        binary_name: (*package).to_owned(),
+                    },
+                    flags: jvm::module::ExportFlags::empty(),
+                    to: to
+                        .iter()
+                        .map(|it| jvm::references::ModuleRef {
+                            name: (*it).to_owned(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            opens: Vec::new(),
+            uses: Vec::new(),
+            provides: Vec::new(),
+        }),
+        module_packages: packages
+            .iter()
+            .map(|it| jvm::references::PackageRef {
+                binary_name: (*it).to_owned(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
 impl Default for Class {
     fn default() -> Self {
         Self {
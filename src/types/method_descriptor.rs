@@ -95,6 +95,21 @@ impl FromStr for ReturnType {
     }
 }
 
+impl MethodDescriptor {
+    /// Returns the JVM descriptor for this method descriptor.
+    #[must_use]
+    pub fn descriptor(&self) -> String {
+        format!(
+            "({}){}",
+            self.parameters_types
+                .iter()
+                .map(FieldType::descriptor)
+                .join(""),
+            self.return_type.descriptor()
+        )
+    }
+}
+
 impl ReturnType {
     /// Returns the descriptor for return type.
     #[must_use]
@@ -136,6 +151,7 @@ mod test {
                 MethodDescriptor::from_str(&descriptor).expect("Failed to parse method descriptor");
             assert_eq!(parsed.return_type, ret);
             assert_eq!(parsed.parameters_types, params);
+            assert_eq!(parsed.descriptor(), descriptor);
         }
 
         #[test]
@@ -1,4 +1,11 @@
-use std::{borrow::Borrow, collections::HashMap, hash::Hash, mem::transmute, sync::RwLock};
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    mem::transmute,
+    num::NonZeroUsize,
+    sync::RwLock,
+};
 
 #[derive(Debug)]
 pub(crate) struct Cache<K, V> {
@@ -60,6 +67,131 @@ impl<K, V> Cache<K, V> {
     }
 }
 
+/// A cache that evicts the least recently used items when the total weight of the items exceeds
+/// its capacity.
+#[derive(Debug)]
+pub(crate) struct LruCache<K, V> {
+    capacity: NonZeroUsize,
+    weight: usize,
+    items: HashMap<K, LruItem<V>>,
+    recency: BTreeMap<u64, K>,
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct LruItem<V> {
+    value: V,
+    weight: usize,
+    last_used: u64,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            capacity,
+            weight: 0,
+            items: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> NonZeroUsize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns the total weight of the items.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    /// Gets an item and marks it as the most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        Q: ?Sized + Eq + Hash,
+        K: Borrow<Q>,
+    {
+        let item = self.items.get_mut(key)?;
+        self.clock += 1;
+        let owned_key = self
+            .recency
+            .remove(&item.last_used)
+            .expect("Every item should be tracked by recency");
+        self.recency.insert(self.clock, owned_key);
+        item.last_used = self.clock;
+        Some(&item.value)
+    }
+
+    /// Puts an item as the most recently used, and returns the items evicted to make room.
+    /// An item heavier than the capacity is not put, and is returned as evicted.
+    pub fn put(&mut self, key: K, value: V, weight: usize) -> Vec<(K, V)> {
+        self.remove(&key);
+        if weight > self.capacity.get() {
+            return vec![(key, value)];
+        }
+        let mut evicted = Vec::new();
+        while self.weight + weight > self.capacity.get() {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(item) = self.items.remove(&oldest) {
+                self.weight -= item.weight;
+                evicted.push((oldest, item.value));
+            }
+        }
+        self.clock += 1;
+        self.recency.insert(self.clock, key.clone());
+        self.weight += weight;
+        self.items.insert(
+            key,
+            LruItem {
+                value,
+                weight,
+                last_used: self.clock,
+            },
+        );
+        evicted
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        Q: ?Sized + Eq + Hash,
+        K: Borrow<Q>,
+    {
+        let item = self.items.remove(key)?;
+        self.recency.remove(&item.last_used);
+        self.weight -= item.weight;
+        Some(item.value)
+    }
+
+    /// Removes the items for which `predicate` returns `false`.
+    pub fn retain(&mut self, mut predicate: impl FnMut(&K, &V) -> bool) {
+        let recency = &mut self.recency;
+        let weight = &mut self.weight;
+        self.items.retain(|key, item| {
+            let keep = predicate(key, &item.value);
+            if !keep {
+                recency.remove(&item.last_used);
+                *weight -= item.weight;
+            }
+            keep
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.recency.clear();
+        self.weight = 0;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::{self, AtomicUsize};
@@ -83,4 +215,35 @@ mod tests {
             assert_eq!(1, counter.load(atomic::Ordering::Relaxed));
         }
     }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut cache = LruCache::new(NonZeroUsize::new(2).unwrap());
+        assert!(cache.put("a", 1, 1).is_empty());
+        assert!(cache.put("b", 2, 1).is_empty());
+        assert_eq!(cache.get("a"), Some(&1));
+        assert_eq!(cache.put("c", 3, 1), [("b", 2)]);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.put("a", 4, 1).is_empty());
+        assert_eq!(cache.put("d", 5, 1), [("c", 3)]);
+        cache.retain(|_, value| *value > 4);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.remove("d"), Some(5));
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn lru_bounds_weight() {
+        let mut cache = LruCache::new(NonZeroUsize::new(10).unwrap());
+        assert!(cache.put("a", 1, 4).is_empty());
+        assert!(cache.put("b", 2, 4).is_empty());
+        assert_eq!(cache.put("c", 3, 6), [("a", 1)]);
+        assert_eq!(cache.weight(), 10);
+        assert_eq!(cache.put("d", 4, 11), [("d", 4)]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.put("e", 5, 9), [("b", 2), ("c", 3)]);
+        cache.retain(|_, _| false);
+        assert_eq!(cache.weight(), 0);
+    }
 }
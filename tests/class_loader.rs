@@ -119,6 +119,30 @@ fn jar_class_path_not_jar() {
     ));
 }
 
+#[test]
+#[cfg(feature = "jimage")]
+fn jrt_image_class_path() {
+    use mokapot::jvm::class_loader::class_paths::JrtImageClassPath;
+
+    let Ok(java_home) = std::env::var("JAVA_HOME") else {
+        eprintln!("Skipping `jrt_image_class_path`: `JAVA_HOME` is not set");
+        return;
+    };
+    let jrt_cp = JrtImageClassPath::from_java_home(java_home).unwrap();
+    assert_eq!(jrt_cp.module_of("java/lang"), Some("java.base"));
+    assert!(jrt_cp.modules().contains("java.sql"));
+    assert!(jrt_cp.find_module_info("java.base").is_ok());
+
+    let class_loader = ClassLoader::new([jrt_cp]);
+    let class = class_loader.load_class("java/lang/Object").unwrap();
+    assert_eq!(class.binary_name, "java/lang/Object");
+    assert!(class_loader.load_class("java/sql/Driver").is_ok());
+    assert!(matches!(
+        class_loader.load_class("java/lang/Object3"),
+        Err(Error::NotFound)
+    ));
+}
+
 fn _class_path_object_safety(_b: Box<dyn ClassPath>) {
     // For compilation checking only.
 }